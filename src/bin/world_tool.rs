@@ -0,0 +1,293 @@
+use std::{collections::BTreeMap, fmt::Display, fs, path::PathBuf};
+
+use itertools::iproduct;
+use nalgebra_glm as glm;
+
+use manufactory::{
+    bytes::{AsFromBytes, BytesCoder, DecodeError}, definitions::DefinitionError, player::player::SPAWN_POSITION,
+    recipes::content::load_content, save_load::{EncodedChunk, MetadataSave, PlayerSave, Region, RegionCoords, WorldMetadata},
+    voxels::{block::blocks::BLOCKS, chunk::{Chunk, CompressChunk, CHUNK_SIZE}}, world::chunk_coords::ChunkCoords,
+    session::new_player, settings::Settings,
+};
+
+const USAGE: &str = "usage: world_tool <command> <world directory> [arguments]
+
+commands:
+  regions                 regions and the number of their chunks
+  chunks [<rx> <rz>]      chunks with the time they were saved
+  stats [<x> <y> <z>]     block ids and voxel data kinds of the chunks
+  dump <x> <y> <z>        machine states of the chunk
+  validate                decodes every chunk and the player
+  delete <x> <y> <z>      deletes the chunk, the game generates it again
+  regenerate <x> <y> <z>  replaces the chunk with a generated one
+  reset-player            moves the player to the spawn, a damaged player starts again";
+
+/// Coordinates and data of a chunk in a region file
+type StoredChunk = (ChunkCoords, Result<Box<[u8]>, DecodeError>);
+
+
+#[derive(Debug)]
+enum ToolError {
+    Usage,
+    InvalidArgument(String),
+    NotSaved(ChunkCoords),
+    Io(std::io::Error),
+    Definition(DefinitionError),
+    Decode(DecodeError),
+    /// The number of damaged chunks and player files
+    Invalid(usize),
+}
+
+impl Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Usage => write!(f, "{}", USAGE),
+            Self::InvalidArgument(argument) => write!(f, "invalid argument \"{}\"", argument),
+            Self::NotSaved(coords) => write!(f, "chunk {} {} {} is not saved", coords.0, coords.1, coords.2),
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Definition(e) => write!(f, "{}", e),
+            Self::Decode(e) => write!(f, "{}", e),
+            Self::Invalid(count) => write!(f, "{} damaged entries", count),
+        }
+    }
+}
+
+impl From<std::io::Error> for ToolError {
+    fn from(value: std::io::Error) -> Self {Self::Io(value)}
+}
+
+impl From<DefinitionError> for ToolError {
+    fn from(value: DefinitionError) -> Self {Self::Definition(value)}
+}
+
+impl From<DecodeError> for ToolError {
+    fn from(value: DecodeError) -> Self {Self::Decode(value)}
+}
+
+
+/// Inspects and repairs a world that is not opened in the game
+fn main() {
+    // Load the block registry before anything depends on it
+    BLOCKS();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<&str>>();
+    let result = match args.as_slice() {
+        [command, path, args @ ..] => WorldTool::open(PathBuf::from(path)).and_then(|tool| tool.execute(command, args)),
+        _ => Err(ToolError::Usage),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+
+struct WorldTool {
+    path: PathBuf,
+    metadata: WorldMetadata,
+}
+
+impl WorldTool {
+    /// Loads the content with the ids of the world, the metadata is not saved
+    fn open(path: PathBuf) -> Result<Self, ToolError> {
+        if !path.join("world.txt").exists() {
+            return Err(ToolError::InvalidArgument(path.display().to_string()));
+        }
+        let mut metadata = MetadataSave::new(path.clone()).load_metadata()?;
+        load_content(&mut metadata)?;
+        Ok(Self { path, metadata })
+    }
+
+    fn execute(&self, command: &str, args: &[&str]) -> Result<(), ToolError> {
+        match (command, args) {
+            ("regions", []) => self.regions(),
+            ("chunks", []) => self.chunks(None),
+            ("chunks", [x, z]) => self.chunks(Some(RegionCoords::from_filename(&format!("{}_{}.bin", x, z))
+                .ok_or_else(|| ToolError::InvalidArgument(format!("{} {}", x, z)))?)),
+            ("stats", []) => self.stats(None),
+            ("stats", coords) => self.stats(Some(parse_coords(coords)?)),
+            ("dump", coords) => self.dump(parse_coords(coords)?),
+            ("validate", []) => self.validate(),
+            ("delete", coords) => self.delete(parse_coords(coords)?),
+            ("regenerate", coords) => self.regenerate(parse_coords(coords)?),
+            ("reset-player", []) => self.reset_player(),
+            _ => Err(ToolError::Usage),
+        }
+    }
+
+    /// Region files sorted by their coordinates
+    fn region_coords(&self) -> Result<Vec<RegionCoords>, ToolError> {
+        let mut coords = fs::read_dir(self.path.join("regions/"))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| RegionCoords::from_filename(&entry.file_name().to_string_lossy()))
+            .collect::<Vec<RegionCoords>>();
+        coords.sort();
+        Ok(coords)
+    }
+
+    /// Reads the region file as it is, without converting or quarantining it like the game
+    fn open_region(&self, coords: RegionCoords) -> Result<Region, ToolError> {
+        Ok(Region::read(coords.path(&self.path))?)
+    }
+
+    /// Opens the region for a change, a damaged region is an error instead of being quarantined
+    fn open_region_mut(&self, coords: RegionCoords) -> Result<Region, ToolError> {
+        let path = coords.path(&self.path);
+        if !path.exists() {
+            fs::create_dir_all(self.path.join("regions/"))?;
+            return Ok(Region::new_empty(path, self.metadata.heights));
+        }
+        Ok(Region::open(&self.path, coords, self.metadata.heights)?)
+    }
+
+    /// Data of every chunk of the world
+    fn stored_chunks(&self) -> Result<Vec<StoredChunk>, ToolError> {
+        let mut chunks = vec![];
+        for coords in self.region_coords()? {
+            let region = self.open_region(coords)?;
+            for index in region.stored_chunks() {
+                let chunk = region.chunk(index).and_then(|chunk| match chunk {
+                    EncodedChunk::Some(bytes) => Ok(bytes),
+                    EncodedChunk::None => Err(DecodeError::Length { expected: CompressChunk::size(), found: 0 }),
+                });
+                chunks.push((coords.chunk_coords(index, region.heights()), chunk));
+            }
+        }
+        Ok(chunks)
+    }
+
+    fn load_chunk(&self, coords: ChunkCoords) -> Result<Chunk, ToolError> {
+        match self.open_region(coords.into())?.chunk(coords)? {
+            EncodedChunk::Some(bytes) => Ok(Chunk::decode_at(&bytes, coords)?),
+            EncodedChunk::None => Err(ToolError::NotSaved(coords)),
+        }
+    }
+
+
+    fn regions(&self) -> Result<(), ToolError> {
+        for coords in self.region_coords()? {
+            match self.open_region(coords) {
+                Ok(region) => println!("{} {} chunks", coords.filename(), region.stored_chunks().len()),
+                Err(err) => println!("{} damaged: {}", coords.filename(), err),
+            }
+        }
+        Ok(())
+    }
+
+    fn chunks(&self, region: Option<RegionCoords>) -> Result<(), ToolError> {
+        for (coords, bytes) in self.stored_chunks()? {
+            if region.is_some_and(|region| region != coords.into()) {continue};
+            match bytes.and_then(|bytes| Ok((CompressChunk::from_bytes_at(&bytes, 0)?, bytes.len()))) {
+                Ok((header, len)) => println!("{} {} {} saved at {} {} bytes", coords.0, coords.1, coords.2, header.time, len),
+                Err(err) => println!("{} {} {} damaged: {}", coords.0, coords.1, coords.2, err),
+            }
+        }
+        Ok(())
+    }
+
+    fn stats(&self, coords: Option<ChunkCoords>) -> Result<(), ToolError> {
+        let chunks = match coords {
+            Some(coords) => vec![self.load_chunk(coords)],
+            None => self.stored_chunks()?.into_iter()
+                .map(|(coords, bytes)| Ok(Chunk::decode_at(&bytes?, coords)?))
+                .collect(),
+        };
+        for chunk in chunks {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(err) => {println!("damaged: {}\n", err); continue},
+            };
+            let mut blocks = BTreeMap::<u32, usize>::new();
+            iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE)
+                .for_each(|xyz| *blocks.entry(chunk.voxel(xyz.into()).id).or_default() += 1);
+            let mut kinds = BTreeMap::<&str, usize>::new();
+            chunk.voxels_data().values().for_each(|data| *kinds.entry(data.additionally.name()).or_default() += 1);
+
+            println!("chunk {} {} {}", chunk.xyz.0, chunk.xyz.1, chunk.xyz.2);
+            blocks.iter().for_each(|(id, count)| println!("  {} {}", BLOCKS()[*id as usize].name(), count));
+            kinds.iter().for_each(|(kind, count)| println!("  {} {}", kind, count));
+            println!();
+        }
+        Ok(())
+    }
+
+    fn dump(&self, coords: ChunkCoords) -> Result<(), ToolError> {
+        let chunk = self.load_chunk(coords)?;
+        let mut voxels_data = chunk.voxels_data().values().collect::<Vec<_>>();
+        voxels_data.sort_by_key(|data| (data.global_coords.1, data.global_coords.2, data.global_coords.0));
+        for data in voxels_data {
+            println!("{:?} {}: {:#?}", data.global_coords, BLOCKS()[data.id as usize].name(), data.additionally);
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ToolError> {
+        let mut damaged = 0;
+        let mut chunks = 0;
+        for coords in self.region_coords()? {
+            let region = match self.open_region(coords) {
+                Ok(region) => region,
+                Err(err) => {println!("region {} damaged: {}", coords.filename(), err); damaged += 1; continue},
+            };
+            for index in region.stored_chunks() {
+                chunks += 1;
+                let coords = coords.chunk_coords(index, region.heights());
+                let result = region.chunk(index).and_then(|chunk| match chunk {
+                    EncodedChunk::Some(bytes) => Chunk::decode_at(&bytes, coords).map(|_| ()),
+                    EncodedChunk::None => Ok(()),
+                });
+                if let Err(err) = result {
+                    println!("chunk {} {} {} damaged: {}", coords.0, coords.1, coords.2, err);
+                    damaged += 1;
+                }
+            }
+        }
+        let player = self.path.join("player.bin");
+        if player.exists() && PlayerSave::new(self.path.clone()).load_player().is_none() {
+            println!("player damaged");
+            damaged += 1;
+        }
+        println!("{} chunks checked", chunks);
+        if damaged > 0 {return Err(ToolError::Invalid(damaged))};
+        Ok(())
+    }
+
+    fn delete(&self, coords: ChunkCoords) -> Result<(), ToolError> {
+        if !RegionCoords::from(coords).path(&self.path).exists() {return Err(ToolError::NotSaved(coords))};
+        let mut region = self.open_region_mut(coords.into())?;
+        if region.remove_chunk(coords).is_none() {
+            return Err(ToolError::NotSaved(coords));
+        }
+        region.save()?;
+        println!("Deleted {:?}", coords);
+        Ok(())
+    }
+
+    fn regenerate(&self, coords: ChunkCoords) -> Result<(), ToolError> {
+        if !self.metadata.heights.contains(coords.1) {
+            return Err(ToolError::InvalidArgument(coords.1.to_string()));
+        }
+        let mut region = self.open_region_mut(coords.into())?;
+        region.save_chunk(coords, Chunk::new(coords.0, coords.1, coords.2, self.metadata.seed).encode_bytes());
+        region.save()?;
+        println!("Regenerated {:?}", coords);
+        Ok(())
+    }
+
+    fn reset_player(&self) -> Result<(), ToolError> {
+        let save = PlayerSave::new(self.path.clone());
+        let mut player = save.load_player().unwrap_or_else(|| new_player(&Settings::default()));
+        player.set_position(glm::make_vec3(&SPAWN_POSITION));
+        save.save_player(&player);
+        println!("The player is moved to {:?}", SPAWN_POSITION);
+        Ok(())
+    }
+}
+
+
+fn parse_coords(args: &[&str]) -> Result<ChunkCoords, ToolError> {
+    let [x, y, z] = args else {return Err(ToolError::Usage)};
+    let parse = |value: &str| value.parse::<i32>().map_err(|_| ToolError::InvalidArgument(value.to_string()));
+    Ok(ChunkCoords(parse(x)?, parse(y)?, parse(z)?))
+}
+
@@ -1,3 +1,33 @@
+use std::{fmt::Display, ops::Range};
+
+/// Decoding error of saved data
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The data is shorter or longer than the expected size
+    Length {expected: usize, found: usize},
+    InvalidValue {name: &'static str, value: u64},
+    /// The data is valid but belongs somewhere else
+    Mismatch {name: &'static str, expected: String, found: String},
+    Decompression(std::io::Error),
+    /// The data could not be read from the file
+    Io(std::io::Error),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Length { expected, found } => write!(f, "expected {} bytes, found {}", expected, found),
+            Self::InvalidValue { name, value } => write!(f, "invalid {} {}", name, value),
+            Self::Mismatch { name, expected, found } => write!(f, "{} {} doesn't match {}", name, found, expected),
+            Self::Decompression(e) => write!(f, "decompression failed: {}", e),
+            Self::Io(e) => write!(f, "read failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+
 /// Only for constant size types,
 /// Only for repr(C)
 pub trait AsFromBytes: Sized + Clone {
@@ -8,17 +38,36 @@ pub trait AsFromBytes: Sized + Clone {
         unsafe { std::slice::from_raw_parts(slf.cast::<u8>(), Self::SIZE) }
     }
 
+    /// Checks the fields that are not valid for every bit pattern, like enums
     #[inline]
-    fn from_bytes(bytes: &[u8]) -> Self {
-        assert_eq!(bytes.len(), Self::SIZE);
-        let ptr = bytes.as_ptr() as *const Self;
-        unsafe {ptr.as_ref()}.unwrap().clone()
+    fn validate(_bytes: &[u8]) -> Result<(), DecodeError> {Ok(())}
+
+    #[inline]
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() != Self::SIZE {
+            return Err(DecodeError::Length { expected: Self::SIZE, found: bytes.len() });
+        }
+        Self::validate(bytes)?;
+        Ok(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+    }
+
+    /// Reads the value at the offset of a longer data
+    #[inline]
+    fn from_bytes_at(bytes: &[u8], offset: usize) -> Result<Self, DecodeError> {
+        Self::from_bytes(slice_bytes(bytes, offset..offset + Self::SIZE)?)
     }
 
     #[inline(always)]
     fn size() -> usize {Self::SIZE}
 }
 
+/// The range of the data, an error if the data is shorter
+#[inline]
+pub fn slice_bytes(bytes: &[u8], range: Range<usize>) -> Result<&[u8], DecodeError> {
+    let found = bytes.len();
+    bytes.get(range.clone()).ok_or(DecodeError::Length { expected: range.end, found })
+}
+
 #[inline]
 pub fn cast_bytes_from_slice<T: Sized>(data: &[T]) -> &[u8] {
     let slf: *const T = data.as_ptr();
@@ -27,11 +76,11 @@ pub fn cast_bytes_from_slice<T: Sized>(data: &[T]) -> &[u8] {
 }
 
 #[inline]
-pub fn cast_vec_from_bytes<T: Clone + Sized>(bytes: &[u8]) -> Vec<T> {
-    let ptr = bytes.as_ptr() as *const T;
-    let len = bytes.len() / std::mem::size_of::<T>();
-    let temp_slice = unsafe { std::slice::from_raw_parts(ptr, len) };
-    temp_slice.to_vec()
+pub fn cast_vec_from_bytes<T: AsFromBytes>(bytes: &[u8]) -> Result<Vec<T>, DecodeError> {
+    if !bytes.len().is_multiple_of(T::SIZE) {
+        return Err(DecodeError::Length { expected: bytes.len() / T::SIZE * T::SIZE, found: bytes.len() });
+    }
+    bytes.chunks_exact(T::SIZE).map(T::from_bytes).collect()
 }
 
 impl AsFromBytes for u8 {}
@@ -51,5 +100,5 @@ impl<const N: usize> AsFromBytes for [u32; N] {}
 
 pub trait BytesCoder: Sized {
     fn encode_bytes(&self) -> Box<[u8]>;
-    fn decode_bytes(bytes: &[u8]) -> Self;
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError>;
 }
\ No newline at end of file
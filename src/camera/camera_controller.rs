@@ -1,4 +1,4 @@
-use crate::input_event::{input_service::{InputService, Key}, KeypressState};
+use crate::{input_event::{input_service::InputService, actions::Action}, settings::DEFAULT_SENSITIVITY};
 use nalgebra_glm as glm;
 
 use super::{camera::Camera, frustum::Frustum};
@@ -7,17 +7,18 @@ use super::{camera::Camera, frustum::Frustum};
 pub struct CameraController {
     yaw: f32,
     pitch: f32,
+    sensitivity: f32,
     camera: Camera
 }
 
 
 impl CameraController {
     const SPEED: f32 = 14.0; //14.0 default
-    const SENSETIV: f32 = 0.3;
     pub fn new(position: glm::Vec3, fov: f32, near: f32, far: f32) -> Self {
         Self {
             yaw: 0.0,
             pitch: 0.0,
+            sensitivity: DEFAULT_SENSITIVITY,
             camera: Camera::new(position, fov, near, far)
         }
     }
@@ -29,8 +30,8 @@ impl CameraController {
 
     pub fn update_rotation(&mut self, mouse_delta_x: f32, mouse_delta_y: f32, delta_time: f32) {
         self.camera.rotation = glm::Mat4::identity();
-        self.yaw -= mouse_delta_x*Self::SENSETIV*delta_time;
-        self.pitch -= mouse_delta_y*Self::SENSETIV*delta_time;
+        self.yaw -= mouse_delta_x*self.sensitivity*delta_time;
+        self.pitch -= mouse_delta_y*self.sensitivity*delta_time;
         if self.pitch > 1.569_051 {self.pitch = 1.569_051}
         if self.pitch < -1.569_051 {self.pitch = -1.569_051}
         self.camera.rotate(self.pitch, self.yaw, 0.0);
@@ -39,27 +40,37 @@ impl CameraController {
     pub fn update(&mut self, input: &InputService, delta_time: f32, is_cursor: bool) {
         if !is_cursor {
             self.camera.rotation = glm::Mat4::identity();
-            self.yaw -= input.delta().0*Self::SENSETIV*delta_time;
-            self.pitch -= input.delta().1*Self::SENSETIV*delta_time;
+            self.yaw -= input.delta().0*self.sensitivity*delta_time;
+            self.pitch -= input.delta().1*self.sensitivity*delta_time;
             if self.pitch > 1.569_051 {self.pitch = 1.569_051}
             if self.pitch < -1.569_051 {self.pitch = -1.569_051}
             self.camera.rotate(self.pitch, self.yaw, 0.0); 
         }
 
-        if input.is_key(&Key::W, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveForward) {
             self.camera.position +=  self.camera.front * Self::SPEED * delta_time;
         }
-        if input.is_key(&Key::S, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveBack) {
             self.camera.position -=  self.camera.front * Self::SPEED * delta_time;
         }
-        if input.is_key(&Key::A, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveLeft) {
             self.camera.position -=  self.camera.right * Self::SPEED * delta_time;
         }
-        if input.is_key(&Key::D, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveRight) {
             self.camera.position +=  self.camera.right * Self::SPEED * delta_time;
         }
     }
 
+    pub fn set_projection(&mut self, fov: f32, near: f32, far: f32) {
+        self.camera.fov = fov;
+        self.camera.near = near;
+        self.camera.far = far;
+    }
+
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
     pub fn set_position(&mut self, position: glm::Vec3) {
         self.camera.position = position;
     }
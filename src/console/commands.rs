@@ -0,0 +1,355 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use itertools::iproduct;
+use nalgebra_glm as glm;
+
+use crate::{world::{World, global_coords::GlobalCoords, sun::Sun}, player::player::Player, recipes::{items::item_by_key, item::Item, storage::Storage},
+    voxels::block::blocks::{BLOCKS, MULTIBLOCK_PART_ID, block_by_name}, direction::Direction, save_load::Save};
+
+const MAX_FILL_VOLUME: usize = 32*32*32;
+
+
+/// Everything a command can change
+pub struct CommandContext<'a> {
+    pub world: &'a mut World,
+    pub player: &'a mut Player,
+    pub sun: &'a mut Sun<9>,
+    pub save: &'a Save,
+    /// The world is saved after the commands released it
+    pub is_save_requested: bool,
+}
+
+
+#[derive(Debug)]
+pub enum CommandError {
+    Unknown(String),
+    Usage(&'static str),
+    InvalidArgument(String),
+    /// Line that came while no world is open
+    NoWorld(String),
+    Other(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown(name) => write!(f, "unknown command \"{}\", try /help", name),
+            Self::Usage(usage) => write!(f, "usage: {}", usage),
+            Self::InvalidArgument(argument) => write!(f, "invalid argument \"{}\"", argument),
+            Self::NoWorld(line) => write!(f, "\"{}\" rejected, no world is open", line),
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+
+type CommandFn = fn(&[&str], &mut CommandContext) -> Result<String, CommandError>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    run: CommandFn,
+}
+
+
+#[derive(Debug)]
+pub struct Commands {
+    commands: HashMap<&'static str, Command>,
+}
+
+
+impl Commands {
+    pub fn new() -> Self {
+        let mut commands = Self { commands: HashMap::new() };
+        commands.register(Command { name: "help", usage: "/help", run: |_, _| Ok(String::new()) });
+        commands.register(Command { name: "give", usage: "/give <item> [count]", run: give });
+        commands.register(Command { name: "tp", usage: "/tp <x> <y> <z>", run: teleport });
+        commands.register(Command { name: "time", usage: "/time [set <seconds>]", run: time });
+        commands.register(Command { name: "fill", usage: "/fill <x1> <y1> <z1> <x2> <y2> <z2> <block>", run: fill });
+        commands.register(Command { name: "save", usage: "/save", run: save });
+        commands
+    }
+
+    pub fn register(&mut self, command: Command) {
+        self.commands.insert(command.name, command);
+    }
+
+    pub fn execute(&self, line: &str, context: &mut CommandContext) -> Result<String, CommandError> {
+        let line = line.trim();
+        let mut words = line.strip_prefix('/').unwrap_or(line).split_whitespace();
+        let Some(name) = words.next() else {return Ok(String::new())};
+        let args = words.collect::<Vec<&str>>();
+        if name == "help" {return Ok(self.help())};
+        let command = self.commands.get(name).ok_or_else(|| CommandError::Unknown(name.to_string()))?;
+        (command.run)(&args, context)
+    }
+
+    fn help(&self) -> String {
+        let mut usages = self.commands.values().map(|c| c.usage).collect::<Vec<&str>>();
+        usages.sort();
+        usages.join("\n")
+    }
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+fn parse<T: FromStr>(arg: &str) -> Result<T, CommandError> {
+    arg.parse().map_err(|_| CommandError::InvalidArgument(arg.to_string()))
+}
+
+
+fn give(args: &[&str], context: &mut CommandContext) -> Result<String, CommandError> {
+    let (key, count) = match args {
+        [key] => (*key, 1),
+        [key, count] => (*key, parse::<u32>(count)?),
+        _ => return Err(CommandError::Usage("/give <item> [count]")),
+    };
+    let item_type = item_by_key(key).ok_or_else(|| CommandError::InvalidArgument(key.to_string()))?;
+    let leftover = context.player.inventory().lock().unwrap().add(&Item::new(item_type.id, count), true);
+    let given = count - leftover.map_or(0, |item| item.count);
+    Ok(format!("Given {} {}", given, item_type.name))
+}
+
+
+fn teleport(args: &[&str], context: &mut CommandContext) -> Result<String, CommandError> {
+    let [x, y, z] = args else {return Err(CommandError::Usage("/tp <x> <y> <z>"))};
+    let position = glm::vec3(parse(x)?, parse(y)?, parse(z)?);
+    context.player.set_position(position);
+    Ok(format!("Teleported to {} {} {}", position.x, position.y, position.z))
+}
+
+
+fn time(args: &[&str], context: &mut CommandContext) -> Result<String, CommandError> {
+    match args {
+        [] => Ok(format!("Time {}", context.sun.time().floor())),
+        ["set", time] => {
+            context.sun.set_time(parse(time)?);
+            Ok(format!("Time set to {}", time))
+        },
+        _ => Err(CommandError::Usage("/time [set <seconds>]")),
+    }
+}
+
+
+fn fill(args: &[&str], context: &mut CommandContext) -> Result<String, CommandError> {
+    let [x1, y1, z1, x2, y2, z2, block] = args else {
+        return Err(CommandError::Usage("/fill <x1> <y1> <z1> <x2> <y2> <z2> <block>"));
+    };
+    let (x1, y1, z1, x2, y2, z2): (i32, i32, i32, i32, i32, i32) =
+        (parse(x1)?, parse(y1)?, parse(z1)?, parse(x2)?, parse(y2)?, parse(z2)?);
+    let id = fill_block_id(block)?;
+
+    let (xs, ys, zs) = (x1.min(x2)..=x1.max(x2), y1.min(y2)..=y1.max(y2), z1.min(z2)..=z1.max(z2));
+    let volume = xs.clone().count() * ys.clone().count() * zs.clone().count();
+    if volume > MAX_FILL_VOLUME {
+        return Err(CommandError::Other(format!("too many blocks {}, max {}", volume, MAX_FILL_VOLUME)));
+    }
+
+    let direction = Direction::new(0.0, 0.0, 1.0);
+    let mut count = 0;
+    for (x, y, z) in iproduct!(xs, ys, zs) {
+        let coords = GlobalCoords(x, y, z);
+        if context.world.voxel(&coords).is_none() {continue};
+        if id == 0 {
+            context.world.break_voxel(&coords);
+        } else {
+            context.world.set_voxel(&coords, id, &direction);
+        }
+        count += 1;
+    }
+    Ok(format!("Filled {} blocks", count))
+}
+
+
+/// Blocks placed by a single voxel, the multiblocks need their structure
+fn fill_block_id(block: &str) -> Result<u32, CommandError> {
+    let id = block_by_name(block).map(|b| b.id()).or_else(|| block.parse().ok())
+        .ok_or_else(|| CommandError::InvalidArgument(block.to_string()))?;
+    if id as usize >= BLOCKS().len() {return Err(CommandError::Other(format!("unknown block {}", id)))};
+    if id == MULTIBLOCK_PART_ID || BLOCKS()[id as usize].is_multiblock() {
+        return Err(CommandError::Other(format!("multiblock {} can't be filled", BLOCKS()[id as usize].name())));
+    }
+    Ok(id)
+}
+
+
+fn save(_: &[&str], context: &mut CommandContext) -> Result<String, CommandError> {
+    context.save.world.player.lock().unwrap().save_player(context.player);
+    context.is_save_requested = true;
+    Ok(String::from("Saving the world"))
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf, sync::{Arc, Condvar, Mutex, RwLock}, time::Duration};
+
+    use nalgebra_glm as glm;
+
+    use crate::{camera::camera_controller::CameraController, player::player::Player, recipes::{content::load_content, items::item_by_key, storage::Storage},
+        save_load::{Save, WorldMetadata}, test_utils::world_path, threads::{save::{self, SaveState}, scheduler::Scheduler}, voxels::block::blocks::{BLOCKS, MULTIBLOCK_PART_ID},
+        world::{World, chunk_coords::ChunkCoords, height_range::HeightRange, sun::{Color, Sun}}};
+
+    use super::{fill_block_id, CommandContext, CommandError, Commands};
+
+    /// Parts of an open world the commands change
+    struct Fixture {
+        world: Arc<RwLock<World>>,
+        player: Player,
+        sun: Sun<9>,
+        save_condvar: Arc<(Mutex<SaveState>, Condvar)>,
+        save: Save,
+        path: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            load_content(&mut WorldMetadata::default()).unwrap();
+            let path = world_path(name);
+            let position = glm::vec3(0.0, 40.0, 0.0);
+            Self {
+                world: Arc::new(RwLock::new(World::new(3, 2, 3, ChunkCoords(0, 0, 0), 0, HeightRange::new(0, 2).unwrap()))),
+                player: Player::new(CameraController::new(position, 1.0, 0.1, 100.0), position),
+                sun: Sun::new(0, [0, 10, 20, 30, 40, 50, 60, 70, 80], [(); 9].map(|_| Color(0.0, 0.0, 0.0)), [(); 9].map(|_| Color(0.0, 0.0, 0.0))),
+                save_condvar: Arc::new((Mutex::new(SaveState::Saved), Condvar::new())),
+                save: Save::new(&path),
+                path,
+            }
+        }
+
+        /// Runs the line like the main loop, the save is requested after the world is released
+        fn execute(&mut self, line: &str) -> Result<String, CommandError> {
+            let mut world = self.world.write().unwrap();
+            let mut context = CommandContext {
+                world: &mut world, player: &mut self.player, sun: &mut self.sun, save: &self.save, is_save_requested: false};
+            let result = Commands::new().execute(line, &mut context);
+            let is_save_requested = context.is_save_requested;
+            drop(world);
+            if is_save_requested {save::request_save(&self.save_condvar)};
+            result
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    #[test]
+    fn parse_lines() {
+        let mut fixture = Fixture::new("commands_parse");
+        assert_eq!(fixture.execute("   ").unwrap(), "");
+        assert!(matches!(fixture.execute("/nothing 1"), Err(CommandError::Unknown(name)) if name == "nothing"));
+        assert!(matches!(fixture.execute("/tp 1 2"), Err(CommandError::Usage(_))));
+        assert!(matches!(fixture.execute("/tp 1 a 3"), Err(CommandError::InvalidArgument(arg)) if arg == "a"));
+        let help = fixture.execute("help").unwrap();
+        assert!(help.lines().count() == 6 && help.lines().any(|usage| usage == "/give <item> [count]"));
+        // The slash is optional and the words are split by any whitespace
+        assert!(fixture.execute("  tp\t1  2 3 ").is_ok());
+        assert_eq!(*fixture.player.camera().position(), glm::vec3(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn give_items() {
+        let mut fixture = Fixture::new("commands_give");
+        let item = item_by_key("iron_plate").expect("The item is defined");
+        assert_eq!(fixture.execute("/give iron_plate 5").unwrap(), format!("Given 5 {}", item.name));
+        assert!(fixture.execute("/give iron_plate").is_ok());
+        let count = |player: &mut Player| player.inventory().lock().unwrap().storage().iter()
+            .filter_map(|possible_item| possible_item.0)
+            .filter(|stack| stack.id() == item.id)
+            .map(|stack| stack.count)
+            .sum::<u32>();
+        assert_eq!(count(&mut fixture.player), 6);
+
+        assert!(matches!(fixture.execute("/give nothing 5"), Err(CommandError::InvalidArgument(_))));
+        assert!(matches!(fixture.execute("/give iron_plate -1"), Err(CommandError::InvalidArgument(_))));
+        assert!(matches!(fixture.execute("/give"), Err(CommandError::Usage(_))));
+        assert_eq!(count(&mut fixture.player), 6);
+    }
+
+    #[test]
+    fn teleport_and_time() {
+        let mut fixture = Fixture::new("commands_tp_time");
+        assert_eq!(fixture.execute("/tp 10 -5.5 3").unwrap(), "Teleported to 10 -5.5 3");
+        assert_eq!(*fixture.player.camera().position(), glm::vec3(10.0, -5.5, 3.0));
+
+        assert_eq!(fixture.execute("/time set 42").unwrap(), "Time set to 42");
+        assert_eq!(fixture.execute("/time").unwrap(), "Time 42");
+        assert!(matches!(fixture.execute("/time set noon"), Err(CommandError::InvalidArgument(_))));
+        assert!(matches!(fixture.execute("/time get"), Err(CommandError::Usage(_))));
+    }
+
+    #[test]
+    fn save_requests_world_save() {
+        let mut fixture = Fixture::new("commands_save");
+        fixture.player.set_position(glm::vec3(7.0, 8.0, 9.0));
+        assert_eq!(fixture.execute("/save").unwrap(), "Saving the world");
+        assert!(matches!(*fixture.save_condvar.0.lock().unwrap(), SaveState::Unsaved));
+        let player = fixture.save.world.player.lock().unwrap().load_player().expect("The player is saved");
+        assert_eq!(*player.camera().position(), glm::vec3(7.0, 8.0, 9.0));
+    }
+
+    #[test]
+    fn save_during_save_loop() {
+        let mut fixture = Fixture::new("commands_save_loop");
+        let mut scheduler = Scheduler::new();
+        save::spawn(&mut scheduler, fixture.world.clone(), fixture.save.world.regions.clone(), fixture.save_condvar.clone());
+        // The save loop notifies the condvar when it takes the request
+        let wait_taken = |save_condvar: &(Mutex<SaveState>, Condvar)| {
+            let (state, _) = save_condvar.1.wait_timeout_while(save_condvar.0.lock().unwrap(), Duration::from_secs(5),
+                |state| *state == SaveState::Unsaved).unwrap();
+            *state == SaveState::Saved
+        };
+
+        // The save loop wakes while the commands write the world and waits for it without the save state
+        let world = fixture.world.clone();
+        let mut world_w = world.write().unwrap();
+        save::request_save(&fixture.save_condvar);
+        assert!(wait_taken(&fixture.save_condvar), "The save loop did not take the request");
+        let mut context = CommandContext {
+            world: &mut world_w, player: &mut fixture.player, sun: &mut fixture.sun, save: &fixture.save, is_save_requested: false};
+        assert_eq!(Commands::new().execute("/save", &mut context).unwrap(), "Saving the world");
+        assert!(context.is_save_requested);
+        drop(world_w);
+
+        save::request_save(&fixture.save_condvar);
+        assert!(wait_taken(&fixture.save_condvar), "The save loop did not take the second request");
+        // The shutdown lets the taken save finish
+        scheduler.cancel();
+        drop(fixture.save_condvar.0.lock().unwrap());
+        fixture.save_condvar.1.notify_all();
+        assert!(scheduler.shutdown(Duration::from_secs(5)));
+        assert!(scheduler.stats().iter().any(|stats| stats.name == "save" && stats.done >= 2), "The requested save is not done");
+    }
+
+    #[test]
+    fn fill_rejects_unknown_block() {
+        assert!(matches!(fill_block_id(&BLOCKS().len().to_string()), Err(CommandError::Other(_))));
+        assert!(matches!(fill_block_id("9999"), Err(CommandError::Other(_))));
+        assert!(matches!(fill_block_id("not_a_block"), Err(CommandError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn fill_rejects_multiblock_part() {
+        assert!(fill_block_id(&MULTIBLOCK_PART_ID.to_string()).is_err());
+        assert!(fill_block_id(BLOCKS()[MULTIBLOCK_PART_ID as usize].name()).is_err());
+    }
+
+    #[test]
+    fn fill_rejects_multiblock() {
+        let multiblock = BLOCKS().iter().find(|block| block.is_multiblock() && block.id() != MULTIBLOCK_PART_ID).expect("A multiblock is defined");
+        assert!(fill_block_id(&multiblock.id().to_string()).is_err());
+        assert!(fill_block_id(multiblock.name()).is_err());
+        assert_eq!(fill_block_id("0").unwrap(), 0);
+    }
+}
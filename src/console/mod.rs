@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+use self::commands::{Commands, CommandContext};
+
+pub mod commands;
+
+const MAX_HISTORY: usize = 100;
+
+
+/// Text console, the lines come from the gui or from stdin
+#[derive(Debug)]
+pub struct Console {
+    commands: Commands,
+    history: VecDeque<String>,
+    submitted: Vec<String>,
+    pub input: String,
+    is_open: bool,
+}
+
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            commands: Commands::new(),
+            history: VecDeque::new(),
+            submitted: vec![],
+            input: String::new(),
+            is_open: false,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {self.is_open}
+
+    pub fn toggle(&mut self) {
+        self.is_open = !self.is_open;
+        self.input.retain(|c| c != '`');
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &String> {
+        self.history.iter()
+    }
+
+    /// Line entered in the gui, it is executed with the next frame
+    pub fn submit(&mut self) {
+        let line = std::mem::take(&mut self.input);
+        if !line.trim().is_empty() {self.submitted.push(line)};
+    }
+
+    pub fn take_submitted(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.submitted)
+    }
+
+    /// Executes the line and returns the output, the output is also added to the history
+    pub fn execute(&mut self, line: &str, context: &mut CommandContext) -> String {
+        let output = match self.commands.execute(line, context) {
+            Ok(output) => output,
+            Err(e) => e.to_string(),
+        };
+        self.push_history(format!("> {}", line.trim()));
+        if !output.is_empty() {self.push_history(output.clone())};
+        output
+    }
+
+    fn push_history(&mut self, line: String) {
+        if self.history.len() >= MAX_HISTORY {self.history.pop_front();}
+        self.history.push_back(line);
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,145 @@
+use std::{fmt::Display, str::FromStr, path::{Path, PathBuf}};
+
+/// Plain text definitions:
+/// ```text
+/// # comment
+/// key = value
+///
+/// [section]
+/// key = value
+/// ```
+#[derive(Debug)]
+pub struct Definitions {
+    pub root: Definition,
+    pub sections: Vec<Definition>,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub path: PathBuf,
+    pub key: String,
+    pub values: Vec<(String, String)>,
+}
+
+
+#[derive(Debug)]
+pub enum DefinitionError {
+    Io(PathBuf, std::io::Error),
+    Syntax {path: PathBuf, line: usize, text: String},
+    MissingKey {path: PathBuf, section: String, key: String},
+    InvalidValue {path: PathBuf, section: String, key: String, value: String},
+    Other {path: PathBuf, section: String, message: String},
+}
+
+
+impl Display for DefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Syntax { path, line, text } =>
+                write!(f, "{}:{}: can't parse line \"{}\"", path.display(), line, text),
+            Self::MissingKey { path, section, key } =>
+                write!(f, "{}: [{}] missing key \"{}\"", path.display(), section, key),
+            Self::InvalidValue { path, section, key, value } =>
+                write!(f, "{}: [{}] invalid value \"{}\" for key \"{}\"", path.display(), section, value, key),
+            Self::Other { path, section, message } =>
+                write!(f, "{}: [{}] {}", path.display(), section, message),
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+
+impl Definitions {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, DefinitionError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| DefinitionError::Io(path.to_path_buf(), e))?;
+        Self::parse(path, &text)
+    }
+
+
+    pub fn parse<P: AsRef<Path>>(path: P, text: &str) -> Result<Self, DefinitionError> {
+        let path = path.as_ref().to_path_buf();
+        let mut root = Definition::new(&path, "");
+        let mut sections: Vec<Definition> = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {continue};
+
+            if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                sections.push(Definition::new(&path, key.trim()));
+            } else if let Some((key, value)) = line.split_once('=') {
+                sections.last_mut().unwrap_or(&mut root).values
+                    .push((key.trim().to_string(), value.trim().to_string()));
+            } else {
+                return Err(DefinitionError::Syntax { path, line: i+1, text: line.to_string() });
+            }
+        }
+
+        Ok(Self { root, sections })
+    }
+}
+
+
+impl Definition {
+    fn new(path: &Path, key: &str) -> Self {
+        Self { path: path.to_path_buf(), key: key.to_string(), values: vec![] }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn require(&self, key: &str) -> Result<&str, DefinitionError> {
+        self.get(key).ok_or_else(|| self.missing_key(key))
+    }
+
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<Option<T>, DefinitionError> {
+        self.get(key).map(|value| value.parse::<T>()
+            .map_err(|_| self.invalid_value(key, value))).transpose()
+    }
+
+    pub fn parse_required<T: FromStr>(&self, key: &str) -> Result<T, DefinitionError> {
+        let value = self.require(key)?;
+        value.parse::<T>().map_err(|_| self.invalid_value(key, value))
+    }
+
+    /// Values separated by spaces or commas
+    pub fn list(&self, key: &str) -> Vec<&str> {
+        self.get(key).map_or(vec![], |value| {
+            value.split(|c: char| c == ',' || c.is_whitespace()).filter(|v| !v.is_empty()).collect()
+        })
+    }
+
+    pub fn parse_list<T: FromStr>(&self, key: &str) -> Result<Vec<T>, DefinitionError> {
+        self.list(key).iter()
+            .map(|value| value.parse::<T>().map_err(|_| self.invalid_value(key, value)))
+            .collect()
+    }
+
+    /// List of `key:count` values, count defaults to 1
+    pub fn key_counts(&self, key: &str) -> Result<Vec<(String, u32)>, DefinitionError> {
+        self.list(key).iter().map(|value| {
+            let (k, count) = value.split_once(':').unwrap_or((value, "1"));
+            let count = count.parse::<u32>().map_err(|_| self.invalid_value(key, value))?;
+            Ok((k.to_string(), count))
+        }).collect()
+    }
+
+    pub fn missing_key(&self, key: &str) -> DefinitionError {
+        DefinitionError::MissingKey { path: self.path.clone(), section: self.key.clone(), key: key.to_string() }
+    }
+
+    pub fn invalid_value(&self, key: &str, value: &str) -> DefinitionError {
+        DefinitionError::InvalidValue {
+            path: self.path.clone(), section: self.key.clone(), key: key.to_string(), value: value.to_string() }
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> DefinitionError {
+        DefinitionError::Other { path: self.path.clone(), section: self.key.clone(), message: message.into() }
+    }
+}
@@ -140,6 +140,12 @@ impl State {
             render_pass.set_vertex_buffer(0, selection_vertex_buffer.slice(..));
             render_pass.draw(0..24, 0..1);
         }
+        if let Some((crack_vertex_buffer, len)) = &self.crack_vertex_buffer {
+            render_pass.set_pipeline(&self.pipelines.selection);
+            render_pass.set_bind_group(0, &self.bind_groups_buffers.camera.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, crack_vertex_buffer.slice(..));
+            render_pass.draw(0..*len, 0..1);
+        }
     }
 
     /// set bind group = 0 (crosshair_aspect_scale)
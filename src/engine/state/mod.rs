@@ -1,15 +1,13 @@
 use std::{iter, collections::HashMap, sync::Arc};
 use wgpu::{util::DeviceExt, TextureFormat, TextureFormatFeatureFlags, Adapter};
 use winit::window::Window;
-use crate::{meshes::Mesh, my_time::Time, models::{load_model::load_models, model::Model, load_animated_model::load_animated_models, animated_model::AnimatedModel}, rev_qumark, engine::{bind_group, shaders::Shaders, bind_group_layout::{Layouts, self}, pipeline::Pipelines, egui::Egui}};
+use crate::{voxels::block::blocks::BLOCK_TEXTURES, meshes::Mesh, my_time::Time, models::{load_model::load_models, model::Model, load_animated_model::load_animated_models, animated_model::AnimatedModel}, rev_qumark, engine::{bind_group, shaders::Shaders, bind_group_layout::{Layouts, self}, pipeline::Pipelines, egui::Egui}};
 use crate::engine::texture::TextureAtlas;
 use super::{texture::{self}, bind_group_buffer::BindGroupsBuffers};
 
 
 pub mod draw;
 
-const MAX_SAMPLE_COUNT: u32 = 4;
-
 fn get_supported_multisample_count(surface_format: &TextureFormat, sample_flags: &TextureFormatFeatureFlags) -> Vec<u32> {
     let sample: [u32; 5] = [1, 2, 4, 8, 16];
     let surface_flags = surface_format.guaranteed_format_features(wgpu::Features::empty()).flags;
@@ -20,6 +18,11 @@ fn get_supported_multisample_count(surface_format: &TextureFormat, sample_flags:
     }).collect()
 }
 
+/// The largest supported count that does not exceed the limit
+fn choose_sample_count(supported: &[u32], max_sample_count: u32) -> u32 {
+    supported.iter().copied().filter(|count| *count <= max_sample_count).max().unwrap_or(1)
+}
+
 
 async fn request_adapter(instance: &wgpu::Instance, surface: &wgpu::Surface, power: wgpu::PowerPreference)
     -> Option<wgpu::Adapter>
@@ -71,6 +74,7 @@ pub struct State {
     depth_texture: texture::Texture,
     multisampled_framebuffer: wgpu::TextureView,
     sample_count: u32,
+    supported_sample_counts: Vec<u32>,
 
     pub egui: Egui,
 
@@ -85,6 +89,7 @@ pub struct State {
     pub texture_atlas: Arc<TextureAtlas>,
 
     pub selection_vertex_buffer: Option<wgpu::Buffer>,
+    pub crack_vertex_buffer: Option<(wgpu::Buffer, u32)>,
 
     is_ui_interaction: bool,
     is_crosshair: bool,
@@ -92,7 +97,7 @@ pub struct State {
 }
 
 impl State {
-    pub async fn new(window: Arc<Window>, proj_view: &[[f32; 4]; 4]) -> Self {
+    pub async fn new(window: Arc<Window>, proj_view: &[[f32; 4]; 4], max_sample_count: u32) -> Self {
         let size = window.inner_size();
         // The instance is a handle to our GPU
         // BackendBit::PRIMARY => Vulkan + Metal + DX12 + Browser WebGPU
@@ -146,12 +151,8 @@ impl State {
         let sample_flags = adapter
             .get_texture_format_features(config.view_formats[0])
             .flags;
-        let samples_count = get_supported_multisample_count(&surface_format, &sample_flags);
-        let sample_count = if samples_count.contains(&MAX_SAMPLE_COUNT) {
-            MAX_SAMPLE_COUNT.min(samples_count.into_iter().max().unwrap())
-        } else {
-            samples_count.into_iter().max().unwrap()
-        };
+        let supported_sample_counts = get_supported_multisample_count(&surface_format, &sample_flags);
+        let sample_count = choose_sample_count(&supported_sample_counts, max_sample_count);
         println!("Sample count X{}", sample_count);
 
         let mut egui = Egui::new(&device, surface_format, size.width, size.height, window.scale_factor());
@@ -164,33 +165,7 @@ impl State {
         let bind_groups_buffers = BindGroupsBuffers::new(&device, &layouts, proj_view);
         let pipelines = Pipelines::new(&device, &layouts, &shaders, config.format, sample_count);
 
-        let block_texture = texture::Texture::image_array(&device, &queue, &[
-            "./assets/blocks/0_no_texture.png",
-            "./assets/blocks/1_block.png",
-            "./assets/blocks/2_block.png",
-            "./assets/blocks/marble.png",
-            "./assets/blocks/iron_ore.png",
-            "./assets/blocks/top.png",
-            "./assets/blocks/green.png",
-            "./assets/blocks/conveyor.png",
-            "./assets/blocks/box.png",
-            "./assets/blocks/rock.png",
-            "./assets/debug/0.png",
-            "./assets/debug/1.png",
-            "./assets/debug/2.png",
-            "./assets/debug/3.png",
-            "./assets/debug/4.png",
-            "./assets/debug/5.png",
-            "./assets/debug/6.png",
-            "./assets/debug/7.png",
-            "./assets/debug/8.png",
-            "./assets/debug/9.png",
-            "./assets/debug/10.png",
-            "./assets/debug/11.png",
-            "./assets/debug/12.png",
-            "./assets/debug/13.png",
-            "./assets/debug/14.png",
-            "./assets/debug/15.png",], None).unwrap();
+        let block_texture = texture::Texture::image_array(&device, &queue, &BLOCK_TEXTURES().paths(), None);
 
         let block_texutre_bg = bind_group::block_texture::get(&device, &layouts.block_texture, &block_texture);
         
@@ -229,6 +204,7 @@ impl State {
             depth_texture,
             multisampled_framebuffer,
             sample_count,
+            supported_sample_counts,
 
             egui,
 
@@ -241,6 +217,7 @@ impl State {
 
             texture_atlas: Arc::new(texture_atlas),
             selection_vertex_buffer: None,
+            crack_vertex_buffer: None,
 
             bind_groups_buffers,
             layouts,
@@ -275,6 +252,21 @@ impl State {
     }
 
 
+    /// Rebuilds the pipelines and the framebuffers when the sample count changes
+    pub fn set_max_sample_count(&mut self, max_sample_count: u32) {
+        let sample_count = choose_sample_count(&self.supported_sample_counts, max_sample_count);
+        if sample_count == self.sample_count {return};
+        println!("Sample count X{}", sample_count);
+        self.sample_count = sample_count;
+        let shaders = Shaders::new(&self.device);
+        self.pipelines = Pipelines::new(&self.device, &self.layouts, &shaders, self.config.format, sample_count);
+        self.depth_texture = texture::Texture::create_depth_texture(
+            &self.device, &self.config, "depth_texture", sample_count);
+        self.multisampled_framebuffer =
+            texture::Texture::create_multisampled_framebuffer(&self.device, &self.config, sample_count);
+    }
+
+
     pub fn update(&mut self, proj_view: &[[f32; 4]; 4], time: &Time) {
         self.queue.write_buffer(&self.bind_groups_buffers.camera.buffer, 0, bytemuck::cast_slice(proj_view));
         self.queue.write_buffer(&self.bind_groups_buffers.time.buffer, 0, &time.current().to_le_bytes());
@@ -1,5 +1,7 @@
 use image::DynamicImage;
 
+use crate::recipes::items::ITEMS;
+
 
 #[derive(Debug)]
 pub struct Texture {
@@ -13,7 +15,7 @@ impl Texture {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         src: &str,
-    ) -> Result<Self, ()> {
+    ) -> Self {
         let img = image::open(src).expect(src);
         let (width, height) = (img.width(), img.height());
         
@@ -63,11 +65,11 @@ impl Texture {
             ..Default::default()
         });
 
-        Ok(Self {
+        Self {
             texture,
             view,
             sampler,
-        })
+        }
     }
 
 
@@ -76,7 +78,7 @@ impl Texture {
         queue: &wgpu::Queue,
         srcs: &[&str],
         label: Option<&str>
-    ) -> Result<Self, ()> {
+    ) -> Self {
         const BASE_SIZE: u32 = 32;
         //Maximum mipmap_count is BASE_SIZE.ilog2() + 1 (img size 1px) but it's too small
         let mipmap_count = BASE_SIZE.ilog2();
@@ -141,11 +143,11 @@ impl Texture {
             ..Default::default()
         });
 
-        Ok(Self {
+        Self {
             texture,
             view,
             sampler,
-        })
+        }
     }
 
 
@@ -330,4 +332,9 @@ impl TextureAtlas {
         let uv = self.uv(id);
         (uv.0, uv.1, uv.0+self.uv_size, uv.1+self.uv_size)
     }
+
+
+    pub fn item_uv_rect(&self, item_id: u32) -> (f32, f32, f32, f32) {
+        self.uv_rect(ITEMS()[item_id as usize].icon)
+    }
 }
@@ -106,11 +106,11 @@ impl ComplexObject {
 }
 
 // I'll rewrite this using files someday
-pub fn new_transport_belt() -> ComplexObject {
+pub fn new_transport_belt(texture: u32) -> ComplexObject {
     ComplexObject::new(vec![
         ComplexObjectParts::Block([
             // Negative x
-            Some(ComplexObjectSide::new(9, vec![
+            Some(ComplexObjectSide::new(texture, vec![
                 [([0.0, 0.0,   0.0], [0.0, 0.0]).into(), 
                  ([0.0, 0.25,  0.0], [0.0, 0.25]).into(),
                  ([0.0, 0.25,  1.0], [1.0, 0.25]).into(),
@@ -121,7 +121,7 @@ pub fn new_transport_belt() -> ComplexObject {
                  ([0.875, 0.125, 1.0], [1.0, 0.125]).into()].into()
             ])),
             // Positive x
-            Some(ComplexObjectSide::new(9, vec![
+            Some(ComplexObjectSide::new(texture, vec![
                 [([1.0, 0.0,   0.0], [0.0, 0.0]).into(), 
                  ([1.0, 0.25,  0.0], [0.0, 0.25]).into(),
                  ([1.0, 0.25,  1.0], [1.0, 0.25]).into(),
@@ -132,14 +132,14 @@ pub fn new_transport_belt() -> ComplexObject {
                  ([0.125, 0.125, 1.0], [1.0, 0.125]).into()].into()
             ])),
             // Negative y
-            Some(ComplexObjectSide::new(9, vec![
+            Some(ComplexObjectSide::new(texture, vec![
                 [([0.0, 0.0, 0.0], [0.0, 0.0]).into(), 
                  ([0.0, 0.0, 1.0], [0.0, 1.0]).into(),
                  ([1.0, 0.0, 1.0], [1.0, 1.0]).into(),
                  ([1.0, 0.0, 0.0], [1.0, 0.0]).into()].into()
             ])),
             // Positive y
-            Some(ComplexObjectSide::new(9, vec![
+            Some(ComplexObjectSide::new(texture, vec![
                 [([0.0,   0.25,   0.0], [0.0, 0.0]).into(), 
                  ([0.0,   0.25,   1.0], [0.0, 1.0]).into(),
                  ([0.125, 0.25,   1.0], [0.125, 1.0]).into(),
@@ -150,7 +150,7 @@ pub fn new_transport_belt() -> ComplexObject {
                  ([1.0,   0.25,  0.0], [1.0, 0.0]).into()].into()
             ])),
             // Negative z
-            Some(ComplexObjectSide::new(9, vec![
+            Some(ComplexObjectSide::new(texture, vec![
                 [([0.0, 0.0,   0.0], [0.0, 0.0]).into(), 
                  ([1.0, 0.0,   0.0], [0.0, 1.0]).into(),
                  ([1.0, 0.125, 0.0], [0.125, 1.0]).into(),
@@ -165,7 +165,7 @@ pub fn new_transport_belt() -> ComplexObject {
                  ([0.875, 0.25,  0.0], [0.25, 0.875]).into()].into(),
             ])),
             // Positive z
-            Some(ComplexObjectSide::new(9, vec![
+            Some(ComplexObjectSide::new(texture, vec![
                 [([0.0, 0.0,   1.0], [0.0, 0.0]).into(), 
                  ([1.0, 0.0,   1.0], [0.0, 1.0]).into(),
                  ([1.0, 0.125, 1.0], [0.125, 1.0]).into(),
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::voxels::chunk::{Chunk, CHUNK_SIZE};
+use super::snapshot::ChunkSnapshot;
 
 #[derive(Debug, Clone)]
 pub struct AnimatedModelRenderResult {
@@ -13,11 +13,11 @@ pub struct AnimatedModelRenderResult {
 pub type AnimatedModels = HashMap::<String, Vec<AnimatedModelRenderResult>>;
 
 #[inline]
-pub fn render_animated_model(animated_models: &mut AnimatedModels, chunk: &Chunk, name: &str, lx: usize, ly: usize, lz: usize) {
-    let voxel_data = chunk.voxels_data.get(&((ly*CHUNK_SIZE+lz)*CHUNK_SIZE+lx)).unwrap();
-    let progress = voxel_data.additionally.animation_progress().unwrap_or(0.0);
-    let rotation_index = voxel_data.rotation_index().unwrap_or(0);
-    let light = chunk.get_light((lx, ly, lz).into()).get_normalized();
+pub fn render_animated_model(animated_models: &mut AnimatedModels, chunk: &ChunkSnapshot, name: &str, lx: usize, ly: usize, lz: usize) {
+    let voxel_data = chunk.voxel_data((lx, ly, lz).into()).unwrap();
+    let progress = voxel_data.animation_progress.unwrap_or(0.0);
+    let rotation_index = voxel_data.rotation_index.unwrap_or(0);
+    let light = chunk.local_light((lx, ly, lz).into()).get_normalized();
 
     let data = AnimatedModelRenderResult {
         position: chunk.xyz.to_global((lx, ly, lz).into()).into(),
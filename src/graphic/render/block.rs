@@ -1,14 +1,14 @@
-use crate::voxels::{block::{light_permeability::LightPermeability, blocks::BLOCKS, interaction::BlockInteraction}, chunks::Chunks, chunk::Chunk};
+use crate::voxels::block::{light_permeability::LightPermeability, blocks::BLOCKS, interaction::BlockInteraction};
 use crate::light::light_map::Light;
-use super::block_managers::BlockManagers;
+use super::{block_managers::BlockManagers, snapshot::ChunkSnapshot};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BlockFaceLight([Light; 9]);
 
 impl BlockFaceLight {
     #[inline]
-    pub fn new(chunks: &Chunks, coords: [(i32, i32, i32); 9]) -> Self {
-        Self(coords.map(|coord| chunks.get_light(coord.into())))
+    pub fn new(chunk: &ChunkSnapshot, coords: [(i32, i32, i32); 9]) -> Self {
+        Self(coords.map(|coord| chunk.light(coord.into())))
     }
 
     const ANGLE_INDICES: [[usize; 3]; 4] = [
@@ -49,12 +49,12 @@ impl BlockFace {
 }
 
 #[inline]
-pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &Chunk, block: &dyn BlockInteraction, faces: &[u32; 6], local: (usize, usize, usize)) {
+pub fn render_block(block_manager: &mut BlockManagers, chunk: &ChunkSnapshot, block: &dyn BlockInteraction, faces: &[u32; 6], local: (usize, usize, usize)) {
     let (lx, ly, lz) = local;
     let (x, y, z) = chunk.xyz.to_global((lx, ly, lz).into()).into();
     let (nx, px, ny, py, nz, pz) = (x-1, x+1, y-1, y+1, z-1, z+1);
-    if !is_blocked(x-1, y, z, chunks, LightPermeability::LEFT, block.light_permeability()) {
-        let light = BlockFaceLight::new(chunks, [
+    if !is_blocked(x-1, y, z, chunk, LightPermeability::LEFT, block.light_permeability()) {
+        let light = BlockFaceLight::new(chunk, [
             (nx, ny, nz), (nx, y, nz), (nx, py, nz),
             (nx, ny,  z), (nx, y, z),  (nx, py, z),
             (nx, ny, pz), (nx, y, pz), (nx, py, pz)
@@ -62,8 +62,8 @@ pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &
         block_manager.set(0, lx, ly, lz, BlockFace::new(faces[0], light));
     }
 
-    if !is_blocked(x+1, y, z, chunks, LightPermeability::RIGHT, block.light_permeability()) {
-        let light = BlockFaceLight::new(chunks, [
+    if !is_blocked(x+1, y, z, chunk, LightPermeability::RIGHT, block.light_permeability()) {
+        let light = BlockFaceLight::new(chunk, [
             (px, ny, nz), (px, y, nz), (px, py, nz),
             (px, ny,  z), (px, y, z),  (px, py, z),
             (px, ny, pz), (px, y, pz), (px, py, pz)
@@ -71,8 +71,8 @@ pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &
         block_manager.set(1, lx, ly, lz, BlockFace::new(faces[1], light));
     }
 
-    if !is_blocked(x, y-1, z, chunks, LightPermeability::DOWN, block.light_permeability()) {
-        let light = BlockFaceLight::new(chunks, [
+    if !is_blocked(x, y-1, z, chunk, LightPermeability::DOWN, block.light_permeability()) {
+        let light = BlockFaceLight::new(chunk, [
             (nx, ny, nz), (nx, ny, z), (nx, ny, pz),
             (x,  ny, nz), (x,  ny, z), (x,  ny, pz),
             (px, ny, nz), (px, ny, z), (px, ny, pz)
@@ -81,8 +81,8 @@ pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &
     }
 
 
-    if !is_blocked(x, y+1, z, chunks, LightPermeability::UP, block.light_permeability()) {
-        let light = BlockFaceLight::new(chunks, [
+    if !is_blocked(x, y+1, z, chunk, LightPermeability::UP, block.light_permeability()) {
+        let light = BlockFaceLight::new(chunk, [
             (nx, py, nz), (nx, py, z), (nx, py, pz),
             (x,  py, nz), (x,  py, z), (x,  py, pz),
             (px, py, nz), (px, py, z), (px, py, pz)
@@ -90,8 +90,8 @@ pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &
         block_manager.set(3, ly, lx, lz, BlockFace::new(faces[3], light));
     }
 
-    if !is_blocked(x, y, z-1, chunks, LightPermeability::DOWN, block.light_permeability()) {
-        let light = BlockFaceLight::new(chunks, [
+    if !is_blocked(x, y, z-1, chunk, LightPermeability::DOWN, block.light_permeability()) {
+        let light = BlockFaceLight::new(chunk, [
             (nx, ny, nz), (x, ny, nz), (px, ny, nz),
             (nx,  y, nz), (x,  y, nz), (px,  y, nz),
             (nx, py, nz), (x, py, nz), (px, py, nz)
@@ -99,8 +99,8 @@ pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &
         block_manager.set(4, lz, lx, ly, BlockFace::new(faces[4], light));
     }
 
-    if !is_blocked(x, y, z+1, chunks, LightPermeability::UP, block.light_permeability()) {
-        let light = BlockFaceLight::new(chunks, [
+    if !is_blocked(x, y, z+1, chunk, LightPermeability::UP, block.light_permeability()) {
+        let light = BlockFaceLight::new(chunk, [
             (nx, ny, pz), (x, ny, pz), (px, ny, pz),
             (nx,  y, pz), (x,  y, pz), (px,  y, pz),
             (nx, py, pz), (x, py, pz), (px, py, pz)
@@ -110,8 +110,8 @@ pub fn render_block(block_manager: &mut BlockManagers, chunks: &Chunks, chunk: &
 }
 
 #[inline]
-fn is_blocked(x: i32, y: i32, z: i32, chunks: &Chunks, side: LightPermeability, current: LightPermeability) -> bool {
-    let Some(voxel) = chunks.voxel_global((x, y, z).into()) else {return false};
-    let block = &BLOCKS()[voxel.id as usize];
+fn is_blocked(x: i32, y: i32, z: i32, chunk: &ChunkSnapshot, side: LightPermeability, current: LightPermeability) -> bool {
+    let Some(id) = chunk.voxel_id((x, y, z).into()) else {return false};
+    let block = &BLOCKS()[id as usize];
     ((block.light_permeability() & side.get_opposite_side()).bits() == 0) && ((current & side).bits() == 0)
 }
\ No newline at end of file
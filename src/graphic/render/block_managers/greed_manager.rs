@@ -3,7 +3,7 @@ use itertools::iproduct;
 
 use crate::graphic::render::Buffer;
 use crate::graphic::render::block::BlockFace;
-use crate::CHUNK_SIZE;
+use crate::voxels::chunk::CHUNK_SIZE;
 use super::face_managers::{manage_z, manage_y, manage_x};
 
 
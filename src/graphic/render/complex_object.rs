@@ -1,5 +1,5 @@
-use crate::{engine::vertices::block_vertex::BlockVertex, graphic::complex_object::{ComplexObjectSide, ComplexObject, ComplexObjectParts}, voxels::chunk::Chunk};
-use super::Buffer;
+use crate::{engine::vertices::block_vertex::BlockVertex, graphic::complex_object::{ComplexObjectSide, ComplexObject, ComplexObjectParts}};
+use super::{Buffer, snapshot::ChunkSnapshot};
 
 const INDICES: [[usize; 6]; 2] = [[0,1,2,0,2,3], [3,2,0,2,1,0]];
 
@@ -27,14 +27,14 @@ pub fn render_complex_object(
   complex_object: &ComplexObject,
   buffer: &mut Buffer,
   belt_buffer: &mut Buffer,
-  chunk: &Chunk,
+  chunk: &ChunkSnapshot,
   lx: usize,
   ly: usize,
   lz: usize
 ) {
     let voxel_data = chunk.voxel_data((lx, ly, lz).into()).unwrap();
-    let rotation_index = voxel_data.rotation_index().unwrap_or(0) as usize;
-    let light = chunk.get_light((lx, ly, lz).into()).get_normalized();
+    let rotation_index = voxel_data.rotation_index.unwrap_or(0) as usize;
+    let light = chunk.local_light((lx, ly, lz).into()).get_normalized();
     let global = chunk.xyz.to_global((lx, ly, lz).into()).into();
 
     complex_object.parts.iter().for_each(|parts| {
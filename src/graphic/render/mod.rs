@@ -2,14 +2,15 @@ use std::{collections::HashMap, time::Instant};
 
 use itertools::iproduct;
 
-use crate::{voxels::{chunk::CHUNK_SIZE, chunks::Chunks, block::{blocks::BLOCKS, block_type::BlockType, light_permeability::LightPermeability}}, engine::vertices::block_vertex::BlockVertex, world::{World, chunk_coords::ChunkCoords}, engine::pipeline::IS_LINE, graphic::render::block_managers::BlockManagers};
+use crate::{voxels::{chunk::CHUNK_SIZE, chunks::Chunks, block::{blocks::BLOCKS, block_type::BlockType, light_permeability::LightPermeability}}, engine::vertices::block_vertex::BlockVertex, world::chunk_coords::ChunkCoords, engine::pipeline::IS_LINE, graphic::render::block_managers::BlockManagers};
 use crate::light::light_map::Light;
-use self::{model::{Models, ModelRenderResult, render_model}, animated_model::{AnimatedModels, AnimatedModelRenderResult, render_animated_model}, complex_object::render_complex_object, block::{BlockFaceLight, BlockFace, render_block}};
+use self::{snapshot::ChunkSnapshot, model::{Models, ModelRenderResult, render_model}, animated_model::{AnimatedModels, AnimatedModelRenderResult, render_animated_model}, complex_object::render_complex_object, block::{BlockFaceLight, BlockFace, render_block}};
 
 pub mod block_managers;
 pub mod model;
 pub mod animated_model;
 pub mod complex_object;
+pub mod snapshot;
 mod block;
 
 const IS_GREEDY_MESHING: bool = true;
@@ -63,6 +64,10 @@ impl Buffer {
     }
 }
 
+impl Default for Buffer {
+    fn default() -> Self {Self::new()}
+}
+
 #[derive(Debug)]
 pub struct RenderResult {
     pub chunk_index: usize,
@@ -76,10 +81,8 @@ pub struct RenderResult {
     pub animated_models: AnimatedModels,
 }
 
-pub fn render(chunk_index: usize, world: &World) -> Option<RenderResult> {
-    let chunks = &world.chunks;
-    let Some(Some(chunk)) = chunks.chunks.get(chunk_index).map(|c| c.as_ref()) else {return None};
-    
+/// Meshes the snapshot, the chunk index is the index of the chunk when the snapshot was taken
+pub fn render(chunk_index: usize, chunk: &ChunkSnapshot) -> RenderResult {
     let mut models = Models::new();
     let mut animated_models = AnimatedModels::new();
     
@@ -89,12 +92,12 @@ pub fn render(chunk_index: usize, world: &World) -> Option<RenderResult> {
     let mut belt_buffer = Buffer::new();
 
     for (ly, lz, lx) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
-        let id = unsafe {chunk.get_unchecked_voxel((lx, ly, lz).into()).id};
+        let id = chunk.local_id((lx, ly, lz).into());
         if id == 0 { continue };
         let block = &BLOCKS()[id as usize];
         match block.block_type() {
             BlockType::Block {faces} => {
-                render_block(&mut block_manager, chunks, chunk, block.as_ref(), faces, (lx, ly, lz));
+                render_block(&mut block_manager, chunk, block.as_ref(), faces, (lx, ly, lz));
             },
             BlockType::None => {},
             BlockType::Model {name} => {
@@ -110,7 +113,7 @@ pub fn render(chunk_index: usize, world: &World) -> Option<RenderResult> {
     }
     let global = chunk.xyz.to_global((0u8, 0, 0).into()).into();
     block_manager.manage_vertices(&mut buffer, global);
-    Some(RenderResult {
+    RenderResult {
         chunk_index,
         xyz: chunk.xyz,
         block_vertices: buffer.buffer,
@@ -119,5 +122,5 @@ pub fn render(chunk_index: usize, world: &World) -> Option<RenderResult> {
         animated_models,
         belt_vertices: belt_buffer.buffer,
         belt_indices: belt_buffer.index_buffer,
-    })
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::voxels::chunk::{Chunk, CHUNK_SIZE};
+use super::snapshot::ChunkSnapshot;
 
 #[derive(Debug, Clone)]
 pub struct ModelRenderResult {
@@ -12,11 +12,11 @@ pub struct ModelRenderResult {
 pub type Models = HashMap::<String, Vec<ModelRenderResult>>;
 
 #[inline]
-pub fn render_model(models: &mut Models, chunk: &Chunk, name: &str, lx: usize, ly: usize, lz: usize) {
-    let rotation_index = chunk.voxels_data.get(&((ly*CHUNK_SIZE+lz)*CHUNK_SIZE+lx))
-        .and_then(|vd| vd.rotation_index()).unwrap_or(0);
+pub fn render_model(models: &mut Models, chunk: &ChunkSnapshot, name: &str, lx: usize, ly: usize, lz: usize) {
+    let rotation_index = chunk.voxel_data((lx, ly, lz).into())
+        .and_then(|vd| vd.rotation_index).unwrap_or(0);
 
-    let light = chunk.get_light((lx, ly, lz).into()).get_normalized();
+    let light = chunk.local_light((lx, ly, lz).into()).get_normalized();
 
     let data = ModelRenderResult {
         position: chunk.xyz.to_global((lx, ly, lz).into()).into(),
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use itertools::iproduct;
+
+use crate::{light::light_map::Light, voxels::{chunk::{Chunk, CHUNK_SIZE}, chunks::Chunks}, world::{chunk_coords::ChunkCoords, global_coords::GlobalCoords, local_coords::LocalCoords}};
+
+/// The chunk with a border of one voxel on every side
+const SIDE: usize = CHUNK_SIZE + 2;
+
+
+/// Voxel data that changes the mesh
+#[derive(Debug, Clone, Copy)]
+pub struct VoxelDataSnapshot {
+    pub rotation_index: Option<u32>,
+    pub animation_progress: Option<f32>,
+}
+
+
+/// Copy of a chunk with the boundary voxels and light of its neighbours,
+/// the chunk is meshed from it without the world
+#[derive(Debug)]
+pub struct ChunkSnapshot {
+    pub xyz: ChunkCoords,
+    /// None in the neighbours that are not loaded
+    ids: Box<[Option<u32>]>,
+    lights: Box<[Light]>,
+    voxels_data: HashMap<usize, VoxelDataSnapshot>,
+}
+
+impl ChunkSnapshot {
+    pub fn new(chunks: &Chunks, chunk: &Chunk) -> Self {
+        let mut ids = vec![None; SIDE.pow(3)];
+        let mut lights = vec![Light::default(); SIDE.pow(3)];
+        let origin = chunk.xyz.to_global((0u8, 0, 0).into());
+        for (y, z, x) in iproduct!(0..SIDE, 0..SIDE, 0..SIDE) {
+            let index = (y*SIDE + z)*SIDE + x;
+            let is_inside = [x, y, z].iter().all(|c| (1..=CHUNK_SIZE).contains(c));
+            if is_inside {
+                let local: LocalCoords = (x-1, y-1, z-1).into();
+                ids[index] = Some(unsafe {chunk.get_unchecked_voxel(local)}.id);
+                lights[index] = chunk.get_light(local);
+            } else {
+                let global = GlobalCoords(origin.0 + x as i32 - 1, origin.1 + y as i32 - 1, origin.2 + z as i32 - 1);
+                ids[index] = chunks.voxel_global(global).map(|voxel| voxel.id);
+                lights[index] = chunks.get_light(global);
+            }
+        }
+
+        let voxels_data = chunk.voxels_data.iter().map(|(index, data)| (*index, VoxelDataSnapshot {
+            rotation_index: data.rotation_index(),
+            animation_progress: data.additionally.animation_progress(),
+        })).collect();
+
+        Self { xyz: chunk.xyz, ids: ids.into_boxed_slice(), lights: lights.into_boxed_slice(), voxels_data }
+    }
+
+    /// Index of a voxel of the chunk or the border
+    #[inline]
+    fn index(&self, coords: GlobalCoords) -> Option<usize> {
+        let origin = self.xyz.to_global((0u8, 0, 0).into());
+        let (x, y, z) = (coords.0 - origin.0 + 1, coords.1 - origin.1 + 1, coords.2 - origin.2 + 1);
+        let side = 0..SIDE as i32;
+        (side.contains(&x) && side.contains(&y) && side.contains(&z))
+            .then(|| (y as usize*SIDE + z as usize)*SIDE + x as usize)
+    }
+
+    /// Id of the voxel of the chunk or the border, None if it is not loaded
+    #[inline]
+    pub fn voxel_id(&self, coords: GlobalCoords) -> Option<u32> {
+        self.index(coords).and_then(|index| self.ids[index])
+    }
+
+    #[inline]
+    pub fn local_id(&self, local: LocalCoords) -> u32 {
+        self.voxel_id(self.xyz.to_global(local)).unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn light(&self, coords: GlobalCoords) -> Light {
+        self.index(coords).map_or(Light::default(), |index| self.lights[index])
+    }
+
+    #[inline]
+    pub fn local_light(&self, local: LocalCoords) -> Light {
+        self.light(self.xyz.to_global(local))
+    }
+
+    pub fn voxel_data(&self, local: LocalCoords) -> Option<&VoxelDataSnapshot> {
+        self.voxels_data.get(&local.index())
+    }
+}
@@ -21,4 +21,38 @@ pub fn render_selection(device: &wgpu::Device, min: &[f32; 3], max: &[f32; 3]) -
         ]),
         usage: wgpu::BufferUsages::VERTEX,
     })
-}
\ No newline at end of file
+}
+
+// Crack segments on a face in the order they appear, (u0, v0, u1, v1)
+const CRACK: [[f32; 4]; 10] = [
+    [0.5, 0.5, 0.62, 0.41], [0.5, 0.5, 0.42, 0.66],
+    [0.5, 0.5, 0.35, 0.42], [0.62, 0.41, 0.8, 0.45],
+    [0.42, 0.66, 0.47, 0.85], [0.35, 0.42, 0.18, 0.3],
+    [0.5, 0.5, 0.58, 0.22], [0.8, 0.45, 0.95, 0.3],
+    [0.47, 0.85, 0.3, 0.97], [0.58, 0.22, 0.52, 0.05],
+];
+
+pub fn render_crack(device: &wgpu::Device, min: &[f32; 3], max: &[f32; 3], progress: f32) -> (wgpu::Buffer, u32) {
+    let segments = ((progress * CRACK.len() as f32).ceil() as usize).min(CRACK.len());
+    let mut vertices: Vec<[f32; 3]> = vec![];
+    for (axis, side) in itertools::iproduct!(0..3, 0..2) {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let point = |u: f32, v: f32| {
+            let mut p = [0.0; 3];
+            p[axis] = if side == 0 {min[axis]} else {max[axis]};
+            p[u_axis] = min[u_axis] + (max[u_axis] - min[u_axis])*u;
+            p[v_axis] = min[v_axis] + (max[v_axis] - min[v_axis])*v;
+            p
+        };
+        CRACK.iter().take(segments).for_each(|s| {
+            vertices.push(point(s[0], s[1]));
+            vertices.push(point(s[2], s[3]));
+        });
+    }
+
+    (device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Crack vertex Buffer"),
+        contents: bytemuck::cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+    }), vertices.len() as u32)
+}
@@ -1,7 +1,9 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use crate::{player::inventory::PlayerInventory, engine::texture::TextureAtlas};
+use crate::engine::texture::TextureAtlas;
+
+use super::gui_action::StorageAction;
 
 pub trait Draw {
-    fn draw(&mut self, ui: &mut egui::Ui, atals: Arc<TextureAtlas>, inventory: Arc<Mutex<PlayerInventory>>);
+    fn draw(&mut self, ui: &mut egui::Ui, atals: Arc<TextureAtlas>) -> Option<StorageAction>;
 }
\ No newline at end of file
@@ -0,0 +1,134 @@
+use crate::{recipes::{recipes::RECIPES, storage::Storage}, player::player::Player, bytes::{AsFromBytes, DecodeError}};
+
+use super::slot_action::SlotAction;
+
+/// Kind, slot action and index
+pub const GUI_ACTION_SIZE: usize = 1 + 1 + 4;
+
+
+/// Change picked in the inventory window, applied in the next frame so that it can be recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuiAction {
+    /// Slot of the player inventory, the hotbar included
+    Inventory(usize, SlotAction),
+    SortInventory,
+    /// Player recipe by its index in all recipes
+    StartRecipe(usize),
+    Storage(StorageAction),
+    /// The held item goes back to the inventory when the window is closed
+    ReturnHeld,
+}
+
+
+/// Change picked in the window of the open storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageAction {
+    Slot(usize, SlotAction),
+    Sort,
+    /// Assembler recipe by its index in all recipes
+    SelectRecipe(usize),
+}
+
+
+impl GuiAction {
+    pub fn apply(self, player: &mut Player) {
+        let inventory = player.inventory();
+        match self {
+            Self::Inventory(index, action) => {
+                let storage = player.open_storage.as_ref().and_then(|op| op.upgrade());
+                let mut inventory = inventory.lock().unwrap();
+                if index >= inventory.storage().len() {return};
+                match (action, storage) {
+                    (SlotAction::Transfer, Some(storage)) => {
+                        let Some(item) = inventory.mut_storage()[index].0.take() else {return};
+                        let remainder = storage.lock().unwrap().add(&item, true);
+                        if let Some(r) = remainder {inventory.set(&r, index)}
+                    },
+                    (SlotAction::Transfer, None) if index < 10 => {inventory.place_in_inventory(index);},
+                    (SlotAction::Transfer, None) => {inventory.place_in_hotbar(index);},
+                    (action, _) => {
+                        let mut held = inventory.take_held();
+                        action.apply(&mut *inventory, index, &mut held);
+                        inventory.set_held(held);
+                    },
+                }
+            },
+            Self::SortInventory => inventory.lock().unwrap().sort(),
+            Self::StartRecipe(index) => {
+                let Some(recipe) = RECIPES().player.all().iter().find(|r| r.index == index) else {return};
+                inventory.lock().unwrap().start_recipe(recipe);
+            },
+            Self::Storage(action) => {
+                let Some(storage) = player.open_storage.as_ref().and_then(|op| op.upgrade()) else {return};
+                storage.lock().unwrap().apply_action(action, &inventory);
+            },
+            Self::ReturnHeld => inventory.lock().unwrap().return_held(),
+        }
+    }
+
+
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        let (kind, slot_action, index) = match *self {
+            Self::Inventory(index, action) => (0u8, Some(action), index),
+            Self::SortInventory => (1, None, 0),
+            Self::StartRecipe(index) => (2, None, index),
+            Self::Storage(StorageAction::Slot(index, action)) => (3, Some(action), index),
+            Self::Storage(StorageAction::Sort) => (4, None, 0),
+            Self::Storage(StorageAction::SelectRecipe(index)) => (5, None, index),
+            Self::ReturnHeld => (6, None, 0),
+        };
+        bytes.push(kind);
+        bytes.push(slot_action.map_or(0, |a| a as u8));
+        bytes.extend((index as u32).as_bytes());
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let index = u32::from_bytes_at(bytes, 2)? as usize;
+        let slot_action = match bytes[1] {
+            0 => SlotAction::Pick,
+            1 => SlotAction::Drop,
+            2 => SlotAction::Split,
+            3 => SlotAction::Transfer,
+            value => return Err(DecodeError::InvalidValue { name: "slot action", value: value as u64 }),
+        };
+        Ok(match bytes[0] {
+            0 => Self::Inventory(index, slot_action),
+            1 => Self::SortInventory,
+            2 => Self::StartRecipe(index),
+            3 => Self::Storage(StorageAction::Slot(index, slot_action)),
+            4 => Self::Storage(StorageAction::Sort),
+            5 => Self::Storage(StorageAction::SelectRecipe(index)),
+            6 => Self::ReturnHeld,
+            value => return Err(DecodeError::InvalidValue { name: "gui action", value: value as u64 }),
+        })
+    }
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use crate::{gui::slot_action::SlotAction, bytes::DecodeError};
+    use super::{GuiAction, StorageAction, GUI_ACTION_SIZE};
+
+    #[test]
+    fn encode_decode() {
+        let actions = [
+            GuiAction::Inventory(12, SlotAction::Split),
+            GuiAction::SortInventory,
+            GuiAction::StartRecipe(3),
+            GuiAction::Storage(StorageAction::Slot(29, SlotAction::Transfer)),
+            GuiAction::Storage(StorageAction::Sort),
+            GuiAction::Storage(StorageAction::SelectRecipe(7)),
+            GuiAction::ReturnHeld,
+        ];
+        for action in actions {
+            let mut bytes = vec![];
+            action.encode(&mut bytes);
+            assert_eq!(bytes.len(), GUI_ACTION_SIZE);
+            assert_eq!(GuiAction::decode(&bytes).unwrap(), action);
+        }
+        assert!(matches!(GuiAction::decode(&[9, 0, 0, 0, 0, 0]), Err(DecodeError::InvalidValue { .. })));
+        assert!(GuiAction::decode(&[0, 0]).is_err());
+    }
+}
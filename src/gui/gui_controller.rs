@@ -1,15 +1,52 @@
-use std::{borrow::BorrowMut, sync::Arc};
+use std::{borrow::BorrowMut, sync::Arc, path::PathBuf, collections::HashMap};
 
-use egui::{Align2, vec2, Context, Align, Color32, epaint::Shadow, Rounding, Margin, RichText};
-use winit::{window::Window, dpi::PhysicalPosition};
+use egui::{Align2, vec2, pos2, Context, Align, Color32, epaint::Shadow, Rounding, Margin, RichText, LayerId, Order, Id, Stroke};
+use nalgebra_glm as glm;
+use winit::{window::{Window, Fullscreen}, dpi::PhysicalPosition};
 
-use crate::{player::player::Player, recipes::{storage::Storage, recipes::RECIPES}, engine::texture::TextureAtlas};
-use super::{my_widgets::{inventory_slot::inventory_slot, category_change_button::category_change_button, container::container, recipe::recipe, hotbar_slot::hotbar_slot, active_recipe::active_recipe}, theme::DEFAULT_THEME};
+use crate::{console::Console, player::{player::Player, inventory::PlayerInventory}, recipes::{storage::Storage, recipes::RECIPES, item::{PossibleItem, Item}, recipe::{Recipe, RecipeCategory}, items::ITEMS, crafting_plan::CraftingPlan}, engine::texture::TextureAtlas, voxels::{block::blocks::BLOCKS, voxel_data::machine_status::MachineStatus}, world::{alerts::Alert, global_coords::GlobalCoords, game_mode::GameMode}, save_load::{WorldEntry, BackupEntry, parse_seed}, settings::{Settings, MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE, MIN_RENDER_HEIGHT, MAX_RENDER_HEIGHT, MAX_BACKUPS}, input_event::actions::{Action, Button, STATES}};
+use super::{my_widgets::{inventory_slot::inventory_slot, category_change_button::category_change_button, container::container, recipe::recipe, hotbar_slot::hotbar_slot, active_recipe::active_recipe}, theme::DEFAULT_THEME, slot_action::SlotAction, gui_action::GuiAction};
 
-enum Task {
-    Hotbar(usize),
-    Inventory(usize),
-    Storage(usize),
+
+const STATUS_ICON_RADIUS: f32 = 6.0;
+const MAX_ALERTS_IN_GROUP: usize = 8;
+const MENU_BUTTON_SIZE: [f32; 2] = [200.0, 30.0];
+const MSAA_OPTIONS: [u32; 4] = [1, 2, 4, 8];
+
+
+/// Button pressed in the title or the pause menu
+#[derive(Debug, Clone)]
+pub enum MenuAction {
+    Resume,
+    Save,
+    QuitToTitle,
+    Exit,
+    OpenWorld(PathBuf),
+    CreateWorld {name: String, seed: u64, mode: GameMode},
+    DeleteWorld(PathBuf),
+    RestoreBackup {world: PathBuf, backup: BackupEntry},
+    ApplySettings(Settings),
+}
+
+
+/// Crafting plans of the recipe list, built again only when the inventory changes
+#[derive(Debug, Default)]
+struct CraftingPlans {
+    contents: Vec<Option<Item>>,
+    plans: HashMap<usize, Option<CraftingPlan>>,
+}
+
+impl CraftingPlans {
+    fn refresh(&mut self, inventory: &PlayerInventory) {
+        let contents = inventory.storage().iter().map(|possible_item| possible_item.0).collect::<Vec<_>>();
+        if contents == self.contents {return};
+        self.contents = contents;
+        self.plans.clear();
+    }
+
+    fn plan(&mut self, inventory: &PlayerInventory, recipe: &'static Recipe) -> Option<&CraftingPlan> {
+        self.plans.entry(recipe.index).or_insert_with(|| inventory.crafting_plan(recipe)).as_ref()
+    }
 }
 
 pub struct GuiController {
@@ -18,17 +55,57 @@ pub struct GuiController {
     is_ui: bool,
     is_menu: bool,
     is_cursor: bool,
+    is_typing: bool,
+    recipe_category: RecipeCategory,
+    recipe_search: String,
+    selected_alert: Option<GlobalCoords>,
+    highlighted_machine: Option<GlobalCoords>,
+    is_settings: bool,
+    is_controls: bool,
+    /// Action waiting for a button in the controls screen
+    rebinding: Option<Action>,
+    menu_action: Option<MenuAction>,
+    new_world_name: String,
+    new_world_seed: String,
+    new_world_mode: GameMode,
+    delete_world: Option<PathBuf>,
+    /// World whose backups are listed
+    backups_world: Option<PathBuf>,
+    restore_backup: Option<PathBuf>,
+    /// Picked in the inventory since the last call of take_gui_actions
+    gui_actions: Vec<GuiAction>,
+    /// Edited in the settings screen until applied
+    settings: Settings,
+    crafting_plans: CraftingPlans,
 }
 
 
 impl GuiController {
-    pub fn new(window: Arc<Window>, items_atlas: Arc<TextureAtlas>) -> Self {
+    pub fn new(window: Arc<Window>, items_atlas: Arc<TextureAtlas>, settings: Settings) -> Self {
         Self {
             window,
             items_atlas,
             is_ui: true,
             is_menu: false,
             is_cursor: true,
+            is_typing: false,
+            recipe_category: RecipeCategory::ITEM,
+            recipe_search: String::new(),
+            selected_alert: None,
+            highlighted_machine: None,
+            is_settings: false,
+            is_controls: false,
+            rebinding: None,
+            menu_action: None,
+            new_world_name: String::from("New world"),
+            new_world_seed: String::new(),
+            new_world_mode: GameMode::default(),
+            delete_world: None,
+            backups_world: None,
+            restore_backup: None,
+            gui_actions: vec![],
+            settings,
+            crafting_plans: CraftingPlans::default(),
         }
     }
     pub fn is_ui(&self) -> bool {
@@ -37,8 +114,27 @@ impl GuiController {
     pub fn toggle_ui(&mut self) {
         self.is_ui = !self.is_ui;
     }
+    pub fn is_menu(&self) -> bool {
+        self.is_menu
+    }
     pub fn toggle_menu(&mut self) {
         self.is_menu = !self.is_menu;
+        self.is_settings = false;
+        self.is_controls = false;
+        self.rebinding = None;
+    }
+
+    /// The controls screen waits for a button
+    pub fn rebinding(&self) -> Option<Action> {
+        self.rebinding
+    }
+
+    /// Binds the button to the action waiting in the controls screen
+    pub fn set_binding(&mut self, button: Button) {
+        let Some(action) = self.rebinding.take() else {return};
+        let mut binding = self.settings.bindings.get(action);
+        binding.button = button;
+        self.settings.bindings.set(action, binding);
     }
 
     pub fn update_cursor_lock(&mut self) {
@@ -60,52 +156,111 @@ impl GuiController {
 
     pub fn is_cursor(&self) -> bool { self.is_cursor }
 
-    pub fn draw_inventory(&self, ctx: &Context, player: &mut Player) -> &Self {
+    /// A text field has the keyboard focus
+    pub fn is_typing(&self) -> bool { self.is_typing }
+
+    /// Icons above the machines, projected from the world to the screen
+    pub fn draw_machine_statuses(&mut self, ctx: &Context, proj_view: &glm::Mat4, machines: &[(GlobalCoords, glm::Vec3, MachineStatus)]) -> &mut Self {
         if !self.is_ui {return self}
-        let mut task: Option<Task> = None;
+        let screen = ctx.screen_rect();
+        let painter = ctx.layer_painter(LayerId::new(Order::Background, Id::new("machine_statuses")));
+        for (coords, position, status) in machines {
+            let clip = proj_view * glm::vec4(position.x, position.y, position.z, 1.0);
+            if clip.w <= 0.0 {continue};
+            let ndc = clip.xy() / clip.w;
+            let center = pos2((ndc.x + 1.0) / 2.0 * screen.width(), (1.0 - ndc.y) / 2.0 * screen.height());
+            painter.circle(center, STATUS_ICON_RADIUS, status.color(), Stroke::new(1.0, Color32::BLACK));
+            if self.highlighted_machine == Some(*coords) {
+                painter.circle_stroke(center, STATUS_ICON_RADIUS*2.0, Stroke::new(2.0, Color32::WHITE));
+            }
+        }
+        self
+    }
+
+
+    /// Stalled machines grouped by status, a click selects the machine
+    pub fn draw_alerts(&mut self, ctx: &Context, alerts: &[Alert]) -> &mut Self {
+        if !self.is_ui || alerts.is_empty() {return self}
+        egui::Area::new("alerts_area")
+            .anchor(Align2::LEFT_TOP, vec2(10.0, 10.0))
+            .show(ctx, |ui| {
+                egui::Frame::none().fill(DEFAULT_THEME.background).inner_margin(Margin::same(5.0)).show(ui, |ui| {
+                    for group in alerts.chunk_by(|a, b| a.status == b.status) {
+                        let status = group[0].status;
+                        ui.label(RichText::new(format!("{} ({})", status.text(), group.len())).color(status.color()).strong());
+                        for alert in group.iter().take(MAX_ALERTS_IN_GROUP) {
+                            let GlobalCoords(x, y, z) = alert.coords;
+                            let text = format!("{} {} {} {}", BLOCKS()[alert.block_id as usize].name(), x, y, z);
+                            if ui.add(egui::Label::new(RichText::new(text).color(DEFAULT_THEME.on_background)).sense(egui::Sense::click())).clicked() {
+                                self.selected_alert = Some(alert.coords);
+                                self.highlighted_machine = Some(alert.coords);
+                            }
+                        }
+                    }
+                });
+            });
+        self
+    }
+
+    /// Machine of the alert clicked since the last call
+    pub fn take_selected_alert(&mut self) -> Option<GlobalCoords> {
+        self.selected_alert.take()
+    }
+
+    /// Inventory changes picked since the last call, the caller applies them
+    pub fn take_gui_actions(&mut self) -> Vec<GuiAction> {
+        std::mem::take(&mut self.gui_actions)
+    }
+
+
+    pub fn draw_inventory(&mut self, ctx: &Context, player: &mut Player) -> &Self {
+        self.is_typing = ctx.wants_keyboard_input();
+        if !self.is_ui {return self}
+        let mut task: Option<GuiAction> = None;
         let inventory = player.inventory();
-        let storage = player.open_storage.as_mut().and_then(|op| op.upgrade());
         egui::Area::new("hotbar_area")
             .anchor(Align2::CENTER_BOTTOM, vec2(1.0, -1.0))
             .show(ctx, |ui| {
                 ui.set_visible(self.is_ui);
-                let storage = player.open_storage.as_mut().and_then(|op| op.upgrade());
-                
                 ui.horizontal_top(|ui| {
                     for (i, item) in player.inventory().lock().unwrap().storage().iter().take(10).enumerate() {
-                        if ui.add(hotbar_slot(&self.items_atlas, item, player.active_slot == i)).drag_started() {
-                            if storage.is_some() {
-                                task = Some(Task::Storage(i));
-                            } else {
-                                task = Some(Task::Inventory(i));
-                            }
+                        let response = ui.add(hotbar_slot(&self.items_atlas, item, player.active_slot == i));
+                        if let Some(action) = SlotAction::from_response(ui, &response) {
+                            task = Some(GuiAction::Inventory(i, action));
                         }
                     }
                 });
             });
-        if !player.is_inventory {return self};
+        if !player.is_inventory {
+            // The closed window returns the held item in the next frame, so that the replay does it too
+            let is_held = inventory.lock().unwrap().held().0.is_some();
+            if is_held && !self.gui_actions.contains(&GuiAction::ReturnHeld) {self.gui_actions.push(GuiAction::ReturnHeld)};
+            self.gui_actions.extend(task);
+            return self;
+        };
+        self.crafting_plans.refresh(&inventory.lock().unwrap());
         egui::Area::new("inventory_area")
             .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
             .show(ctx, |ui| {
                 ui.set_visible(self.is_ui & player.is_inventory);
                 if let Some(storage) = &player.open_storage {
                     if let Some(up) = storage.upgrade() {
-                        up.lock().unwrap().draw(ui, self.items_atlas.clone(), inventory.clone());
+                        if let Some(action) = up.lock().unwrap().draw(ui, self.items_atlas.clone()) {
+                            task = Some(GuiAction::Storage(action));
+                        }
                     }
                 }
                 let inventory_len = inventory.clone().lock().unwrap().storage().len();
                 ui.horizontal(|ui| {        
                     ui.vertical(|ui| {
-                        ui.add_space(60.0);
+                        ui.add_space(30.0);
+                        if ui.button("Sort").clicked() {task = Some(GuiAction::SortInventory)};
                         for i in 1..=(inventory_len / 10) {
                             ui.horizontal(|ui| {
                                 for j in 0..std::cmp::min(inventory_len-10*i, 10) {
-                                    if ui.add(inventory_slot(&self.items_atlas, &inventory.clone().lock().unwrap().storage()[i*10 + j])).clicked() {
-                                        if storage.is_some() {
-                                            task = Some(Task::Storage(i*10 + j));
-                                        } else {
-                                            task = Some(Task::Hotbar(i*10 + j));
-                                        }
+                                    let response = ui.add(inventory_slot(&self.items_atlas, &inventory.clone().lock().unwrap().storage()[i*10 + j]));
+                                    if let Some(action) = SlotAction::from_response(ui, &response) {
+                                        task = Some(GuiAction::Inventory(i*10 + j, action));
                                     };
                                 }
                             });
@@ -115,9 +270,13 @@ impl GuiController {
                         .show(ui, |ui| {
                             ui.vertical(|ui| {
                                 ui.horizontal(|ui| {
-                                    ui.add(category_change_button());
-                                    ui.add(category_change_button());
+                                    for (name, category) in RecipeCategory::all().iter_names() {
+                                        if ui.add(category_change_button(name, self.recipe_category == category)).clicked() {
+                                            self.recipe_category = category;
+                                        }
+                                    }
                                 });
+                                ui.add(egui::TextEdit::singleline(&mut self.recipe_search).hint_text("Search").desired_width(280.0));
                                 
                                 ui.add(container(|ui| {
                                     let style = egui::Style {
@@ -125,12 +284,18 @@ impl GuiController {
                                         ..Default::default()
                                     };
                                     ui.set_style(style);
+                                    let search = self.recipe_search.to_lowercase();
+                                    let recipes = RECIPES().player.all().iter()
+                                        .filter(|r| r.category.intersects(self.recipe_category))
+                                        .filter(|r| ITEMS()[r.result.id() as usize].name.to_lowercase().contains(&search))
+                                        .collect::<Vec<_>>();
                                     ui.vertical(|ui| {
-                                        for i in 0..=(RECIPES().all.len()/5) {
+                                        for row in recipes.chunks(5) {
                                             ui.horizontal(|ui| {
-                                                for i in RECIPES().all.iter().skip(5*i).take(5) {
-                                                    if ui.add(recipe(&self.items_atlas, i)).drag_started() {
-                                                        player.inventory().lock().unwrap().start_recipe(i);
+                                                for i in row {
+                                                    let plan = self.crafting_plans.plan(&inventory.lock().unwrap(), i);
+                                                    if ui.add(recipe(&self.items_atlas, i, plan, plan.is_some())).drag_started() {
+                                                        task = Some(GuiAction::StartRecipe(i.index));
                                                     };
                                                 }
                                             });
@@ -141,17 +306,234 @@ impl GuiController {
                         });
                 });   
             });
-        if let Some(task) = task {
-            match task {
-                Task::Hotbar(i) => {inventory.lock().unwrap().place_in_hotbar(i);},
-                Task::Inventory(i) => {inventory.lock().unwrap().place_in_inventory(i);},
-                Task::Storage(i) => {
-                    let Some(item) = inventory.lock().unwrap().mut_storage()[i].0.take() else {return self};
-                    let remainder = storage.unwrap().lock().unwrap().add(&item, true);
-                    if let Some(r) = remainder {inventory.lock().unwrap().set(&r, i)}
-                },
+        if let Some(item) = inventory.lock().unwrap().held().0 {
+            let pointer = ctx.input(|i| i.pointer.hover_pos());
+            egui::Area::new("held_item_area")
+                .fixed_pos(pointer.unwrap_or_default() - vec2(25.0, 25.0))
+                .order(egui::Order::Tooltip)
+                .interactable(false)
+                .show(ctx, |ui| {ui.add(inventory_slot(&self.items_atlas, &PossibleItem(Some(item))));});
+        }
+        self.gui_actions.extend(task);
+        self
+    }
+
+
+    /// Button of the title or the pause menu pressed since the last call
+    pub fn take_menu_action(&mut self) -> Option<MenuAction> {
+        self.menu_action.take()
+    }
+
+
+    pub fn draw_pause_menu(&mut self, ctx: &Context) -> &mut Self {
+        if !self.is_menu {return self}
+        egui::Window::new(if self.is_settings {"Settings"} else {"Menu"})
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .resizable(false)
+            .collapsible(false)
+            .frame(egui::Frame::none().fill(DEFAULT_THEME.background).rounding(Rounding::same(5.0)).inner_margin(Margin::same(10.0)))
+            .show(ctx, |ui| {
+                if self.is_settings {
+                    self.draw_settings(ui);
+                    return;
+                }
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Resume")).clicked() {
+                    self.menu_action = Some(MenuAction::Resume);
+                }
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Settings")).clicked() {
+                    self.is_settings = true;
+                }
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Save")).clicked() {
+                    self.menu_action = Some(MenuAction::Save);
+                }
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Quit to title")).clicked() {
+                    self.menu_action = Some(MenuAction::QuitToTitle);
+                }
+            });
+        self
+    }
+
+
+    fn draw_settings(&mut self, ui: &mut egui::Ui) {
+        if self.is_controls {
+            self.draw_controls(ui);
+            return;
+        }
+        let settings = &mut self.settings;
+        ui.add(egui::Slider::new(&mut settings.render_distance, MIN_RENDER_DISTANCE..=MAX_RENDER_DISTANCE).step_by(2.0).text("Render distance"));
+        ui.add(egui::Slider::new(&mut settings.render_height, MIN_RENDER_HEIGHT..=MAX_RENDER_HEIGHT).text("Render height"));
+        ui.add(egui::Slider::new(&mut settings.fov, 0.5..=2.0).text("FOV"));
+        ui.add(egui::Slider::new(&mut settings.near, 0.01..=1.0).text("Near"));
+        ui.add(egui::Slider::new(&mut settings.far, 100.0..=4000.0).text("Far"));
+        ui.add(egui::Slider::new(&mut settings.sensitivity, 0.05..=1.0).text("Sensitivity"));
+        ui.add(egui::Slider::new(&mut settings.backups, 0..=MAX_BACKUPS).text("World backups"));
+        ui.horizontal(|ui| {
+            ui.colored_label(DEFAULT_THEME.on_background, "MSAA");
+            for count in MSAA_OPTIONS {
+                ui.radio_value(&mut settings.msaa, count, format!("x{}", count));
             }
+        });
+        let mut is_fullscreen = self.window.fullscreen().is_some();
+        if ui.checkbox(&mut is_fullscreen, "Fullscreen").changed() {
+            self.window.set_fullscreen(is_fullscreen.then_some(Fullscreen::Borderless(None)));
         }
+        if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Controls")).clicked() {
+            self.is_controls = true;
+        }
+        if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Apply")).clicked() {
+            self.menu_action = Some(MenuAction::ApplySettings(self.settings.clone()));
+        }
+        if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Back")).clicked() {
+            self.is_settings = false;
+        }
+    }
+
+
+    /// Bindings of the actions, applied together with the other settings
+    fn draw_controls(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            egui::Grid::new("controls").striped(true).show(ui, |ui| {
+                for action in Action::all() {
+                    let mut binding = self.settings.bindings.get(action);
+                    ui.colored_label(DEFAULT_THEME.on_background, action.name());
+                    let text = if self.rebinding == Some(action) {String::from("Press a button...")} else {binding.button.name()};
+                    if ui.add_sized([150.0, 20.0], egui::Button::new(text)).clicked() {
+                        self.rebinding = Some(action);
+                    }
+                    egui::ComboBox::from_id_source(action.name())
+                        .selected_text(binding.state_name())
+                        .show_ui(ui, |ui| {
+                            for (name, state) in STATES {
+                                ui.selectable_value(&mut binding.state, state, name);
+                            }
+                        });
+                    if binding != self.settings.bindings.get(action) {self.settings.bindings.set(action, binding)};
+                    ui.end_row();
+                }
+            });
+        });
+        if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Back")).clicked() {
+            self.is_controls = false;
+            self.rebinding = None;
+        }
+    }
+
+
+    /// World list and the creation of a new world
+    pub fn draw_title_menu(&mut self, ctx: &Context, worlds: &[WorldEntry]) -> &mut Self {
+        egui::Window::new("Manufactory")
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .resizable(false)
+            .collapsible(false)
+            .frame(egui::Frame::none().fill(DEFAULT_THEME.background).rounding(Rounding::same(5.0)).inner_margin(Margin::same(10.0)))
+            .show(ctx, |ui| {
+                if self.is_settings {
+                    self.draw_settings(ui);
+                    return;
+                }
+                ui.label(RichText::new("Worlds").color(DEFAULT_THEME.on_background).strong());
+                egui::ScrollArea::vertical().max_height(250.0).show(ui, |ui| {
+                    for world in worlds {
+                        ui.horizontal(|ui| {
+                            let metadata = &world.metadata;
+                            ui.colored_label(DEFAULT_THEME.on_background,
+                                format!("{} ({}, seed {})", metadata.name, metadata.mode.name(), metadata.seed));
+                            if ui.button("Play").clicked() {
+                                self.menu_action = Some(MenuAction::OpenWorld(world.path.clone()));
+                            }
+                            if self.delete_world.as_ref() == Some(&world.path) {
+                                if ui.button("Confirm").clicked() {
+                                    self.menu_action = Some(MenuAction::DeleteWorld(world.path.clone()));
+                                    self.delete_world = None;
+                                }
+                            } else if ui.button("Delete").clicked() {
+                                self.delete_world = Some(world.path.clone());
+                            }
+                            if !world.backups.is_empty() && ui.button(format!("Backups ({})", world.backups.len())).clicked() {
+                                let is_open = self.backups_world.as_ref() == Some(&world.path);
+                                self.backups_world = (!is_open).then(|| world.path.clone());
+                            }
+                        });
+                        if self.backups_world.as_ref() == Some(&world.path) {
+                            self.draw_backups(ui, world);
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.label(RichText::new("New world").color(DEFAULT_THEME.on_background).strong());
+                ui.horizontal(|ui| {
+                    ui.colored_label(DEFAULT_THEME.on_background, "Name");
+                    ui.text_edit_singleline(&mut self.new_world_name);
+                });
+                ui.horizontal(|ui| {
+                    ui.colored_label(DEFAULT_THEME.on_background, "Seed");
+                    ui.text_edit_singleline(&mut self.new_world_seed);
+                });
+                ui.horizontal(|ui| {
+                    for mode in GameMode::all() {
+                        ui.radio_value(&mut self.new_world_mode, mode, mode.name());
+                    }
+                });
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Create")).clicked() {
+                    self.menu_action = Some(MenuAction::CreateWorld {
+                        name: self.new_world_name.trim().to_string(),
+                        seed: parse_seed(&self.new_world_seed),
+                        mode: self.new_world_mode,
+                    });
+                }
+
+                ui.separator();
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Settings")).clicked() {
+                    self.is_settings = true;
+                }
+                if ui.add_sized(MENU_BUTTON_SIZE, egui::Button::new("Exit")).clicked() {
+                    self.menu_action = Some(MenuAction::Exit);
+                }
+            });
+        self
+    }
+
+
+    fn draw_backups(&mut self, ui: &mut egui::Ui, world: &WorldEntry) {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        ui.indent(&world.path, |ui| {
+            for backup in world.backups.iter() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(DEFAULT_THEME.on_background, format_age(now.saturating_sub(backup.time)));
+                    if self.restore_backup.as_ref() == Some(&backup.path) {
+                        if ui.button("Confirm").clicked() {
+                            self.menu_action = Some(MenuAction::RestoreBackup { world: world.path.clone(), backup: backup.clone() });
+                            self.restore_backup = None;
+                        }
+                    } else if ui.button("Restore").clicked() {
+                        self.restore_backup = Some(backup.path.clone());
+                    }
+                });
+            }
+        });
+    }
+
+
+    pub fn draw_console(&mut self, ctx: &Context, console: &mut Console) -> &mut Self {
+        if !console.is_open() {return self}
+        egui::Window::new("Console")
+            .anchor(Align2::LEFT_BOTTOM, vec2(10.0, -10.0))
+            .resizable(false)
+            .default_width(500.0)
+            .frame(egui::Frame::none().fill(DEFAULT_THEME.background).inner_margin(Margin::same(10.0)))
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().max_height(200.0).stick_to_bottom(true).show(ui, |ui| {
+                    for line in console.history() {
+                        ui.colored_label(DEFAULT_THEME.on_background, line);
+                    }
+                });
+                let response = ui.add(egui::TextEdit::singleline(&mut console.input).desired_width(f32::INFINITY));
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    console.submit();
+                }
+                response.request_focus();
+            });
         self
     }
 
@@ -175,13 +557,13 @@ impl GuiController {
             .show(ctx, |ui| {
                 ui.colored_label(DEFAULT_THEME.on_background, debug_data);
                 let button = egui::Button::new(
-                    RichText::new(format!("{}", debug_block_id.map_or(-1, |a| a as i32)))
+                    RichText::new(debug_block_id.map_or(String::from("-1"), |id| format!("{} {}", id, BLOCKS()[id as usize].name())))
                         .color(DEFAULT_THEME.on_primary))
                         .fill(DEFAULT_THEME.primary);
                 if ui.add(button).clicked() {
                     if let Some(block_id) = debug_block_id {
                         *block_id += 1;
-                        if *block_id > 17 {
+                        if *block_id as usize >= BLOCKS().len() {
                             *debug_block_id = None;
                         }
                     } else {
@@ -216,4 +598,15 @@ impl GuiController {
         }
         self
     }
-}
\ No newline at end of file
+}
+
+
+/// Age of a backup, like "5 min ago"
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => String::from("just now"),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
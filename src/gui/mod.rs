@@ -1,4 +1,6 @@
 pub mod gui_controller;
 pub mod theme;
 pub mod my_widgets;
-pub mod draw;
\ No newline at end of file
+pub mod draw;
+pub mod slot_action;
+pub mod gui_action;
\ No newline at end of file
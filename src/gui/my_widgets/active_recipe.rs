@@ -20,7 +20,7 @@ fn active_recipe_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, active_reci
         ui.painter().rect_filled(recipe_rect, 0.0, DEFAULT_THEME.background);
 
         // Paint image
-        let uv_rect = texture_atlas.uv_rect(active_recipe.recipe.result.id());
+        let uv_rect = texture_atlas.item_uv_rect(active_recipe.recipe.result.id());
         let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
             .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
         ui.put(recipe_rect, image);
@@ -14,7 +14,7 @@ const GREY: Color32 = Color32::from_rgb(50,50,50);
 fn assembling_machine_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &PossibleItem, slot_id: usize, recipe: &Recipe, result: bool) -> egui::Response {
     let mut is_hover = false;
     let desired_size = egui::vec2(WIDTH, HEIGHT);
-    let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::drag());
+    let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
 
     if response.hovered() {
         is_hover = true;
@@ -55,7 +55,7 @@ fn assembling_machine_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, i
                 |i| Some(i.id()));
         if let Some(item_id) = item_id {
             // Paint image
-            let uv_rect = texture_atlas.uv_rect(item_id);
+            let uv_rect = texture_atlas.item_uv_rect(item_id);
             let mut image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
                 .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
             if is_not_item {image = image.tint(GREY)}
@@ -7,17 +7,17 @@ const HEIGHT: f32 = 60.0;
 const STROKE_WIDTH: f32 = 2.0;
 
 
-fn category_change_button_ui(ui: &mut egui::Ui) -> egui::Response {
+fn category_change_button_ui(ui: &mut egui::Ui, text: &str, is_selected: bool) -> egui::Response {
     let mut is_hover = false;
     let desired_size = egui::vec2(WIDTH, HEIGHT);
-    let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::drag());
+    let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
 
     if response.hovered() {
         is_hover = true;
         response.mark_changed();
     }
 
-    if response.drag_started() {
+    if response.clicked() {
         response.mark_changed();
     }
 
@@ -27,11 +27,12 @@ fn category_change_button_ui(ui: &mut egui::Ui) -> egui::Response {
             max: egui::Pos2 { x: rect.left()+WIDTH, y: rect.top()+HEIGHT }
         };
         // Paint rectangle
-        let theme = if is_hover {DEFAULT_THEME.on_background} else {DEFAULT_THEME.background};
+        let theme = if is_hover || is_selected {DEFAULT_THEME.on_background} else {DEFAULT_THEME.background};
         let stroke = Stroke {color: DEFAULT_THEME.on_background, width: STROKE_WIDTH};
         let rounding = Rounding {ne: 8.0, nw: 8.0, ..Default::default()};
         ui.painter().rect(category_change_button_rect, rounding, theme, stroke);
-        let count_text = RichText::new("ALL").size(20.0).color(DEFAULT_THEME.on_background).strong();
+        let text_color = if is_hover || is_selected {DEFAULT_THEME.background} else {DEFAULT_THEME.on_background};
+        let count_text = RichText::new(text).size(20.0).color(text_color).strong();
         let label = egui::Label::new(count_text);
         ui.put(Rect {
             min: egui::Pos2 { x: rect.left(), y: rect.top() },
@@ -42,6 +43,6 @@ fn category_change_button_ui(ui: &mut egui::Ui) -> egui::Response {
 }
 
 
-pub fn category_change_button<'a>() -> impl egui::Widget+ 'a {
-    category_change_button_ui
+pub fn category_change_button(text: &str, is_selected: bool) -> impl egui::Widget + '_ {
+    move |ui: &mut egui::Ui| category_change_button_ui(ui, text, is_selected)
 }
\ No newline at end of file
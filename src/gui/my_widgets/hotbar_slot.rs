@@ -2,6 +2,8 @@ use egui::{Rect, RichText, vec2, Stroke, pos2};
 
 use crate::{gui::theme::DEFAULT_THEME, engine::texture::TextureAtlas, recipes::item::PossibleItem};
 
+use super::tooltip::item_tooltip;
+
 const WIDTH: f32 = 50.0;
 const HEIGHT: f32 = 50.0;
 const FONT_SIZE: f32 = 20.0;
@@ -11,7 +13,7 @@ const STROKE_WIDTH: f32 = 2.0;
 
 fn hotbar_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &PossibleItem, is_active: bool) -> egui::Response {
     let desired_size = egui::vec2(WIDTH, HEIGHT);
-    let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::drag());
+    let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click_and_drag());
 
     if ui.is_rect_visible(rect) {
         let left_top = rect.left_top();
@@ -23,7 +25,7 @@ fn hotbar_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &Possib
 
         if let Some(item) = item.0 {
             // Paint image
-            let uv_rect = texture_atlas.uv_rect(item.id());
+            let uv_rect = texture_atlas.item_uv_rect(item.id());
             let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
                 .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
             
@@ -40,7 +42,11 @@ fn hotbar_slot_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, item: &Possib
             }
         } 
     }
-    response
+
+    match item.0 {
+        Some(item) => response.on_hover_ui(|ui| item_tooltip(ui, item.id())),
+        None => response,
+    }
 }
 
 
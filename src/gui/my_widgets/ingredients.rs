@@ -1,8 +1,6 @@
 use egui::{Rect, pos2, vec2, RichText, Color32};
 
-use crate::{engine::texture::TextureAtlas, recipes::recipe::Recipe};
-
-use crate::gui::my_widgets::container::container;
+use crate::{engine::texture::TextureAtlas, recipes::{recipe::Recipe, item::Item, crafting_plan::CraftingPlan}};
 
 const WIDTH: f32 = 50.0;
 const HEIGHT: f32 = 50.0;
@@ -10,42 +8,49 @@ const FONT_SIZE: f32 = 25.0;
 const BOTTOM_PADDING: f32 = 3.0;
 
 
-pub fn ingredients(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe) {
-    egui::Area::new("Recipe")
-        .fixed_pos(ui.next_widget_position())
-        .order(egui::Order::Tooltip)
-        .show(ui.ctx(), |ui| {ui.add(container(|ui| {
-            let mut rect = ui.cursor();
-            rect.max = pos2(
-                rect.min.x + WIDTH,
-                rect.min.y + HEIGHT,
-            );
-
-            let left_top = rect.left_top();
-            for (i, item) in recipe.ingredients.iter().enumerate() {
-                let inventory_slot_rect = Rect::from_min_max(
-                    pos2(left_top.x+WIDTH*i as f32, left_top.y),
-                    pos2(left_top.x+WIDTH*(i+1) as f32, left_top.y+HEIGHT)
-                );
-
-                // Paint image
-                let uv_rect = texture_atlas.uv_rect(item.id());
-                let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
-                    .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
-                ui.put(inventory_slot_rect, image);
-
-                if item.count > 1 {
-                    // Paint number
-                    let count_text = RichText::new(format!("{}", item.count))
-                        .size(FONT_SIZE)
-                        .color(Color32::WHITE)
-                        .strong();
-                    let label = egui::Label::new(count_text);
-                    ui.put(Rect {
-                        min: egui::Pos2 { x: rect.left() + WIDTH*i as f32, y: rect.bottom()-FONT_SIZE-BOTTOM_PADDING },
-                        max: egui::Pos2 { x: rect.left() + WIDTH*(i+1) as f32, y: rect.bottom() }
-                    }, label);
-                }
-            }
-        }, None));});
-}
\ No newline at end of file
+/// Shows the recipe ingredients, and if there is a crafting plan,
+/// the intermediate crafts and the items taken from the inventory
+pub fn ingredients(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe, plan: Option<&CraftingPlan>) {
+    ui.vertical(|ui| {
+        items_row(ui, texture_atlas, &recipe.ingredients);
+        let Some(plan) = plan.filter(|plan| !plan.intermediates().is_empty()) else {return};
+        ui.label(RichText::new("Craft").color(Color32::WHITE));
+        let crafts = plan.intermediates().iter()
+            .map(|step| Item::new(step.recipe.result.id(), step.recipe.result.count*step.crafts)).collect::<Vec<Item>>();
+        items_row(ui, texture_atlas, &crafts);
+        ui.label(RichText::new("Uses").color(Color32::WHITE));
+        items_row(ui, texture_atlas, &plan.reserved);
+    });
+}
+
+
+fn items_row(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, items: &[Item]) {
+    let (rect, _) = ui.allocate_exact_size(vec2(WIDTH*items.len() as f32, HEIGHT), egui::Sense::hover());
+
+    let left_top = rect.left_top();
+    for (i, item) in items.iter().enumerate() {
+        let inventory_slot_rect = Rect::from_min_max(
+            pos2(left_top.x+WIDTH*i as f32, left_top.y),
+            pos2(left_top.x+WIDTH*(i+1) as f32, left_top.y+HEIGHT)
+        );
+
+        // Paint image
+        let uv_rect = texture_atlas.item_uv_rect(item.id());
+        let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(WIDTH, HEIGHT)))
+            .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
+        ui.put(inventory_slot_rect, image);
+
+        if item.count > 1 {
+            // Paint number
+            let count_text = RichText::new(format!("{}", item.count))
+                .size(FONT_SIZE)
+                .color(Color32::WHITE)
+                .strong();
+            let label = egui::Label::new(count_text);
+            ui.put(Rect {
+                min: egui::Pos2 { x: rect.left() + WIDTH*i as f32, y: rect.bottom()-FONT_SIZE-BOTTOM_PADDING },
+                max: egui::Pos2 { x: rect.left() + WIDTH*(i+1) as f32, y: rect.bottom() }
+            }, label);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use egui::RichText;
+
+use crate::voxels::voxel_data::machine_status::MachineStatus;
+
+const WIDTH: f32 = 200.0;
+
+
+fn machine_status_ui(ui: &mut egui::Ui, status: MachineStatus, progress: f32) -> egui::Response {
+    ui.vertical(|ui| {
+        ui.label(RichText::new(status.text()).color(status.color()).strong());
+        ui.add(egui::ProgressBar::new(progress).desired_width(WIDTH));
+    }).response
+}
+
+
+pub fn machine_status(status: MachineStatus, progress: f32) -> impl egui::Widget {
+    move |ui: &mut egui::Ui| machine_status_ui(ui, status, progress)
+}
@@ -5,4 +5,6 @@ pub mod container;
 pub mod hotbar_slot;
 pub mod active_recipe;
 pub mod assembling_machine_slot;
-pub mod ingredients;
\ No newline at end of file
+pub mod ingredients;
+pub mod tooltip;
+pub mod machine_status;
\ No newline at end of file
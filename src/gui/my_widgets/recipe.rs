@@ -1,17 +1,19 @@
-use egui::{Rect, vec2, Stroke, pos2};
+use egui::{Rect, vec2, Stroke, pos2, Color32};
 
-use crate::{gui::theme::DEFAULT_THEME, recipes::recipe::Recipe, engine::texture::TextureAtlas};
+use crate::{gui::theme::DEFAULT_THEME, recipes::{recipe::Recipe, crafting_plan::CraftingPlan}, engine::texture::TextureAtlas};
 
-use super::ingredients::ingredients;
+use super::tooltip::recipe_tooltip;
 
 const WIDTH: f32 = 50.0;
 const HEIGHT: f32 = 50.0;
 const STROKE_WIDTH: f32 = 2.0;
 const PADDING: f32 = 3.0;
+const GREY: Color32 = Color32::from_rgb(80, 80, 80);
 const IMAGE_WIDTH: f32 = WIDTH - PADDING*2.0;
 const IMAGE_HEIGHT: f32 = HEIGHT - PADDING*2.0;
 
-fn recipe_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe) -> egui::Response {
+/// Not available recipes are greyed out
+fn recipe_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe, plan: Option<&CraftingPlan>, available: bool) -> egui::Response {
     let mut is_hover = false;
     let desired_size = egui::vec2(WIDTH, HEIGHT);
     let (rect, mut response) = ui.allocate_exact_size(desired_size, egui::Sense::drag());
@@ -36,20 +38,17 @@ fn recipe_ui(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe) -
         // Paint image
         recipe_rect.min = pos2(recipe_rect.min.x+PADDING, recipe_rect.min.y+PADDING);
         recipe_rect.max = pos2(recipe_rect.max.x-PADDING, recipe_rect.max.y-PADDING);
-        let uv_rect = texture_atlas.uv_rect(recipe.result.id());
+        let uv_rect = texture_atlas.item_uv_rect(recipe.result.id());
         let image = egui::Image::new(egui::load::SizedTexture::new(texture_atlas.texture_id, vec2(IMAGE_WIDTH, IMAGE_HEIGHT)))
-            .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)));
+            .uv(Rect::from_min_max(pos2(uv_rect.0, uv_rect.1), pos2(uv_rect.2, uv_rect.3)))
+            .tint(if available {Color32::WHITE} else {GREY});
         ui.put(recipe_rect, image);
     }
 
-    if is_hover {
-        ingredients(ui, texture_atlas, recipe);
-    }
-
-    response
+    response.on_hover_ui(|ui| recipe_tooltip(ui, texture_atlas, recipe, plan))
 }
 
 
-pub fn recipe<'a>(texture_atlas: &'a TextureAtlas, recipe: &'a Recipe) -> impl egui::Widget + 'a {
-    move |ui: &mut egui::Ui| recipe_ui(ui, texture_atlas, recipe)
+pub fn recipe<'a>(texture_atlas: &'a TextureAtlas, recipe: &'a Recipe, plan: Option<&'a CraftingPlan>, available: bool) -> impl egui::Widget + 'a {
+    move |ui: &mut egui::Ui| recipe_ui(ui, texture_atlas, recipe, plan, available)
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use egui::RichText;
+
+use crate::{engine::texture::TextureAtlas, recipes::{recipe::{Recipe, RecipeCrafter}, recipes::RECIPES, items::ITEMS, item::Item, crafting_plan::CraftingPlan}};
+
+use super::ingredients::ingredients;
+
+const CRAFTERS: [(RecipeCrafter, &str); 3] = [
+    (RecipeCrafter::PLAYER, "Player"),
+    (RecipeCrafter::ASSEMBLER, "Assembling machine"),
+    (RecipeCrafter::FURNACE, "Furnace"),
+];
+
+
+fn item_name(item_id: u32) -> &'static str {
+    &ITEMS()[item_id as usize].name
+}
+
+fn recipe_names<'a>(recipes: impl Iterator<Item = &'a Recipe>) -> String {
+    recipes.map(|recipe| item_name(recipe.result.id())).collect::<Vec<_>>().join(", ")
+}
+
+fn items_text(items: &[Item]) -> String {
+    items.iter().map(|item| format!("{} x{}", item_name(item.id()), item.count)).collect::<Vec<_>>().join(", ")
+}
+
+
+/// Name, stack size and the recipes that produce or use the item
+pub fn item_tooltip(ui: &mut egui::Ui, item_id: u32) {
+    let item_type = &ITEMS()[item_id as usize];
+    ui.label(RichText::new(&item_type.name).strong());
+    if !item_type.description.is_empty() {ui.label(&item_type.description);}
+    ui.label(format!("Stack size: {}", item_type.stack_size));
+
+    let all = RECIPES().all;
+    let produced = recipe_names(all.iter().filter(|recipe| recipe.result.id() == item_id));
+    if !produced.is_empty() {ui.label(format!("Produced by: {}", produced));}
+    let used = recipe_names(all.iter().filter(|recipe| recipe.ingredients.iter().any(|item| item.id() == item_id)));
+    if !used.is_empty() {ui.label(format!("Used in: {}", used));}
+}
+
+
+/// Ingredients, result, duration and crafters of the recipe, with the crafting plan if there is one
+pub fn recipe_tooltip(ui: &mut egui::Ui, texture_atlas: &TextureAtlas, recipe: &Recipe, plan: Option<&CraftingPlan>) {
+    ui.label(RichText::new(items_text(&[recipe.result])).strong());
+    ui.label(format!("Ingredients: {}", items_text(&recipe.ingredients)));
+    ui.label(format!("Time: {:.1} s", recipe.duration.as_secs_f32()));
+    let crafters = CRAFTERS.iter()
+        .filter(|(crafter, _)| recipe.crafter.intersects(*crafter))
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>();
+    ui.label(format!("Crafted by: {}", crafters.join(", ")));
+    ingredients(ui, texture_atlas, recipe, plan);
+}
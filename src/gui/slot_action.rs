@@ -0,0 +1,60 @@
+use std::sync::Mutex;
+
+use egui::PointerButton;
+
+use crate::{recipes::{storage::Storage, item::PossibleItem}, player::inventory::PlayerInventory};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotAction {
+    /// Press: pick up the stack or put the held one
+    Pick,
+    /// Release over a slot after dragging from another one
+    Drop,
+    /// Right click: pick up half of the stack or put one held item
+    Split,
+    /// Shift click: move the stack to the other container
+    Transfer,
+}
+
+
+impl SlotAction {
+    pub fn from_response(ui: &egui::Ui, response: &egui::Response) -> Option<Self> {
+        if response.drag_started_by(PointerButton::Primary) {
+            let shift = ui.input(|i| i.modifiers.shift);
+            return Some(if shift {Self::Transfer} else {Self::Pick});
+        }
+        if response.secondary_clicked() {return Some(Self::Split)};
+
+        let released = ui.input(|i| i.pointer.primary_released());
+        if released && !response.drag_released() && ui.rect_contains_pointer(response.rect) {
+            return Some(Self::Drop);
+        }
+        None
+    }
+
+
+    /// Applies the action to the held item, transfer is left to the caller
+    pub fn apply(self, storage: &mut dyn Storage, index: usize, held: &mut PossibleItem) {
+        match self {
+            Self::Pick => storage.place_held(index, held),
+            Self::Drop => if held.0.is_some() {storage.place_held(index, held)},
+            Self::Split => storage.split_held(index, held),
+            Self::Transfer => (),
+        }
+    }
+}
+
+
+/// Handles an action on a slot of an open container, transfer moves the stack to the inventory
+pub fn container_slot_action(action: SlotAction, storage: &mut dyn Storage, index: usize, inventory: &Mutex<PlayerInventory>) {
+    if index >= storage.storage().len() {return};
+    let mut inventory = inventory.lock().unwrap();
+    if action == SlotAction::Transfer {
+        let Some(item) = storage.mut_storage()[index].0.take() else {return};
+        if let Some(r) = inventory.add(&item, true) {storage.set(&r, index)}
+        return;
+    }
+    let mut held = inventory.take_held();
+    action.apply(storage, index, &mut held);
+    inventory.set_held(held);
+}
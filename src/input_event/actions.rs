@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use crate::definitions::{Definition, DefinitionError};
+
+use super::{KeypressState, input_service::{Key, Mouse}};
+
+
+/// Keys that can be bound, the names are the `Debug` names of the keys
+pub const KEYS: [Key; 78] = [
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9, Key::Key0,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Escape, Key::Grave, Key::Tab, Key::Space, Key::Return, Key::Back, Key::Delete, Key::Insert,
+    Key::Home, Key::End, Key::PageUp, Key::PageDown, Key::Up, Key::Down, Key::Left, Key::Right,
+    Key::LShift, Key::RShift, Key::LControl, Key::RControl, Key::LAlt, Key::RAlt,
+    Key::Minus, Key::Equals, Key::LBracket, Key::RBracket, Key::Semicolon, Key::Apostrophe, Key::Comma, Key::Period,
+];
+
+/// States a binding can wait for, with their names in the settings file
+pub const STATES: [(&str, KeypressState); 7] = [
+    ("just", KeypressState::AnyJustPress),
+    ("hold", KeypressState::AnyStayPress),
+    ("press", KeypressState::AnyPress),
+    ("double", KeypressState::JustDoublePressed),
+    ("double_hold", KeypressState::AnyDoublePress),
+    ("triple", KeypressState::JustTriplePressed),
+    ("release", KeypressState::JustReleased),
+];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Break,
+    Place,
+    Rotate,
+    OpenInventory,
+    Console,
+    Pause,
+    ToggleUi,
+    Fullscreen,
+    HotbarSlot1,
+    HotbarSlot2,
+    HotbarSlot3,
+    HotbarSlot4,
+    HotbarSlot5,
+    HotbarSlot6,
+    HotbarSlot7,
+    HotbarSlot8,
+    HotbarSlot9,
+    HotbarSlot10,
+}
+
+impl Action {
+    pub const HOTBAR: [Action; 10] = [
+        Action::HotbarSlot1, Action::HotbarSlot2, Action::HotbarSlot3, Action::HotbarSlot4, Action::HotbarSlot5,
+        Action::HotbarSlot6, Action::HotbarSlot7, Action::HotbarSlot8, Action::HotbarSlot9, Action::HotbarSlot10];
+
+    pub fn all() -> [Action; 22] {
+        use Action::*;
+        [MoveForward, MoveBack, MoveLeft, MoveRight, Break, Place, Rotate, OpenInventory, Console, Pause, ToggleUi, Fullscreen,
+            HotbarSlot1, HotbarSlot2, HotbarSlot3, HotbarSlot4, HotbarSlot5, HotbarSlot6, HotbarSlot7, HotbarSlot8, HotbarSlot9, HotbarSlot10]
+    }
+
+    /// Key in the settings file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBack => "move_back",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Break => "break",
+            Action::Place => "place",
+            Action::Rotate => "rotate",
+            Action::OpenInventory => "open_inventory",
+            Action::Console => "console",
+            Action::Pause => "pause",
+            Action::ToggleUi => "toggle_ui",
+            Action::Fullscreen => "fullscreen",
+            Action::HotbarSlot1 => "hotbar_slot_1",
+            Action::HotbarSlot2 => "hotbar_slot_2",
+            Action::HotbarSlot3 => "hotbar_slot_3",
+            Action::HotbarSlot4 => "hotbar_slot_4",
+            Action::HotbarSlot5 => "hotbar_slot_5",
+            Action::HotbarSlot6 => "hotbar_slot_6",
+            Action::HotbarSlot7 => "hotbar_slot_7",
+            Action::HotbarSlot8 => "hotbar_slot_8",
+            Action::HotbarSlot9 => "hotbar_slot_9",
+            Action::HotbarSlot10 => "hotbar_slot_10",
+        }
+    }
+
+    fn default_binding(&self) -> Binding {
+        let (just, hold, press) = (KeypressState::AnyJustPress, KeypressState::AnyStayPress, KeypressState::AnyPress);
+        let key = |key, state| Binding { button: Button::Key(key), state };
+        match self {
+            Action::MoveForward => key(Key::W, hold),
+            Action::MoveBack => key(Key::S, hold),
+            Action::MoveLeft => key(Key::A, hold),
+            Action::MoveRight => key(Key::D, hold),
+            Action::Break => Binding { button: Button::Mouse(Mouse::Left), state: press },
+            Action::Place => Binding { button: Button::Mouse(Mouse::Right), state: just },
+            Action::Rotate => key(Key::R, just),
+            Action::OpenInventory => key(Key::E, just),
+            Action::Console => key(Key::Grave, just),
+            Action::Pause => key(Key::Escape, just),
+            Action::ToggleUi => key(Key::F1, just),
+            Action::Fullscreen => key(Key::F11, just),
+            Action::HotbarSlot1 => key(Key::Key1, press),
+            Action::HotbarSlot2 => key(Key::Key2, press),
+            Action::HotbarSlot3 => key(Key::Key3, press),
+            Action::HotbarSlot4 => key(Key::Key4, press),
+            Action::HotbarSlot5 => key(Key::Key5, press),
+            Action::HotbarSlot6 => key(Key::Key6, press),
+            Action::HotbarSlot7 => key(Key::Key7, press),
+            Action::HotbarSlot8 => key(Key::Key8, press),
+            Action::HotbarSlot9 => key(Key::Key9, press),
+            Action::HotbarSlot10 => key(Key::Key0, press),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Button {
+    Key(Key),
+    Mouse(Mouse),
+}
+
+impl Button {
+    pub fn name(&self) -> String {
+        match self {
+            Button::Key(key) => format!("{:?}", key),
+            Button::Mouse(Mouse::Other(id)) => format!("Mouse{}", id),
+            Button::Mouse(mouse) => format!("Mouse{:?}", mouse),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(mouse) = name.strip_prefix("Mouse") {
+            return match mouse {
+                "Left" => Some(Button::Mouse(Mouse::Left)),
+                "Right" => Some(Button::Mouse(Mouse::Right)),
+                "Middle" => Some(Button::Mouse(Mouse::Middle)),
+                id => id.parse().ok().map(|id| Button::Mouse(Mouse::Other(id))),
+            };
+        }
+        KEYS.into_iter().find(|key| format!("{:?}", key) == name).map(Button::Key)
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binding {
+    pub button: Button,
+    pub state: KeypressState,
+}
+
+impl Binding {
+    pub fn state_name(&self) -> &'static str {
+        STATES.iter().find(|(_, state)| *state == self.state).map_or("press", |(name, _)| name)
+    }
+
+    /// Text in the settings file, the button and the state
+    pub fn to_text(self) -> String {
+        format!("{} {}", self.button.name(), self.state_name())
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        let mut words = text.split_whitespace();
+        let button = Button::from_name(words.next()?)?;
+        let state = match words.next() {
+            Some(name) => STATES.iter().find(|(n, _)| *n == name)?.1,
+            None => KeypressState::AnyJustPress,
+        };
+        Some(Self { button, state })
+    }
+}
+
+
+/// Binding table of the actions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    bindings: HashMap<Action, Binding>,
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> Binding {
+        self.bindings.get(&action).copied().unwrap_or_else(|| action.default_binding())
+    }
+
+    pub fn set(&mut self, action: Action, binding: Binding) {
+        self.bindings.insert(action, binding);
+    }
+
+    /// Missing actions keep the default bindings
+    /// The bad bindings keep the defaults and are added to the errors
+    pub fn parse(definition: &Definition, errors: &mut Vec<DefinitionError>) -> Self {
+        let mut bindings = Self::default();
+        for (key, value) in definition.values.iter() {
+            let Some(action) = Action::all().into_iter().find(|a| a.name() == key) else {
+                errors.push(definition.error(format!("unknown action \"{}\"", key)));
+                continue;
+            };
+            let Some(binding) = Binding::from_text(value) else {
+                errors.push(definition.invalid_value(key, value));
+                continue;
+            };
+            bindings.set(action, binding);
+        }
+        bindings
+    }
+
+    pub fn to_text(&self) -> String {
+        Action::all().iter()
+            .map(|action| format!("{} = {}\n", action.name(), self.get(*action).to_text()))
+            .collect()
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self { bindings: Action::all().into_iter().map(|action| (action, action.default_binding())).collect() }
+    }
+}
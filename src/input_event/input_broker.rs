@@ -43,6 +43,20 @@ impl InputBroker {
         index < INPUT_LENGTH && self.keys[index].state.is(state)
     }
 
+    /// Button pressed since the last update
+    pub(super) fn just_pressed(&self) -> Option<usize> {
+        self.prev_id.filter(|id| self.keys[*id].state.is(KeypressState::AnyJustPress))
+    }
+
+    pub(super) fn state(&self, index: usize) -> KeypressState {
+        self.keys[index].state
+    }
+
+    /// Sets a state without the press timing, used to replay the recorded states
+    pub(super) fn set_state(&mut self, index: usize, state: KeypressState) {
+        if let Some(key) = self.keys.get_mut(index) {key.state = state};
+    }
+
     pub(super) fn update_delta_mouse(&mut self) {
         self.delta = (0.0, 0.0);
     }
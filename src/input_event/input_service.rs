@@ -1,6 +1,7 @@
 use winit::event::{Event, WindowEvent, ElementState, DeviceEvent, MouseScrollDelta};
 
-use super::{input_broker::InputBroker, KeypressState, InputOffset};
+use super::{input_broker::InputBroker, KeypressState, InputOffset, actions::{Action, Bindings, Button, KEYS}, recording::{InputRecorder, InputReplay, FrameTime, RecordingError}};
+use crate::gui::gui_action::GuiAction;
 
 pub type Key = winit::event::VirtualKeyCode;
 pub type Mouse = winit::event::MouseButton;
@@ -9,11 +10,14 @@ pub type Mouse = winit::event::MouseButton;
 #[derive(Debug)]
 pub struct InputService {
     input_broker: InputBroker,
+    bindings: Bindings,
+    recorder: Option<InputRecorder>,
+    replay: Option<InputReplay>,
 }
 
 
 impl InputService {
-    pub fn new() -> Self { Self { input_broker: InputBroker::new() } }
+    pub fn new(bindings: Bindings) -> Self { Self { input_broker: InputBroker::new(), bindings, recorder: None, replay: None } }
     pub fn delta(&self) -> &(f32, f32) { &self.input_broker.delta }
     pub fn coords(&self) -> &(f32, f32) { &self.input_broker.coords }
 
@@ -34,6 +38,82 @@ impl InputService {
         self.input_broker.is_button(Self::to_mouse_id(mouse), state)
     }
 
+    pub fn is_action(&self, action: Action) -> bool {
+        let binding = self.bindings.get(action);
+        match binding.button {
+            Button::Key(key) => self.is_key(&key, binding.state),
+            Button::Mouse(mouse) => self.is_mouse(&mouse, binding.state),
+        }
+    }
+
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    /// Bindable button pressed in this frame
+    pub fn just_pressed_button(&self) -> Option<Button> {
+        let id = self.input_broker.just_pressed()?;
+        if id >= InputOffset::Mouse as usize {
+            return Some(Button::Mouse(match id - InputOffset::Mouse as usize {
+                0 => Mouse::Left,
+                1 => Mouse::Right,
+                2 => Mouse::Middle,
+                other => Mouse::Other((other - 3) as u16),
+            }));
+        }
+        KEYS.into_iter().find(|key| *key as usize + InputOffset::Key as usize == id).map(Button::Key)
+    }
+
+    pub fn start_recording(&mut self, recorder: InputRecorder) {
+        self.stop_recording();
+        self.recorder = Some(recorder);
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {recorder.finish()};
+    }
+
+    /// The window input is ignored until the replay is over
+    pub fn start_replay(&mut self, replay: InputReplay) {
+        self.input_broker = InputBroker::new();
+        self.replay = Some(replay);
+    }
+
+    pub fn stop_replay(&mut self) {
+        if self.replay.take().is_some() {self.input_broker = InputBroker::new()};
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Stops the replay when the opened world doesn't have the seed of the recording
+    pub fn check_replay_seed(&mut self, world: u64) -> Result<(), RecordingError> {
+        let Some(replay) = &self.replay else {return Ok(())};
+        let result = replay.check_seed(world);
+        if result.is_err() {self.stop_replay()};
+        result
+    }
+
+    /// Records the input and the GUI actions of the frame or replaces them with the replayed ones,
+    /// returns the time the frame runs with and the GUI actions to apply
+    pub fn sync_frame(&mut self, time: FrameTime, gui_actions: Vec<GuiAction>) -> (FrameTime, Vec<GuiAction>) {
+        if let Some(replay) = self.replay.as_mut() {
+            if let Some(frame) = replay.next_frame(&mut self.input_broker) {return frame};
+            println!("Replay finished");
+            self.replay = None;
+            self.input_broker = InputBroker::new();
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(&self.input_broker, time, &gui_actions);
+        }
+        (time, gui_actions)
+    }
+
     pub fn update_delta_mouse(&mut self) {
         self.input_broker.update_delta_mouse();
     }
@@ -47,6 +127,7 @@ impl InputService {
     }
 
     pub fn handle_event(&mut self, event: &Event<'_, ()>) {
+        if self.replay.is_some() {return};
         match event {
             Event::WindowEvent { event, .. } => {
                 match event {
@@ -80,6 +161,6 @@ impl InputService {
 
 impl Default for InputService {
     fn default() -> Self {
-        Self::new()
+        Self::new(Bindings::default())
     }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 pub mod input_broker;
 pub mod input_service;
+pub mod actions;
+pub mod recording;
 
 use bitflags::bitflags;
 
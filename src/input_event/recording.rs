@@ -0,0 +1,214 @@
+use std::{collections::VecDeque, fmt::Display, fs::{self, File}, io::{self, BufWriter, Write}, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{bytes::{AsFromBytes, DecodeError, slice_bytes}, definitions::{Definitions, DefinitionError}, save_load::{copy_world, MetadataSave}, gui::gui_action::{GuiAction, GUI_ACTION_SIZE}};
+
+use super::{input_broker::InputBroker, KeypressState, INPUT_LENGTH};
+
+pub const RECORDINGS_PATH: &str = "./data/recordings/";
+
+const RECORDING_FILE: &str = "recording.txt";
+const INPUT_FILE: &str = "input.bin";
+const WORLD_DIR: &str = "world";
+/// Version 2 adds the GUI actions to the frames
+const RECORDING_VERSION: u32 = 2;
+
+/// Frame time, ticks, mouse delta and coords, wheel, the number of states and of GUI actions
+const FRAME_HEADER_SIZE: usize = 4 + 8 + 4*4 + 1 + 2 + 2;
+const FRAME_STATE_SIZE: usize = 3;
+
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Definition(DefinitionError),
+    SeedMismatch {recorded: u64, world: u64},
+}
+
+impl Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Definition(e) => write!(f, "{}", e),
+            Self::SeedMismatch { recorded, world } => write!(f, "the recording has the seed {}, the world has {}", recorded, world),
+        }
+    }
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(value: io::Error) -> Self {Self::Io(value)}
+}
+
+impl From<DefinitionError> for RecordingError {
+    fn from(value: DefinitionError) -> Self {Self::Definition(value)}
+}
+
+
+/// Time of a frame, recorded together with the input
+#[derive(Debug, Clone, Copy)]
+pub struct FrameTime {
+    /// Seconds since the previous frame
+    pub delta: f32,
+    /// Simulation ticks done before the frame
+    pub ticks: u64,
+}
+
+
+#[derive(Debug, Clone)]
+struct InputFrame {
+    time: FrameTime,
+    delta: (f32, f32),
+    coords: (f32, f32),
+    wheel: i8,
+    /// Buttons whose state changed since the previous frame
+    states: Vec<(u16, KeypressState)>,
+    /// Picked in the GUI in the previous frame, egui reads the window directly so it can't replay them
+    gui_actions: Vec<GuiAction>,
+}
+
+impl InputFrame {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend(self.time.delta.as_bytes());
+        bytes.extend(self.time.ticks.as_bytes());
+        [self.delta.0, self.delta.1, self.coords.0, self.coords.1].iter().for_each(|v| bytes.extend(v.as_bytes()));
+        bytes.extend(self.wheel.as_bytes());
+        bytes.extend((self.states.len() as u16).as_bytes());
+        bytes.extend((self.gui_actions.len() as u16).as_bytes());
+        self.states.iter().for_each(|(id, state)| {
+            bytes.extend(id.as_bytes());
+            bytes.push(state.bits());
+        });
+        self.gui_actions.iter().for_each(|action| action.encode(bytes));
+    }
+
+    /// The frame and its size in bytes
+    fn decode(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        let header = slice_bytes(bytes, 0..FRAME_HEADER_SIZE)?;
+        let states_end = FRAME_HEADER_SIZE + u16::from_bytes_at(header, 29)? as usize * FRAME_STATE_SIZE;
+        let size = states_end + u16::from_bytes_at(header, 31)? as usize * GUI_ACTION_SIZE;
+        let states = slice_bytes(bytes, FRAME_HEADER_SIZE..states_end)?.chunks_exact(FRAME_STATE_SIZE)
+            .map(|state| Ok((u16::from_bytes_at(state, 0)?, KeypressState::from_bits_retain(state[2]))))
+            .collect::<Result<Vec<(u16, KeypressState)>, DecodeError>>()?;
+        let gui_actions = slice_bytes(bytes, states_end..size)?.chunks_exact(GUI_ACTION_SIZE)
+            .map(GuiAction::decode)
+            .collect::<Result<Vec<GuiAction>, DecodeError>>()?;
+        Ok((Self {
+            time: FrameTime { delta: f32::from_bytes_at(header, 0)?, ticks: u64::from_bytes_at(header, 4)? },
+            delta: (f32::from_bytes_at(header, 12)?, f32::from_bytes_at(header, 16)?),
+            coords: (f32::from_bytes_at(header, 20)?, f32::from_bytes_at(header, 24)?),
+            wheel: header[28] as i8,
+            states,
+            gui_actions,
+        }, size))
+    }
+}
+
+
+/// Writes the input of every frame next to a copy of the world it starts in
+#[derive(Debug)]
+pub struct InputRecorder {
+    path: PathBuf,
+    file: BufWriter<File>,
+    previous: Vec<KeypressState>,
+    frames: u64,
+}
+
+impl InputRecorder {
+    /// Copies the world, it must be saved and not opened yet
+    pub fn start(world_path: &Path) -> Result<Self, RecordingError> {
+        let metadata = MetadataSave::new(world_path.to_path_buf()).load_metadata()?;
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let dir_name = world_path.file_name().map_or(String::from("world"), |name| name.to_string_lossy().to_string());
+        let path = PathBuf::from(RECORDINGS_PATH).join(format!("{}_{}", dir_name, time));
+        copy_world(world_path, &path.join(WORLD_DIR))?;
+        fs::write(path.join(RECORDING_FILE), format!("[recording]\nversion = {}\nworld = {}\nseed = {}\n", RECORDING_VERSION, metadata.name, metadata.seed))?;
+        Ok(Self {
+            file: BufWriter::new(File::create(path.join(INPUT_FILE))?),
+            path,
+            previous: vec![KeypressState::default(); INPUT_LENGTH],
+            frames: 0,
+        })
+    }
+
+    pub(super) fn record(&mut self, broker: &InputBroker, time: FrameTime, gui_actions: &[GuiAction]) {
+        let states = (0..INPUT_LENGTH)
+            .filter(|i| broker.state(*i) != self.previous[*i])
+            .map(|i| (i as u16, broker.state(i)))
+            .collect::<Vec<(u16, KeypressState)>>();
+        states.iter().for_each(|(i, state)| self.previous[*i as usize] = *state);
+        let frame = InputFrame { time, delta: broker.delta, coords: broker.coords, wheel: broker.wheel(), states, gui_actions: gui_actions.to_vec() };
+        let mut bytes = vec![];
+        frame.encode(&mut bytes);
+        if let Err(err) = self.file.write_all(&bytes) {
+            eprintln!("Input recording write error: {}", err);
+        }
+        self.frames += 1;
+    }
+
+    pub fn finish(mut self) {
+        match self.file.flush() {
+            Ok(()) => println!("Recorded {} frames to {}", self.frames, self.path.display()),
+            Err(err) => eprintln!("Input recording write error: {}", err),
+        }
+    }
+}
+
+
+/// Recorded input fed back instead of the window events
+#[derive(Debug)]
+pub struct InputReplay {
+    seed: u64,
+    frames: VecDeque<InputFrame>,
+}
+
+impl InputReplay {
+    /// Loads the input and copies the recorded world, which the replay has to start in, to the temporary directory
+    pub fn load(path: &Path) -> Result<(Self, PathBuf), RecordingError> {
+        let definitions = Definitions::load(path.join(RECORDING_FILE))?;
+        let recording = definitions.sections.iter().find(|d| d.key == "recording")
+            .ok_or_else(|| definitions.root.error("missing section \"recording\""))?;
+        let version = recording.parse::<u32>("version")?.unwrap_or(1);
+        if version != RECORDING_VERSION {
+            return Err(recording.error(format!("unsupported recording version {}", version)).into());
+        }
+        let seed = recording.parse_required::<u64>("seed")?;
+
+        let bytes = fs::read(path.join(INPUT_FILE))?;
+        let mut frames = VecDeque::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let (frame, size) = match InputFrame::decode(&bytes[offset..]) {
+                Ok(frame) => frame,
+                Err(err) => {
+                    eprintln!("Input recording is truncated after {} frames: {}", frames.len(), err);
+                    break;
+                },
+            };
+            frames.push_back(frame);
+            offset += size;
+        }
+
+        let name = format!("manufactory_replay_{}", path.file_name().map_or(String::new(), |name| name.to_string_lossy().to_string()));
+        let world_path = std::env::temp_dir().join(name);
+        if world_path.exists() {fs::remove_dir_all(&world_path)?};
+        copy_world(&path.join(WORLD_DIR), &world_path)?;
+        Ok((Self { seed, frames }, world_path))
+    }
+
+    /// The opened world must have the seed of the recording
+    pub fn check_seed(&self, world: u64) -> Result<(), RecordingError> {
+        if self.seed != world {
+            return Err(RecordingError::SeedMismatch { recorded: self.seed, world });
+        }
+        Ok(())
+    }
+
+    /// Applies the states of the next frame, None when the replay is over
+    pub(super) fn next_frame(&mut self, broker: &mut InputBroker) -> Option<(FrameTime, Vec<GuiAction>)> {
+        let frame = self.frames.pop_front()?;
+        frame.states.iter().for_each(|(id, state)| broker.set_state(*id as usize, *state));
+        broker.set_delta(frame.delta.0, frame.delta.1);
+        broker.set_coords(frame.coords.0, frame.coords.1);
+        broker.set_wheel(frame.wheel);
+        Some((frame.time, frame.gui_actions))
+    }
+}
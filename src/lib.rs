@@ -0,0 +1,24 @@
+pub mod input_event;
+pub mod my_time;
+pub mod voxels;
+pub mod graphic;
+pub mod light;
+pub mod meshes;
+pub mod camera;
+pub mod gui;
+pub mod recipes;
+pub mod player;
+pub mod models;
+pub mod direction;
+pub mod world;
+pub mod macros;
+pub mod threads;
+pub mod engine;
+pub mod save_load;
+pub mod bytes;
+pub mod definitions;
+pub mod console;
+pub mod session;
+pub mod settings;
+#[cfg(test)]
+mod test_utils;
@@ -1,6 +1,6 @@
 use itertools::iproduct;
 
-use crate::{voxels::{chunks::{Chunks, WORLD_HEIGHT}, chunk::CHUNK_SIZE, block::blocks::BLOCKS}, world::chunk_coords::ChunkCoords};
+use crate::{voxels::{chunks::Chunks, chunk::CHUNK_SIZE, block::blocks::BLOCKS}, world::chunk_coords::ChunkCoords};
 
 use super::light_solver::LightSolver;
 const MAX_LIGHT: u16 = 15;
@@ -30,10 +30,12 @@ impl LightSolvers {
 
     pub fn build_sky_light_chunk(&mut self, chunks: &mut Chunks, cx: i32, cy: i32, cz: i32) {
         let chunks_ptr = chunks as *mut Chunks;
+        // The sun only enters the world at its top, chunks under an unloaded chunk get it from above later
+        let top = chunks.heights.top;
         let Some(chunk) = chunks.mut_chunk(ChunkCoords(cx, cy, cz)) else {return};
         let max_y = (CHUNK_SIZE-1) as u8;
 
-        if cy == (WORLD_HEIGHT-1) as i32 {
+        if cy == top-1 {
             for (lz, lx) in iproduct!(0..CHUNK_SIZE as u8, 0..CHUNK_SIZE as u8) {
                 chunk.lightmap.set_sun((lx, max_y, lz), 15);
             }
@@ -57,15 +59,36 @@ impl LightSolvers {
             }
         }
         self.solver_sun.solve(chunks);
+        if let Some(chunk) = chunks.mut_chunk(ChunkCoords(cx, cy, cz)) {chunk.lightmap.compact()};
+    }
+
+
+    /// Lights the loaded chunks under the chunk again while its sun reaches them.
+    /// The chunks of a column are loaded from the top, so this happens after the loaded area moves up
+    pub fn build_sky_light_below(&mut self, chunks: &mut Chunks, cx: i32, cy: i32, cz: i32) {
+        let mut cy = cy;
+        while Self::is_sun_going_down(chunks, cx, cy, cz) {
+            cy -= 1;
+            self.build_sky_light_chunk(chunks, cx, cy, cz);
+        }
+    }
+
+
+    fn is_sun_going_down(chunks: &Chunks, cx: i32, cy: i32, cz: i32) -> bool {
+        let (Some(chunk), Some(below)) = (chunks.chunk(ChunkCoords(cx, cy, cz)), chunks.chunk(ChunkCoords(cx, cy-1, cz))) else {return false};
+        iproduct!(0..CHUNK_SIZE as u8, 0..CHUNK_SIZE as u8).any(|(lz, lx)| {
+            chunk.lightmap.get_sun((lx, 0, lz)) == 15 && below.lightmap.get_sun((lx, (CHUNK_SIZE-1) as u8, lz)) != 15
+        })
     }
 
 
     pub fn build_sky_light(&mut self, chunks: &mut Chunks) {
+        let top = chunks.heights.top;
         for (cy, cz, cx) in iproduct!((0..chunks.height).rev(), 0..chunks.depth, 0..chunks.width) {
             let chunks_ptr = chunks as *mut Chunks;
             let Some(chunk) = chunks.mut_local_chunk(ChunkCoords(cx, cy, cz)) else {continue};
 
-            if chunk.xyz.1 == (WORLD_HEIGHT-1) as i32 {
+            if chunk.xyz.1 == top-1 {
                 for (lz, lx) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE) {
                     chunk.lightmap.set_sun((lx as u8, (CHUNK_SIZE-1) as u8, lz as u8), 15);
                 }
@@ -89,6 +112,9 @@ impl LightSolvers {
             }
         }
         self.solver_sun.solve(chunks);
+        for (cy, cz, cx) in iproduct!(0..chunks.height, 0..chunks.depth, 0..chunks.width) {
+            if let Some(chunk) = chunks.mut_local_chunk(ChunkCoords(cx, cy, cz)) {chunk.lightmap.compact()};
+        }
     }
 
 
@@ -128,8 +154,8 @@ impl LightSolvers {
         self.remove_rgb(chunks, x, y, z);
         self.solve_rgb(chunks);
 
-        if chunks.get_sun((x, y+1, z).into()) == MAX_LIGHT || (y+1) as usize == WORLD_HEIGHT*CHUNK_SIZE {
-            for i in (0..=y).rev() {
+        if chunks.get_sun((x, y+1, z).into()) == MAX_LIGHT || y+1 == chunks.heights.top_y() {
+            for i in (chunks.heights.bottom_y()..=y).rev() {
                 if chunks.voxel_global((x, i, z).into()).map_or(true, |v| v.id != 0) {break};
                 self.solver_sun.add_with_emission(chunks, x, i, z, MAX_LIGHT as u8);
             }
@@ -148,7 +174,7 @@ impl LightSolvers {
         self.remove_rgbs(chunks, x, y, z);
         self.solver_sun.solve(chunks);
 
-        for ny in (0..y).rev() {
+        for ny in (chunks.heights.bottom_y()..y).rev() {
             if chunks.voxel_global((x, ny, z).into()).map_or(0, |v| v.id) != 0 {break};
             self.solver_sun.remove(chunks, x, ny, z);
             self.solver_sun.solve(chunks);
@@ -199,4 +225,8 @@ impl LightSolvers {
         self.remove_rgb(chunks, x, y, z);
         self.solver_sun.remove(chunks, x, y, z);
     }
+}
+
+impl Default for LightSolvers {
+    fn default() -> Self {Self::new()}
 }
\ No newline at end of file
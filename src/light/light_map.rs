@@ -21,10 +21,10 @@ impl Light {
     pub fn set(&mut self, value: u16, channel: u8) {
         self.0 = (self.0 & (!(0xF << (channel*4)))) | (value << (channel << 2));
     }
-    #[inline] pub fn set_red(&mut self, value: u16) {self.0 = self.0 & 0xFFF0 | value}
-    #[inline] pub fn set_green(&mut self, value: u16) {self.0 = self.0 & 0xFFF0 | (value << 4)}
-    #[inline] pub fn set_blue(&mut self, value: u16) {self.0 = self.0 & 0xFFF0 | (value << 8)}
-    #[inline] pub fn set_sun(&mut self, value: u16) {self.0 = self.0 & 0xFFF0 | (value << 12)}
+    #[inline] pub fn set_red(&mut self, value: u16) {self.set(value, 0)}
+    #[inline] pub fn set_green(&mut self, value: u16) {self.set(value, 1)}
+    #[inline] pub fn set_blue(&mut self, value: u16) {self.set(value, 2)}
+    #[inline] pub fn set_sun(&mut self, value: u16) {self.set(value, 3)}
 
     pub fn get_normalized(&self) -> [f32; 4] {
         [self.get_red() as f32 / Self::MAX_VALUE as f32,
@@ -34,15 +34,18 @@ impl Light {
     }
 }
 
+/// Light of the voxels of a chunk, the map is only allocated when the light differs between voxels,
+/// so the sky and the closed underground take no memory
 #[derive(Debug)]
 pub struct LightMap {
-    pub map: [Light; CHUNK_VOLUME],
+    uniform: Light,
+    map: Option<Box<[Light]>>,
 }
 
 impl LightMap {
     #[inline]
     pub fn new() -> LightMap {
-        LightMap { map: [Light::default(); CHUNK_VOLUME]}
+        LightMap { uniform: Light::default(), map: None }
     }
 
     #[inline]
@@ -51,59 +54,80 @@ impl LightMap {
         ((y * CHUNK_SIZE as u16 + z) * CHUNK_SIZE as u16 + x) as usize
     }
 
+    /// Allocates the map when the change makes the light differ
+    #[inline]
+    fn update(&mut self, local: (u8, u8, u8), change: impl Fn(&mut Light)) {
+        if self.map.is_none() {
+            let mut light = self.uniform;
+            change(&mut light);
+            if light == self.uniform {return};
+        }
+        let uniform = self.uniform;
+        change(&mut self.map.get_or_insert_with(|| vec![uniform; CHUNK_VOLUME].into_boxed_slice())[LightMap::index(local)]);
+    }
+
+    /// Frees the map when every voxel has the same light
+    pub fn compact(&mut self) {
+        let Some(map) = &self.map else {return};
+        if map.iter().all(|light| *light == map[0]) {
+            self.uniform = map[0];
+            self.map = None;
+        }
+    }
+
     #[inline]
     pub fn get_light(&self, local: (u8, u8, u8)) -> Light {
-        self.map[LightMap::index(local)]
+        self.map.as_ref().map_or(self.uniform, |map| map[LightMap::index(local)])
     }
 
     #[inline]
     pub fn get(&self, local: (u8, u8, u8), channel: u8) -> u16 { 
-        self.map[LightMap::index(local)].get(channel)
+        self.get_light(local).get(channel)
     }
 
     #[inline]
     pub fn get_red(&self, local: (u8, u8, u8)) -> u16 {
-        self.map[LightMap::index(local)].get_red()
+        self.get_light(local).get_red()
     }
 
     #[inline]
     pub fn get_green(&self, local: (u8, u8, u8)) -> u16 {
-        self.map[LightMap::index(local)].get_green()
+        self.get_light(local).get_green()
     }
 
     #[inline]
     pub fn get_blue(&self, local: (u8, u8, u8)) -> u16 {
-        self.map[LightMap::index(local)].get_blue()
+        self.get_light(local).get_blue()
     }
 
     #[inline]
     pub fn get_sun(&self, local: (u8, u8, u8)) -> u16 {
-        self.map[LightMap::index(local)].get_sun()
+        self.get_light(local).get_sun()
     }
 
     #[inline]
     pub fn set_red(&mut self, local: (u8, u8, u8), value: u16) {
-        self.map[LightMap::index(local)].set_red(value);
+        self.update(local, |light| light.set_red(value));
     }
 
     #[inline]
     pub fn set_green(&mut self, local: (u8, u8, u8), value: u16) {
-        self.map[LightMap::index(local)].set_green(value);
+        self.update(local, |light| light.set_green(value));
     }
 
     #[inline]
     pub fn set_blue(&mut self, local: (u8, u8, u8), value: u16) {
-        self.map[LightMap::index(local)].set_blue(value);
+        self.update(local, |light| light.set_blue(value));
     }
 
     #[inline]
     pub fn set_sun(&mut self, local: (u8, u8, u8), value: u16) {
-        self.map[LightMap::index(local)].set_sun(value);
+        self.update(local, |light| light.set_sun(value));
     }
 
     #[inline]
     pub fn set(&mut self, local: (u8, u8, u8), value: u16, channel: u8) {
-        self.map[LightMap::index(local)].set(value, channel);
+        self.update(local, |light| light.set(value, channel));
     }
 }
 
@@ -111,4 +135,38 @@ impl Default for LightMap {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::{Light, LightMap};
+
+    /// A setter changes only its own channel and can lower it
+    #[test]
+    fn light_setters() {
+        let mut light = Light(0);
+        light.set_red(1);
+        light.set_green(2);
+        light.set_blue(3);
+        light.set_sun(15);
+        assert_eq!((light.get_red(), light.get_green(), light.get_blue(), light.get_sun()), (1, 2, 3, 15));
+        light.set_sun(4);
+        light.set_green(0);
+        assert_eq!((light.get_red(), light.get_green(), light.get_blue(), light.get_sun()), (1, 0, 3, 4));
+    }
+
+    #[test]
+    fn uniform_light() {
+        let mut lightmap = LightMap::new();
+        lightmap.set((1, 2, 3), 0, 3);
+        assert!(lightmap.map.is_none());
+        lightmap.set((1, 2, 3), 15, 3);
+        assert_eq!((lightmap.get_sun((1, 2, 3)), lightmap.get_sun((0, 0, 0))), (15, 0));
+        assert!(lightmap.map.is_some());
+        lightmap.set((1, 2, 3), 0, 3);
+        lightmap.compact();
+        assert!(lightmap.map.is_none());
+        assert_eq!(lightmap.get_sun((1, 2, 3)), 0);
+    }
+}
@@ -1,19 +1,17 @@
-use std::{time::{Duration, Instant}, sync::{Arc, Mutex, Condvar}, collections::VecDeque, io::BufReader, fs::File};
-use camera::frustum::Frustum;
-use direction::Direction;
-use engine::state;
-use graphic::{render_selection::render_selection, render::RenderResult};
-use gui::gui_controller::GuiController;
-use input_event::KeypressState;
-use meshes::{MeshesRenderInput, Mesh};
-use player::player::Player;
-use recipes::{storage::Storage, item::Item};
+use std::{time::{Duration, Instant}, sync::{Arc, atomic::Ordering}, collections::VecDeque, io::BufReader, fs::File, path::PathBuf};
+use manufactory::camera::frustum::Frustum;
+use manufactory::engine::state;
+use manufactory::graphic::render_selection::{render_selection, render_crack};
+use manufactory::gui::gui_controller::{GuiController, MenuAction};
+use manufactory::meshes::{MeshesRenderInput, Mesh};
 use rodio::{OutputStream, Decoder, Source};
-use threads::save::SaveState;
-use unsafe_mutex::UnsafeMutex;
-use world::{World, global_coords::GlobalCoords, sun::{Sun, Color}};
-use crate::{voxels::chunk::HALF_CHUNK_SIZE, world::{chunk_coords::ChunkCoords, local_coords::LocalCoords}, save_load::Save};
-use voxels::{chunks::{Chunks, WORLD_HEIGHT}, chunk::CHUNK_SIZE, block::blocks::BLOCKS};
+use manufactory::console::{Console, commands::{CommandContext, CommandError}};
+use manufactory::session::Session;
+use manufactory::threads::world_loader::{CHUNK_POOL, TRANSLATE_PRIORITY};
+use manufactory::settings::{Settings, SETTINGS_PATH};
+use manufactory::world::{global_coords::GlobalCoords, sun::{Sun, Color}, game_mode::GameMode};
+use manufactory::{voxels::chunk::HALF_CHUNK_SIZE, world::{chunk_coords::ChunkCoords, local_coords::LocalCoords}, save_load::{Save, list_worlds, create_world, delete_world, backup_world, restore_backup}};
+use manufactory::voxels::{chunks::Chunks, chunk::CHUNK_SIZE, block::blocks::BLOCKS, voxel_data::machine_status::MachineStatus};
 
 use winit::{
     event::*,
@@ -22,40 +20,14 @@ use winit::{
 };
 use itertools::iproduct;
 
-use crate::{input_event::input_service::{Key, Mouse}, voxels::ray_cast, my_time::Timer};
+use manufactory::{input_event::{actions::Action, input_service::InputService, recording::{InputRecorder, InputReplay, FrameTime}}, voxels::ray_cast, my_time::{self, Timer}, threads, camera};
 use nalgebra_glm as glm;
 
-mod input_event;
-mod my_time;
-mod voxels;
-mod graphic;
-mod light;
-mod meshes;
-mod camera;
-mod gui;
-mod recipes;
-mod player;
-mod models;
-mod direction;
-mod world;
-mod macros;
-mod threads;
-mod unsafe_mutex;
-mod engine;
-mod save_load;
-mod bytes;
-
-static mut WORLD_EXIT: bool = false;
 const _GAME_VERSION: u32 = 1;
 
-const RENDER_DISTANCE: i32 = 30;
-const HALF_RENDER_DISTANCE: i32 = RENDER_DISTANCE / 2;
+const MACHINE_STATUS_DISTANCE: f32 = 48.0;
 
-const CAMERA_FOV: f32 = 1.2;
-const CAMERA_NEAR: f32 = 0.1;
-const CAMERA_FAR: f32 = 1000.0;
-
-pub fn frustum(chunks: &mut Chunks, frustum: &Frustum) -> Vec<usize> {
+pub fn frustum(chunks: &Chunks, frustum: &Frustum) -> Vec<usize> {
     // UPDATE
     // This function could be much faster
     let mut indices: Vec<usize> = vec![];
@@ -73,20 +45,103 @@ pub fn frustum(chunks: &mut Chunks, frustum: &Frustum) -> Vec<usize> {
 }
 
 
+/// Status icons of the multiblock machines in the visible chunks
+pub fn machine_statuses(chunks: &Chunks, indices: &[usize], position: &glm::Vec3) -> Vec<(GlobalCoords, glm::Vec3, MachineStatus)> {
+    indices.iter()
+        .filter_map(|i| chunks.chunks.get(*i).and_then(|c| c.as_ref()))
+        .flat_map(|chunk| chunk.voxels_data().values())
+        .filter_map(|voxel_data| {
+            let status = voxel_data.additionally.status()?;
+            let structure = voxel_data.additionally.structure_coordinates()?;
+            let len = structure.len() as f32;
+            let x = structure.iter().map(|c| c.0 as f32 + 0.5).sum::<f32>() / len;
+            let y = structure.iter().map(|c| c.1).max()? as f32 + 1.5;
+            let z = structure.iter().map(|c| c.2 as f32 + 0.5).sum::<f32>() / len;
+            let icon_position = glm::vec3(x, y, z);
+            (glm::distance(&icon_position, position) < MACHINE_STATUS_DISTANCE).then_some((voxel_data.global_coords, icon_position, status))
+        })
+        .collect()
+}
+
+
+/// Command line options
+#[derive(Debug, Default)]
+struct LaunchOptions {
+    /// Records the input of every opened world
+    record: bool,
+    /// Recording replayed at start
+    replay: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => options.record = true,
+                "--replay" => options.replay = args.next().map(PathBuf::from),
+                _ => eprintln!("Unknown argument: {arg}"),
+            }
+        }
+        options
+    }
+}
+
+
+/// Opens the world, the first opened world creates the save
+fn open_world(save: &mut Option<Save>, path: PathBuf, settings: &Settings, input: &mut InputService, options: &LaunchOptions) -> Option<Session> {
+    if let Err(err) = backup_world(&path, settings.backups) {
+        eprintln!("World backup error: {err}");
+    }
+    if options.record && !input.is_replaying() {
+        match InputRecorder::start(&path) {
+            Ok(recorder) => input.start_recording(recorder),
+            Err(err) => eprintln!("Failed to start the input recording: {err}"),
+        }
+    }
+    let save = save.get_or_insert_with(|| Save::new(path.clone()));
+    Session::open(save, path, settings, input.is_replaying()).map_err(|e| eprintln!("Failed to open the world: {e}")).ok()
+}
+
+
+fn on_render_error(state: &mut state::State, control_flow: &mut ControlFlow, error: wgpu::SurfaceError) {
+    match error {
+        wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated => {
+            state.resize(state.size)
+        }
+        wgpu::SurfaceError::OutOfMemory => *control_flow = ControlFlow::Exit,
+        wgpu::SurfaceError::Timeout => eprintln!("Surface timeout"),
+    }
+}
+
+
 #[tokio::main]
 pub async fn main() {
+    // Load the block registry before anything depends on it
+    BLOCKS();
+    let options = LaunchOptions::parse(std::env::args().skip(1));
+
+    let mut settings = Settings::load(SETTINGS_PATH).unwrap_or_else(|e| {
+        eprintln!("Failed to load settings: {e}");
+        Settings::default()
+    });
+
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
     // Load a sound from a file, using a path relative to Cargo.toml
-    let file = BufReader::new(File::open("./audio/music/Kyle Gabler - Years of Work.mp3").unwrap());
-    // Decode that sound file into a source
-    let source = Decoder::new(file).unwrap();
-    // Play the sound directly on the device
-    let _ = stream_handle.play_raw(source.convert_samples());
-
-    let (tx, rx) = std::sync::mpsc::channel::<Vec<(usize, usize)>>();
-    let (render_sender, render_recv) = std::sync::mpsc::channel::<RenderResult>();
-    let save = Save::new("./data/worlds/debug/");
-    let sun = Sun::new(
+    match File::open(&settings.music) {
+        Ok(file) => {
+            // Decode that sound file into a source
+            let source = Decoder::new(BufReader::new(file)).unwrap();
+            // Play the sound directly on the device
+            let _ = stream_handle.play_raw(source.convert_samples());
+        },
+        Err(err) => eprintln!("Music open error: {}", err),
+    }
+
+    let mut save: Option<Save> = None;
+    let mut session: Option<Session> = None;
+    let mut worlds = list_worlds();
+    let mut sun = Sun::new(
         60,
         [0, 50, 60, 230, 240, 290, 300, 490, 500],
         [Color(1.0, 0.301, 0.0), Color(1.0, 0.654, 0.0),
@@ -102,62 +157,43 @@ pub async fn main() {
          Color(1.0, 0.301, 0.0)]);
 
     let mut debug_block_id = None;
+    let mut console = Console::new();
+    let (console_sender, console_recv) = std::sync::mpsc::channel::<String>();
+    threads::console_input::spawn(console_sender);
 
     let event_loop = EventLoop::new();
     let window = Arc::new(WindowBuilder::new()
         .with_title("Manufactory")
-        .with_inner_size(PhysicalSize::new(1150u32, 700u32))
+        .with_inner_size(PhysicalSize::new(settings.window_width, settings.window_height))
         .build(&event_loop)
         .unwrap());
 
-    let mut player = match save.world.player.lock().unwrap().load_player() {
-        Some(player) => player,
-        _ => {
-            let camera = camera::camera_controller::CameraController::new(
-                glm::vec3(0.0, 20.0, 0.0), CAMERA_FOV, CAMERA_NEAR, CAMERA_FAR);
-            let mut player = Player::new(camera, glm::vec3(0.0, 20.0, 0.0));
-            let binding = player.inventory();
-            let mut inventory = binding.lock().unwrap();
-            _ = inventory.add_by_index(&Item::new(0, 100), 10);
-            _ = inventory.add_by_index(&Item::new(1, 100), 11);
-            _ = inventory.add_by_index(&Item::new(2, 100), 12);
-            _ = inventory.add_by_index(&Item::new(3, 100), 13);
-            player
-        }
-    };
-    
-    let mut meshes = meshes::Meshes::new();
-    let mut input = input_event::input_service::InputService::new();
+    let mut input = InputService::new(settings.bindings.clone());
     let mut time = my_time::Time::new();
     let window_size = window.inner_size();
+    let camera = camera::camera_controller::CameraController::new(glm::vec3(0.0, 20.0, 0.0), settings.fov, settings.near, settings.far);
     let mut state = state::State::new(
-        window.clone(), &player.camera().proj_view(window_size.width as f32, window_size.height as f32).into()).await;
-    let mut gui_controller = GuiController::new(window, state.texture_atlas.clone());
-
-    let player_coords = Arc::new(Mutex::new(player.camera().position_tuple()));
-    let c: ChunkCoords = GlobalCoords::from(player.camera().position_tuple()).into();
-    let ox = c.0 - HALF_RENDER_DISTANCE;
-    let oz = c.2 - HALF_RENDER_DISTANCE;
-    let world = Arc::new(UnsafeMutex::new(
-        World::new(RENDER_DISTANCE, WORLD_HEIGHT as i32, RENDER_DISTANCE, ox, 0, oz)));
-    let save_condvar = Arc::new((Mutex::new(SaveState::Unsaved), Condvar::new()));
-    
-    let thread_save = threads::save::spawn(world.clone(), save.world.regions.clone(), save_condvar.clone());
-    let thread_world_loader = threads::world_loader::spawn(world.clone(), save.world.regions.clone());
-    let thread_renderer = threads::renderer::spawn(world.clone(), render_sender);
-    let thread_voxel_data_updater = threads::voxel_data_updater::spawn(world.clone());
-    
-    let mut finalize = Some(move || {
-        unsafe {WORLD_EXIT = true};
-        let _ = thread_renderer.join();
-        let _ = thread_world_loader.join();
-        let _ = thread_voxel_data_updater.join();
-
-        let (save_state, cvar) = &*save_condvar;
-        *save_state.lock().unwrap() = SaveState::WorldExit;
-        cvar.notify_one();
-        thread_save.join().expect("Failed to terminate thread save");
-    });
+        window.clone(), &camera.proj_view(window_size.width as f32, window_size.height as f32).into(), settings.msaa).await;
+    let mut gui_controller = GuiController::new(window, state.texture_atlas.clone(), settings.clone());
+    state.set_crosshair(false);
+
+    if let Some(path) = &options.replay {
+        match InputReplay::load(path) {
+            Ok((replay, world_path)) => {
+                input.start_replay(replay);
+                session = open_world(&mut save, world_path, &settings, &mut input, &options);
+                if let Some(Err(err)) = session.as_ref().map(|s| input.check_replay_seed(s.metadata.seed)) {
+                    eprintln!("Failed to start the replay: {err}");
+                    if let (Some(session), Some(save)) = (session.take(), &save) {session.close(save)};
+                }
+                gui_controller.set_cursor_lock(false);
+                state.set_ui_interaction(false);
+                state.set_crosshair(session.is_some() && gui_controller.is_ui());
+            },
+            Err(err) => eprintln!("Failed to load the replay: {err}"),
+        }
+    }
+
     let mut timer_16ms = Timer::new(Duration::from_millis(16));
     let mut fps = Instant::now();
     let mut fps_queue = VecDeque::from([0.0; 10]);
@@ -169,50 +205,129 @@ pub async fn main() {
         }
         match event {
             Event::WindowEvent {
-                ref event,
+                event: WindowEvent::CloseRequested,
                 window_id,
-            } if window_id == state.window().id() => {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            },
-                        ..
-                    } => *control_flow = ControlFlow::Exit,
-                    _ => {}
-                }
-            }
+            } if window_id == state.window().id() => *control_flow = ControlFlow::Exit,
             Event::RedrawRequested(window_id) if window_id == state.window().id() => {
-                player.handle_input(&input, time.delta(), gui_controller.is_cursor());
-
-                let mut world_g = unsafe {world.lock_immediately()}.unwrap();
                 time.update();
+                if let Some(action) = gui_controller.take_menu_action() {
+                    match action {
+                        MenuAction::Resume => gui_controller.toggle_menu(),
+                        MenuAction::Save => {
+                            if let (Some(session), Some(save)) = (&session, &save) {session.save(save)};
+                        },
+                        MenuAction::QuitToTitle => {
+                            gui_controller.toggle_menu();
+                            if let (Some(session), Some(save)) = (session.take(), &save) {session.close(save)};
+                            input.stop_recording();
+                            input.stop_replay();
+                            worlds = list_worlds();
+                        },
+                        MenuAction::Exit => *control_flow = ControlFlow::Exit,
+                        MenuAction::OpenWorld(path) => session = open_world(&mut save, path, &settings, &mut input, &options),
+                        MenuAction::CreateWorld { name, seed, mode } => match create_world(&name, seed, mode) {
+                            Ok(path) => session = open_world(&mut save, path, &settings, &mut input, &options),
+                            Err(err) => eprintln!("World create error: {}", err),
+                        },
+                        MenuAction::ApplySettings(new_settings) => {
+                            new_settings.save(SETTINGS_PATH);
+                            state.set_max_sample_count(new_settings.msaa);
+                            input.set_bindings(new_settings.bindings.clone());
+                            if let Some(session) = &mut session {session.player.apply_settings(&new_settings)};
+                            settings = new_settings;
+                        },
+                        MenuAction::RestoreBackup { world, backup } => {
+                            if let Err(err) = restore_backup(&world, &backup) {eprintln!("Backup restore error: {}", err)};
+                            worlds = list_worlds();
+                        },
+                        MenuAction::DeleteWorld(path) => {
+                            if let Err(err) = delete_world(&path) {eprintln!("World delete error: {}", err)};
+                            worlds = list_worlds();
+                        },
+                    }
+                    let is_cursor = session.as_ref().is_none_or(|s| s.player.is_inventory) || gui_controller.is_menu();
+                    gui_controller.set_cursor_lock(is_cursor);
+                    state.set_ui_interaction(is_cursor);
+                    state.set_crosshair(session.is_some() && gui_controller.is_ui());
+                }
+
+                let rebound_button = gui_controller.rebinding().and(input.just_pressed_button());
+                if let Some(button) = rebound_button {gui_controller.set_binding(button)};
+
+                let Some(Session { metadata, player, world, meshes, mining, alerts, ticks, save_condvar, translate_sender, translate_recv, render_recv, scheduler, .. }) = session.as_mut() else {
+                    state.selection_vertex_buffer = None;
+                    state.crack_vertex_buffer = None;
+                    if let Err(error) = state.render(&[], |ctx| {gui_controller.draw_title_menu(ctx, &worlds);}) {
+                        on_render_error(&mut state, control_flow, error);
+                    }
+                    // The commands change an open world, the lines typed on the title screen are not kept for it
+                    console_recv.try_iter().filter(|line| !line.trim().is_empty())
+                        .for_each(|line| println!("{}", CommandError::NoWorld(line.trim().to_string())));
+                    input.update();
+                    return;
+                };
+
+                // A recorded frame holds the ticks while it changes the world, so the replay applies its input at the same tick
+                let held = input.is_recording().then(|| ticks.hold());
+                if let Some(Err(err)) = held {
+                    eprintln!("Recording stopped: {err}");
+                    input.stop_recording();
+                }
+                let is_recording = input.is_recording();
+                let done = match held {Some(Ok(done)) => done, _ => ticks.done()};
+                let (frame, gui_actions) = input.sync_frame(FrameTime { delta: time.delta(), ticks: done }, gui_controller.take_gui_actions());
+                if input.is_replaying() {
+                    if let Err(err) = ticks.run_until(frame.ticks) {
+                        eprintln!("Replay failed: {err}");
+                        input.stop_replay();
+                        if let (Some(session), Some(save)) = (session.take(), &save) {session.close(save)};
+                        worlds = list_worlds();
+                        return;
+                    }
+                } else if !is_recording {
+                    ticks.release();
+                }
+                gui_actions.into_iter().for_each(|action| action.apply(player));
+                time.set_delta(frame.delta);
+
+                if input.is_action(Action::Pause) && rebound_button.is_none() {
+                    gui_controller.toggle_menu();
+                    let is_cursor = player.is_inventory || gui_controller.is_menu();
+                    gui_controller.set_cursor_lock(is_cursor);
+                    state.set_ui_interaction(is_cursor);
+                }
+
+                if !gui_controller.is_typing() && !gui_controller.is_menu() {
+                    player.handle_input(&input, time.delta(), gui_controller.is_cursor());
+                }
+
+                // The frame reads the world, the edits take the write lock for a moment
+                let mut world_g = world.read().unwrap();
                 let c: ChunkCoords = GlobalCoords::from(player.camera().position_tuple()).into();
                 let mut debug_data = format!("{:?}", player.camera().position_tuple());
-                if ((c.0-HALF_RENDER_DISTANCE - world_g.chunks.ox).abs() >= 2 || (c.2-HALF_RENDER_DISTANCE - world_g.chunks.oz).abs() >= 2) && !world_g.chunks.is_translate {
-                    world_g.chunks.is_translate = true;
-                    drop(world_g);
+                let render_distance = settings.render_distance;
+                let (ox, oz) = (c.0 - render_distance/2, c.2 - render_distance/2);
+                let (oy, height) = world_g.chunks.heights.window(c.1, settings.render_height);
+                let is_resize = world_g.chunks.width != render_distance || world_g.chunks.depth != render_distance
+                    || world_g.chunks.height != height;
+                let is_moved = (ox - world_g.chunks.ox).abs() >= 2 || (oy - world_g.chunks.oy).abs() >= 2 || (oz - world_g.chunks.oz).abs() >= 2;
+                if (is_moved || is_resize) && !world_g.chunks.is_translate.swap(true, Ordering::AcqRel) {
                     let w = world.clone();
-                    let tx_clone = tx.clone();
+                    let tx_clone = translate_sender.clone();
                     let need_translate = meshes.need_translate.clone();
-                    tokio::spawn(async move {
-                        let mut world = w.lock().unwrap();
+                    let jobs = scheduler.sender(CHUNK_POOL).expect("The session has the chunk pool");
+                    jobs.submit(TRANSLATE_PRIORITY, move |_| {
+                        let mut world = w.write().unwrap();
                         *need_translate.lock().unwrap() += 1;
-                        let vec = world.chunks.translate(c.0-HALF_RENDER_DISTANCE, c.2-HALF_RENDER_DISTANCE);
-                        world.chunks.is_translate = false;
+                        let vec = world.chunks.resize(render_distance, height, render_distance, ox, oy, oz);
+                        world.chunks.is_translate.store(false, Ordering::Release);
                         drop(world);
                         let _ = tx_clone.send(vec);
                     });
                 }
-                let mut world_g = unsafe {world.lock_immediately()}.unwrap();
                 let indices = frustum(
-                    &mut world_g.chunks,
+                    &world_g.chunks,
                     &player.camera().new_frustum(state.size.width as f32/state.size.height as f32));
-                *player_coords.lock().unwrap() = player.camera().position_tuple();
                 state.update(&player.camera().proj_view(state.size.width as f32, state.size.height as f32).into(), &time);
                 gui_controller.update_cursor_lock();
                 meshes.update_transforms_buffer(&state, &world_g, &indices);
@@ -220,19 +335,44 @@ pub async fn main() {
                 fps_queue.push_back(1.0/fps.elapsed().as_secs_f32());
                 debug_data += &(fps_queue.iter().sum::<f32>() / fps_queue.len() as f32).floor().to_string();
                 fps_queue.pop_front();
+                scheduler.stats().iter().for_each(|stats| debug_data += &format!("\n{}", stats));
                 fps = Instant::now();
 
-                if input.is_key(&Key::E, KeypressState::AnyJustPress) {
+                if input.is_action(Action::OpenInventory) && !gui_controller.is_typing() && !gui_controller.is_menu() {
                     gui_controller.set_cursor_lock(player.is_inventory);
                     state.set_ui_interaction(player.is_inventory);
                 }
 
-                if input.is_key(&Key::F1, KeypressState::AnyJustPress) {
+                if input.is_action(Action::Console) && (!gui_controller.is_typing() || console.is_open()) {
+                    console.toggle();
+                    let is_cursor = console.is_open() || player.is_inventory;
+                    gui_controller.set_cursor_lock(is_cursor);
+                    state.set_ui_interaction(is_cursor);
+                }
+
+                let lines = console.take_submitted();
+                let stdin_lines = console_recv.try_iter().collect::<Vec<String>>();
+                if !lines.is_empty() || !stdin_lines.is_empty() {
+                    drop(world_g);
+                    let mut world_w = world.write().unwrap();
+                    let save = save.as_ref().expect("An opened world has a save");
+                    let mut context = CommandContext {
+                        world: &mut world_w, player, sun: &mut sun, save, is_save_requested: false};
+                    lines.iter().for_each(|line| {console.execute(line, &mut context);});
+                    stdin_lines.iter().for_each(|line| println!("{}", console.execute(line, &mut context)));
+                    let is_save_requested = context.is_save_requested;
+                    drop(world_w);
+                    // The save loop reads the world while it holds the save state
+                    if is_save_requested {threads::save::request_save(save_condvar)};
+                    world_g = world.read().unwrap();
+                }
+
+                if input.is_action(Action::ToggleUi) {
                     gui_controller.toggle_ui();
                     state.set_crosshair(gui_controller.is_ui());
                 }
                 
-                if input.is_key(&Key::F11, KeypressState::AnyJustPress) {
+                if input.is_action(Action::Fullscreen) {
                     let window = state.window();
                     if window.fullscreen().is_some() {
                         window.set_fullscreen(None);
@@ -250,43 +390,60 @@ pub async fn main() {
                     debug_data += &format!("{:?} {:?}", result.3, world_g.chunks.chunk(chunk_coords).and_then(|c| c.voxel_data(local_coords)));
                     let voxel_id = voxel.map_or(0, |v| v.id);
 
+                    let block_id = world_g.chunks.voxel_data(global_coords).map_or(voxel_id, |vd| vd.id);
+                    let hardness = if metadata.mode == GameMode::Creative {0.0} else {BLOCKS()[block_id as usize].hardness()};
+                    let is_mining = voxel_id != 0 && !gui_controller.is_cursor()
+                        && input.is_action(Action::Break);
+                    if !is_mining {mining.reset()};
+
                     if voxel_id != 0 {
                         let min_point = BLOCKS()[voxel_id as usize].min_point();
                         let max_point = BLOCKS()[voxel_id as usize].max_point();
-                        state.selection_vertex_buffer =
-                            Some(render_selection(
-                                state.device(),
-                                &[min_point.0 + x as f32, min_point.1 + y as f32, min_point.2 + z as f32],
-                                &[max_point.0 + x as f32, max_point.1 + y as f32, max_point.2 + z as f32]
-                            ));
+                        let min = [min_point.0 + x as f32, min_point.1 + y as f32, min_point.2 + z as f32];
+                        let max = [max_point.0 + x as f32, max_point.1 + y as f32, max_point.2 + z as f32];
+                        state.selection_vertex_buffer = Some(render_selection(state.device(), &min, &max));
+                        let progress = mining.progress(hardness);
+                        state.crack_vertex_buffer = (progress > 0.0)
+                            .then(|| render_crack(state.device(), &min, &max, progress));
                     } else {
                         state.selection_vertex_buffer = None;
+                        state.crack_vertex_buffer = None;
                     }
 
-                    if input.is_mouse(&Mouse::Left, KeypressState::AnyJustPress) && !gui_controller.is_cursor() {
-                        BLOCKS()[voxel_id as usize].on_block_break(&mut world_g, &mut player, &(x, y, z).into());
-                    } else if input.is_mouse(&Mouse::Right, KeypressState::AnyJustPress) && !gui_controller.is_cursor() {
+                    if is_mining {
+                        if mining.update(global_coords, hardness, time.delta()) {
+                            drop(world_g);
+                            BLOCKS()[voxel_id as usize].on_block_break(&mut world.write().unwrap(), player, &global_coords);
+                            world_g = world.read().unwrap();
+                            state.crack_vertex_buffer = None;
+                        }
+                    } else if input.is_action(Action::Place) && !gui_controller.is_cursor() {
                         let gxyz = GlobalCoords(x+norm.x as i32, y+norm.y as i32, z+norm.z as i32);
                         if let Some(storage) = world_g.chunks.voxel_data(global_coords).and_then(|vd| vd.player_unlockable()) {
                             player.set_open_storage(storage);
                             gui_controller.set_cursor_lock(player.is_inventory);
                             state.set_ui_interaction(player.is_inventory);
                         } else {
-                            let front = player.camera().front();
-                            let direction = &Direction::new(front.x, front.y, front.z);
+                            let direction = &player.placement_direction();
+                            drop(world_g);
+                            let mut world_w = world.write().unwrap();
                             if let Some(block_id) = debug_block_id {
                                 BLOCKS()[block_id as usize].on_block_set(
-                                    &mut world_g, &mut player, &gxyz, direction);
+                                    &mut world_w, player, &gxyz, direction);
                             } else {
-                                player.on_right_click(&mut world_g, &gxyz, direction);
+                                player.on_right_click(&mut world_w, &gxyz, direction);
                             }
+                            drop(world_w);
+                            world_g = world.read().unwrap();
                         }                     
                     }
                 } else {
+                    mining.reset();
                     state.selection_vertex_buffer = None;
+                    state.crack_vertex_buffer = None;
                 }
 
-                if let Ok(indices) = rx.try_recv() {
+                if let Ok(indices) = translate_recv.try_recv() {
                     meshes.translate(&indices);
                     meshes.sub_need_translate();
                 }
@@ -306,25 +463,35 @@ pub async fn main() {
                 }
 
                 
-                player.inventory().lock().unwrap().update_recipe();
+                player.inventory().lock().unwrap().update_recipe(ticks.done());
+                if is_recording {ticks.release()};
                 let (sun, sky) = sun.sun_sky();
                 state.set_sun_color(sun.into());
                 state.set_clear_color(sky.into());
                 let mesh_vec = indices.iter().filter_map(|i| meshes.meshes().get(*i).and_then(|c| c.as_ref()))
                     .collect::<Vec<&Mesh>>();
+                let machines = machine_statuses(&world_g.chunks, &indices, player.camera().position());
+                let alert_list = alerts.lock().unwrap().alerts();
+                if let Some(coords) = gui_controller.take_selected_alert() {
+                    if let Some(storage) = world_g.chunks.voxel_data(coords).and_then(|vd| vd.player_unlockable()) {
+                        player.set_open_storage(storage);
+                        gui_controller.set_cursor_lock(player.is_inventory);
+                        state.set_ui_interaction(player.is_inventory);
+                    }
+                }
+                let proj_view = player.camera().proj_view(state.size.width as f32, state.size.height as f32);
                 
-                match state.render(&mesh_vec, |ctx| {
+                if let Err(error) = state.render(&mesh_vec, |ctx| {
                     gui_controller
-                        .draw_inventory(ctx, &mut player)
+                        .draw_machine_statuses(ctx, &proj_view, &machines)
+                        .draw_alerts(ctx, &alert_list)
+                        .draw_console(ctx, &mut console)
+                        .draw_pause_menu(ctx)
+                        .draw_inventory(ctx, player)
                         .draw_debug(ctx, &debug_data, &mut debug_block_id)
-                        .draw_active_recieps(ctx, &mut player);
+                        .draw_active_recieps(ctx, player);
                 }) {
-                    Ok(_) => {}
-                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                        state.resize(state.size)
-                    }
-                    Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
-                    Err(wgpu::SurfaceError::Timeout) => eprintln!("Surface timeout"),
+                    on_render_error(&mut state, control_flow, error);
                 }
                 input.update();
             }
@@ -332,8 +499,8 @@ pub async fn main() {
                 state.window().request_redraw();
             }
             Event::LoopDestroyed => {
-                finalize.take().unwrap()();
-                save.world.player.lock().unwrap().save_player(&player);
+                if let (Some(session), Some(save)) = (session.take(), &save) {session.close(save)};
+                input.stop_recording();
                 println!("All saved!");
             }
             _ => {}
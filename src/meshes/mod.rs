@@ -3,7 +3,7 @@ use std::{collections::HashMap, sync::{Mutex, Arc, mpsc::Receiver}};
 use itertools::Itertools;
 use wgpu::util::DeviceExt;
 
-use crate::{graphic::render::{RenderResult, animated_model::AnimatedModelRenderResult, model::ModelRenderResult}, voxels::block::{block_type::BlockType, blocks::BLOCKS}, engine::vertices::{model_instance::ModelInstance, animated_model_instance::AnimatedModelInstance}, models::animated_model::AnimatedModel, world::World, state::State};
+use crate::{graphic::render::{RenderResult, animated_model::AnimatedModelRenderResult, model::ModelRenderResult}, voxels::block::{block_type::BlockType, blocks::BLOCKS}, engine::vertices::{model_instance::ModelInstance, animated_model_instance::AnimatedModelInstance}, models::animated_model::AnimatedModel, world::World, engine::state::State};
 
 #[derive(Debug)]
 pub struct Mesh {
@@ -227,4 +227,8 @@ impl Meshes {
     pub fn sub_need_translate(&mut self) {
         *self.need_translate.lock().unwrap() -= 1;
     }
+}
+
+impl Default for Meshes {
+    fn default() -> Self {Self::new()}
 }
\ No newline at end of file
@@ -7,7 +7,7 @@ pub fn load_texture(
   src: &str,
   name: &str
 ) -> wgpu::BindGroup {
-    let image = Texture::image(device, queue, src).unwrap();
+    let image = Texture::image(device, queue, src);
     device.create_bind_group(&wgpu::BindGroupDescriptor {
         layout: texture_layout,
         entries: &[
@@ -53,7 +53,16 @@ impl Time {
         self.last_frame = current_time;
     }
 
+    /// Replaces the measured delta, used by replays
+    pub fn set_delta(&mut self, delta: f32) {
+        self.delta = delta;
+    }
+
     pub fn delta(&self) -> f32 {
         self.delta
     }
+}
+
+impl Default for Time {
+    fn default() -> Self {Self::new()}
 }
\ No newline at end of file
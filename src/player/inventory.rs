@@ -1,8 +1,13 @@
 use std::ops::Range;
 
-use crate::{recipes::{recipe::{Recipe, ActiveRecipe, RecipeCrafter}, item::PossibleItem, storage::Storage, recipes::RECIPES}, bytes::{BytesCoder, cast_bytes_from_slice, AsFromBytes, cast_vec_from_bytes}};
+use crate::{recipes::{recipe::{Recipe, ActiveRecipe, RecipeCrafter}, item::{PossibleItem, Item}, storage::{Storage, sort_items}, recipes::RECIPES, crafting_plan::CraftingPlan}, bytes::{BytesCoder, cast_bytes_from_slice, AsFromBytes, DecodeError, slice_bytes, cast_vec_from_bytes}};
 
 
+/// Marks the saved id of a crafting plan step, the step output follows it
+const INTERMEDIATE_FLAG: u32 = 1 << 31;
+/// Marks a step that crafts more than once, the crafts follow the output
+const CRAFTS_FLAG: u32 = 1 << 30;
+
 #[derive(Debug)]
 pub struct ActiveRecipes(pub Vec<ActiveRecipe>);
 
@@ -10,42 +15,90 @@ pub struct ActiveRecipes(pub Vec<ActiveRecipe>);
 #[derive(Debug)]
 pub struct PlayerInventory {
     storage: [PossibleItem; 50],
-    active_recipes: ActiveRecipes
+    active_recipes: ActiveRecipes,
+    /// Stack on the cursor, saved after the storage so a save with the inventory open keeps it
+    held: PossibleItem,
+    /// Last simulation tick the crafting was advanced to
+    tick: u64,
 }
 
 
 impl PlayerInventory {
     pub fn new() -> Self {
-        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]) }
+        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]), held: PossibleItem::new_none(), tick: 0 }
     }
 
     pub fn active_recipe(&self) -> &Vec<ActiveRecipe> {
         &self.active_recipes.0
     }
 
-    pub fn start_recipe(&mut self, recipe: &Recipe) -> bool {
-        if !recipe.crafter.intersects(RecipeCrafter::PLAYER) {return false};
-        let Some(active_recipe) = recipe.start(self) else {return false};
-        self.active_recipes.0.push(active_recipe);
+    pub fn crafting_plan(&self, recipe: &'static Recipe) -> Option<CraftingPlan> {
+        if !recipe.crafter.intersects(RecipeCrafter::PLAYER) {return None};
+        CraftingPlan::new(recipe, self, &RECIPES().player)
+    }
+
+    pub fn start_recipe(&mut self, recipe: &'static Recipe) -> bool {
+        let Some(plan) = self.crafting_plan(recipe) else {return false};
+        let Some(active_recipes) = plan.start(self) else {return false};
+        self.active_recipes.0.extend(active_recipes);
         true
     }
 
-    pub fn update_recipe(&mut self) {
+    /// The hand crafting goes on with the simulation ticks
+    pub fn update_recipe(&mut self, tick: u64) {
+        let ticks = tick.saturating_sub(self.tick) as u32;
+        self.tick = self.tick.max(tick);
+        self.active_recipes.0.iter_mut().for_each(|ar| ar.advance(ticks));
         let self_ptr = self as *mut Self;
         self.active_recipes.0.retain(|ar| !ar.update(unsafe {self_ptr.as_mut().unwrap()}));
     }
 
 
+    /// Cancels the whole crafting plan the recipe belongs to
     pub fn cancel_active_recipe(&mut self, index: usize) -> bool {
-        let Some(recipe) = self.active_recipes.0.get(index).map(|ar| ar.recipe.clone()) else {return false};
-        if self.cancel_recipe(&recipe) {
-            self.active_recipes.0.remove(index);
-            return true;
-        }
+        let active_recipes = &self.active_recipes.0;
+        let Some(end) = (index..active_recipes.len()).find(|&i| !active_recipes[i].intermediate) else {return false};
+        let start = (0..end).rev().take_while(|&i| active_recipes[i].intermediate).last().unwrap_or(end);
+
+        let chain = self.active_recipes.0.drain(start..=end).collect::<Vec<ActiveRecipe>>();
+        if self.cancel_recipe(&chain) {return true};
+        self.active_recipes.0.splice(start..start, chain);
         false
     }
 
 
+    pub fn held(&self) -> &PossibleItem {
+        &self.held
+    }
+
+    pub fn take_held(&mut self) -> PossibleItem {
+        PossibleItem(self.held.0.take())
+    }
+
+    pub fn set_held(&mut self, held: PossibleItem) {
+        self.held = held;
+    }
+
+    /// Puts the held stack back into the inventory, the rest stays held and is saved with it
+    pub fn return_held(&mut self) {
+        let Some(item) = self.held.0.take() else {return};
+        self.held.0 = self.add(&item, true);
+    }
+
+    /// Adds all the items or none of them, returns false if they don't fit
+    pub fn add_all(&mut self, items: &[Item]) -> bool {
+        let storage = self.storage;
+        let is_added = items.iter().all(|item| self.add(item, true).is_none());
+        if !is_added {self.storage = storage};
+        is_added
+    }
+
+    /// Sorts the inventory without the hotbar
+    pub fn sort(&mut self) {
+        sort_items(&mut self.storage[10..]);
+    }
+
+
     pub fn place_in_hotbar(&mut self, index: usize) -> bool {
         let len = self.storage.iter().take(10).len();
         self.place_in_range(index, 0..len)
@@ -53,7 +106,7 @@ impl PlayerInventory {
 
     pub fn place_in_inventory(&mut self, index: usize) -> bool {
         let len = self.storage.iter().skip(10).len();
-        self.place_in_range(index, 10..len)
+        self.place_in_range(index, 10..10+len)
     }
 
     fn place_in_range(&mut self, index: usize, range: Range<usize>) -> bool {
@@ -73,7 +126,7 @@ impl PlayerInventory {
 
 impl Default for PlayerInventory {
     fn default() -> Self {
-        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]) }
+        Self { storage: [PossibleItem::new_none(); 50], active_recipes: ActiveRecipes(vec![]), held: PossibleItem::new_none(), tick: 0 }
     }
 }
 
@@ -92,7 +145,13 @@ impl Storage for PlayerInventory {
 impl BytesCoder for PlayerInventory {
     fn encode_bytes(&self) -> Box<[u8]> {
         let mut bytes = Vec::new();
-        let recipies: Vec<u32> = self.active_recipes.0.iter().map(|ar| ar.recipe.id).collect();
+        let recipies: Vec<u32> = self.active_recipes.0.iter().flat_map(|ar| {
+            match (ar.intermediate, ar.crafts) {
+                (false, _) => vec![ar.recipe.id],
+                (true, 1) => vec![ar.recipe.id | INTERMEDIATE_FLAG, ar.output.count],
+                (true, crafts) => vec![ar.recipe.id | INTERMEDIATE_FLAG | CRAFTS_FLAG, ar.output.count, crafts],
+            }
+        }).collect();
         let recipies_bytes = cast_bytes_from_slice(&recipies);
         let recipies_len = recipies_bytes.len();
 
@@ -103,19 +162,71 @@ impl BytesCoder for PlayerInventory {
         bytes.extend((storage_len as u32).as_bytes());
         bytes.extend(recipies_bytes);
         bytes.extend(storage.as_ref());
+        bytes.extend([self.held].encode_bytes().as_ref());
         bytes.into()
     }
 
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let recipe_end = u32::from_bytes(&bytes[0..4]) as usize + 8;
-        let storage_end = recipe_end + u32::from_bytes(&bytes[4..8]) as usize;
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let recipe_end = u32::from_bytes_at(bytes, 0)? as usize + 8;
+        let storage_end = recipe_end + u32::from_bytes_at(bytes, 4)? as usize;
+
+        let recipies_id = cast_vec_from_bytes::<u32>(slice_bytes(bytes, 8..recipe_end)?)?;
+        let storage = <[PossibleItem; 50]>::decode_bytes(slice_bytes(bytes, recipe_end..storage_end)?)?;
+        // The saves before the held stack end with the storage
+        let [held] = <[PossibleItem; 1]>::decode_bytes(slice_bytes(bytes, storage_end..bytes.len())?)?;
+        let mut active_recipes = ActiveRecipes(vec![]);
+        let mut delay = 0;
+        let mut ids = recipies_id.into_iter();
+        while let Some(id) = ids.next() {
+            let intermediate = id & INTERMEDIATE_FLAG != 0;
+            let output = if intermediate {ids.next().unwrap_or(0)} else {0};
+            let crafts = if intermediate && id & CRAFTS_FLAG != 0 {ids.next().unwrap_or(1).max(1)} else {1};
+            let Some(recipe) = RECIPES().by_id(id & !(INTERMEDIATE_FLAG | CRAFTS_FLAG)) else {continue};
+            let output = if intermediate {Item::new(recipe.result.id(), output)} else {recipe.result};
+            let active_recipe = ActiveRecipe::new_step(delay, recipe.clone(), crafts, output, intermediate);
+            delay = if intermediate {delay + active_recipe.duration()} else {0};
+            active_recipes.0.push(active_recipe);
+        }
+
+        Ok(Self { storage, active_recipes, held, tick: 0 })
+    }
+}
 
-        let recipies_id = cast_vec_from_bytes::<u32>(&bytes[8..recipe_end]);
-        let storage = <[PossibleItem; 50]>::decode_bytes(&bytes[recipe_end..storage_end]);
-        let active_recipes = ActiveRecipes(recipies_id.iter()
-            .map(|id| RECIPES().all[*id as usize].start_absolute())
-            .collect::<Vec<ActiveRecipe>>());
+#[cfg(test)]
+mod test {
+    use crate::{bytes::BytesCoder, recipes::{content::load_content, item::{Item, PossibleItem}, storage::Storage}, save_load::WorldMetadata};
+    use super::PlayerInventory;
+
+    #[test]
+    fn held_stack_saved() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let mut inventory = PlayerInventory::new();
+        inventory.add_by_index(&Item::new(0, 3), 12);
+        inventory.set_held(PossibleItem(Some(Item::new(1, 5))));
+        let bytes = inventory.encode_bytes();
+
+        let decoded = PlayerInventory::decode_bytes(&bytes).unwrap();
+        assert_eq!(decoded.held().0, Some(Item::new(1, 5)));
+        assert_eq!(decoded.storage()[12].0, Some(Item::new(0, 3)));
+
+        // A save without the held stack
+        inventory.set_held(PossibleItem::new_none());
+        let old = PlayerInventory::decode_bytes(&inventory.encode_bytes()).unwrap();
+        assert!(old.held().0.is_none());
+        assert_eq!(old.storage()[12].0, Some(Item::new(0, 3)));
+    }
 
-        Self { storage, active_recipes }
+    #[test]
+    fn add_all_or_none() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let mut inventory = PlayerInventory::new();
+        let size = Item::new(0, 1).stack_size();
+        let slots = inventory.storage().len() as u32;
+        assert!(inventory.add_all(&[Item::new(0, size*(slots - 1))]));
+        // The first item fits in the last slot, the second one doesn't
+        let full = inventory.storage().to_vec();
+        assert!(!inventory.add_all(&[Item::new(0, 1), Item::new(1, 1)]));
+        assert!(inventory.storage().iter().zip(full).all(|(slot, old)| slot.0 == old.0));
+        assert!(inventory.add_all(&[Item::new(1, 1)]));
     }
-}
\ No newline at end of file
+}
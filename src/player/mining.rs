@@ -0,0 +1,35 @@
+use crate::world::global_coords::GlobalCoords;
+
+#[derive(Debug, Default)]
+pub struct BlockMining {
+    target: Option<GlobalCoords>,
+    elapsed: f32,
+}
+
+
+impl BlockMining {
+    pub fn new() -> Self {Self::default()}
+
+    /// Returns true when the block has been held long enough to break
+    pub fn update(&mut self, target: GlobalCoords, hardness: f32, delta_time: f32) -> bool {
+        if self.target != Some(target) {
+            self.target = Some(target);
+            self.elapsed = 0.0;
+        }
+        self.elapsed += delta_time;
+        if self.elapsed < hardness {return false};
+        self.reset();
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.target = None;
+        self.elapsed = 0.0;
+    }
+
+    pub fn progress(&self, hardness: f32) -> f32 {
+        if self.target.is_none() {return 0.0};
+        if hardness <= 0.0 {return 1.0};
+        (self.elapsed / hardness).min(1.0)
+    }
+}
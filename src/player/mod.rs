@@ -1,2 +1,3 @@
 pub mod inventory;
-pub mod player;
\ No newline at end of file
+pub mod player;
+pub mod mining;
\ No newline at end of file
@@ -1,15 +1,20 @@
 use std::sync::{Mutex, Arc, Weak};
-use crate::{recipes::{storage::Storage, items::ITEMS, item_interaction::ItemInteraction}, world::{World, global_coords::GlobalCoords}, direction::Direction, voxels::voxel_data::DrawStorage, camera::camera_controller::CameraController, input_event::{input_service::{InputService, Key}, KeypressState}, bytes::{AsFromBytes, BytesCoder}, CAMERA_FOV, CAMERA_NEAR, CAMERA_FAR};
+use crate::{recipes::{storage::Storage, items::ITEMS, item_interaction::ItemInteraction}, world::{World, global_coords::GlobalCoords}, direction::Direction, voxels::voxel_data::DrawStorage, camera::camera_controller::CameraController, input_event::{input_service::InputService, actions::Action}, bytes::{AsFromBytes, BytesCoder, DecodeError}, settings::{Settings, DEFAULT_FOV, DEFAULT_NEAR, DEFAULT_FAR}};
 use super::inventory::PlayerInventory;
 
 use nalgebra_glm as glm;
 
+/// Where a new player starts
+pub const SPAWN_POSITION: [f32; 3] = [0.0, 20.0, 0.0];
+
 #[derive(Debug)]
 pub struct Player {
     position: glm::Vec3,
     camera: CameraController,
     pub is_inventory: bool,
     pub active_slot: usize,
+    /// Quarter turns of placed blocks relative to the camera
+    rotation: u8,
     pub open_storage: Option<Weak<Mutex<dyn DrawStorage>>>,
     inventory: Arc<Mutex<PlayerInventory>>,
 }
@@ -23,6 +28,7 @@ impl Player {
             open_storage: None,
             inventory: Arc::new(Mutex::new(PlayerInventory::new())),
             active_slot: 0,
+            rotation: 0,
             position,
             camera,
             is_inventory: true,
@@ -48,24 +54,34 @@ impl Player {
         self.is_inventory = true;
     }
 
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.camera.set_projection(settings.fov, settings.near, settings.far);
+        self.camera.set_sensitivity(settings.sensitivity);
+    }
+
+    pub fn set_position(&mut self, position: glm::Vec3) {
+        self.position = position;
+        self.camera.set_position(position);
+    }
+
     pub fn handle_input(&mut self, input: &InputService, delta_time: f32, is_cursor: bool) {
         if !self.is_inventory && !is_cursor {self.camera.update_rotation(input.delta().0, input.delta().1, delta_time)}
 
-        if input.is_key(&Key::E, KeypressState::AnyJustPress) {
+        if input.is_action(Action::OpenInventory) {
             self.is_inventory = !self.is_inventory;
             if !self.is_inventory {self.open_storage = None};
         }
 
-        if input.is_key(&Key::W, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveForward) {
             self.position +=  self.camera.front() * Self::SPEED * delta_time;
         }
-        if input.is_key(&Key::S, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveBack) {
             self.position -=  self.camera.front() * Self::SPEED * delta_time;
         }
-        if input.is_key(&Key::A, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveLeft) {
             self.position -=  self.camera.right() * Self::SPEED * delta_time;
         }
-        if input.is_key(&Key::D, KeypressState::AnyStayPress) {
+        if input.is_action(Action::MoveRight) {
             self.position +=  self.camera.right() * Self::SPEED * delta_time;
         }
         self.camera.set_position(self.position);
@@ -83,16 +99,30 @@ impl Player {
         }
         
         
-        [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5,
-            Key::Key6, Key::Key7, Key::Key8, Key::Key9, Key::Key0]
-            .iter().enumerate().for_each(|(i, key)| {
-                if input.is_key(key, KeypressState::AnyPress) {
-                    self.active_slot = i;
-                }
-            });
+        Action::HOTBAR.iter().enumerate().for_each(|(i, action)| {
+            if input.is_action(*action) {
+                self.active_slot = i;
+            }
+        });
+
+        if input.is_action(Action::Rotate) {
+            self.rotation = (self.rotation + 1) % 4;
+        }
     }
 
     pub fn camera(&self) -> &CameraController {&self.camera}
+
+    /// Direction of a placed block, the camera front turned by the rotation
+    pub fn placement_direction(&self) -> Direction {
+        let front = self.camera.front();
+        let (x, z) = match self.rotation {
+            0 => (front.x, front.z),
+            1 => (-front.z, front.x),
+            2 => (-front.x, -front.z),
+            _ => (front.z, -front.x),
+        };
+        Direction::new(x, front.y, z)
+    }
 }
 
 
@@ -126,20 +156,57 @@ impl BytesCoder for Player {
         bytes.into()
     }
 
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes[0..Header::size()]);
-        let inventory = PlayerInventory::decode_bytes(&bytes[Header::size()..]);
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::from_bytes_at(bytes, 0)?;
+        let inventory = PlayerInventory::decode_bytes(&bytes[Header::size()..])?;
         let position = glm::vec3(header.x, header.y, header.z);
-        let mut camera = CameraController::new(position, CAMERA_FOV, CAMERA_NEAR, CAMERA_FAR);
+        let mut camera = CameraController::new(position, DEFAULT_FOV, DEFAULT_NEAR, DEFAULT_FAR);
         camera.set_angle(header.yaw, header.pitch);
         camera.update_rotation(0.0, 0.0, 0.0);
-        Self {
+        Ok(Self {
             position,
             camera,
             is_inventory: true,
             active_slot: header.slot as usize,
+            rotation: 0,
             open_storage: None,
             inventory: Arc::new(Mutex::new(inventory)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm as glm;
+    use crate::{camera::camera_controller::CameraController, bytes::BytesCoder, recipes::{content::load_content, item::Item, storage::Storage}, save_load::WorldMetadata, settings::{DEFAULT_FOV, DEFAULT_NEAR, DEFAULT_FAR}};
+    use super::Player;
+
+    fn player_bytes() -> Box<[u8]> {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let position = glm::vec3(1.0, 2.0, 3.0);
+        let mut player = Player::new(CameraController::new(position, DEFAULT_FOV, DEFAULT_NEAR, DEFAULT_FAR), position);
+        player.inventory().lock().unwrap().add(&Item::new(0, 5), true);
+        player.encode_bytes()
+    }
+
+    #[test]
+    fn player_decode() {
+        let bytes = player_bytes();
+        let player = Player::decode_bytes(&bytes).unwrap();
+        assert_eq!(player.position, glm::vec3(1.0, 2.0, 3.0));
+        assert_eq!(player.encode_bytes(), bytes);
+    }
+
+    #[test]
+    fn damaged_player() {
+        let bytes = player_bytes();
+        for len in 0..bytes.len() {
+            assert!(Player::decode_bytes(&bytes[..len]).is_err(), "decoded {len} of {} bytes", bytes.len());
+        }
+        for i in 0..bytes.len() {
+            let mut corrupt = bytes.to_vec();
+            corrupt[i] ^= 0xFF;
+            let _ = Player::decode_bytes(&corrupt);
         }
     }
-}
\ No newline at end of file
+}
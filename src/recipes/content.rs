@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use crate::{definitions::{Definitions, Definition, DefinitionError}, save_load::WorldMetadata, voxels::block::blocks::{BLOCKS, block_by_name}};
+
+use super::{item_type::ItemType, item::Item, recipe::{Recipe, RecipeCrafter, RecipeCategory}, items::set_items, recipes::set_recipes};
+
+const ITEMS_PATH: &str = "./assets/data/items.txt";
+const RECIPES_PATH: &str = "./assets/data/recipes.txt";
+
+/// Loads items and recipes, ids are taken from the world metadata.
+/// New keys get the next free ids and are added to the metadata.
+/// The content is set by the first world, a world with other ids is an error
+pub fn load_content(metadata: &mut WorldMetadata) -> Result<(), DefinitionError> {
+    let item_definitions = Definitions::load(ITEMS_PATH)?;
+    let recipe_definitions = Definitions::load(RECIPES_PATH)?;
+
+    let item_ids = assign_ids(&item_definitions.sections, &mut metadata.items)?;
+    let len = metadata.items.iter().map(|(_, id)| *id + 1).max().unwrap_or(0);
+    let mut items = (0..len).map(|id| {
+        let key = metadata.items.iter().find(|(_, i)| *i == id).map_or("", |(key, _)| key.as_str());
+        removed_item(id, key)
+    }).collect::<Vec<ItemType>>();
+    for (definition, id) in item_definitions.sections.iter().zip(item_ids) {
+        items[id as usize] = item_from_definition(definition, id)?;
+    }
+
+    let recipe_ids = assign_ids(&recipe_definitions.sections, &mut metadata.recipes)?;
+    let mut recipes = recipe_definitions.sections.iter().zip(recipe_ids)
+        .map(|(definition, id)| recipe_from_definition(definition, id, &items))
+        .collect::<Result<Vec<Recipe>, DefinitionError>>()?;
+    recipes.sort_by_key(|recipe| recipe.id);
+    recipes.iter_mut().enumerate().for_each(|(index, recipe)| recipe.index = index);
+
+    for block in BLOCKS().iter() {
+        if let Some(key) = block.item_keys().into_iter().find(|key| find_item(&items, key).is_none()) {
+            return Err(item_definitions.root.error(format!("unknown item \"{}\" in block \"{}\"", key, block.name())));
+        }
+    }
+
+    // The items and the recipes live until the game exits, the machines keep references to the recipes
+    if !set_items(items) || !set_recipes(recipes) {
+        return Err(item_definitions.root.error("the world has other item or recipe ids than the opened one, restart the game to open it"));
+    }
+    Ok(())
+}
+
+
+fn assign_ids(definitions: &[Definition], table: &mut Vec<(String, u32)>) -> Result<Vec<u32>, DefinitionError> {
+    let mut ids = vec![];
+    for (i, definition) in definitions.iter().enumerate() {
+        if definitions[..i].iter().any(|d| d.key == definition.key) {
+            return Err(definition.error("duplicate key"));
+        }
+        let id = match table.iter().find(|(key, _)| *key == definition.key) {
+            Some((_, id)) => *id,
+            None => {
+                let id = table.iter().map(|(_, id)| id + 1).max().unwrap_or(0);
+                table.push((definition.key.clone(), id));
+                id
+            }
+        };
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+
+fn find_item<'a>(items: &'a [ItemType], key: &str) -> Option<&'a ItemType> {
+    items.iter().find(|item| item.key == key)
+}
+
+
+/// Keeps the id of an item that was removed from the definitions
+fn removed_item(id: u32, key: &str) -> ItemType {
+    ItemType {
+        id,
+        key: key.to_string(),
+        name: key.to_string(),
+        description: String::from("Removed item"),
+        stack_size: 100,
+        block_id: None,
+        icon: 0,
+    }
+}
+
+
+fn item_from_definition(definition: &Definition, id: u32) -> Result<ItemType, DefinitionError> {
+    let block_id = definition.get("block").map(|name| {
+        block_by_name(name).map(|block| block.id()).ok_or_else(|| definition.invalid_value("block", name))
+    }).transpose()?;
+
+    let stack_size = definition.parse_required::<u32>("stack_size")?;
+    if stack_size == 0 {return Err(definition.invalid_value("stack_size", "0"))};
+
+    Ok(ItemType {
+        id,
+        key: definition.key.clone(),
+        name: definition.get("name").unwrap_or(&definition.key).to_string(),
+        description: definition.get("description").unwrap_or_default().to_string(),
+        stack_size,
+        block_id,
+        icon: definition.parse_required("icon")?,
+    })
+}
+
+
+fn recipe_from_definition(definition: &Definition, id: u32, items: &[ItemType]) -> Result<Recipe, DefinitionError> {
+    let item = |key: &str, (item_key, count): (String, u32)| {
+        find_item(items, &item_key)
+            .map(|item| Item::new(item.id, count))
+            .ok_or_else(|| definition.error(format!("unknown item \"{}\" in \"{}\"", item_key, key)))
+    };
+
+    let ingredients = definition.key_counts("ingredients")?.into_iter()
+        .map(|key_count| item("ingredients", key_count))
+        .collect::<Result<Vec<Item>, DefinitionError>>()?;
+    if ingredients.is_empty() {return Err(definition.error("recipe without ingredients"))};
+
+    let Some(result) = definition.key_counts("result")?.into_iter().next() else {
+        return Err(definition.require("result").err().unwrap_or_else(|| definition.invalid_value("result", "")));
+    };
+
+    let duration = definition.parse_required::<f32>("duration")?;
+    if !duration.is_finite() || duration < 0.0 {
+        return Err(definition.invalid_value("duration", &duration.to_string()));
+    }
+
+    Ok(Recipe {
+        index: 0,
+        id,
+        key: definition.key.clone(),
+        duration: Duration::from_secs_f32(duration),
+        crafter: parse_flags::<RecipeCrafter>(definition, "crafter")?,
+        category: parse_flags::<RecipeCategory>(definition, "category")?,
+        ingredients,
+        result: item("result", result)?,
+    })
+}
+
+
+fn parse_flags<T: bitflags::Flags>(definition: &Definition, key: &str) -> Result<T, DefinitionError> {
+    let value = definition.require(key)?;
+    value.split('|').map(|name| name.trim()).try_fold(T::empty(), |mut flags, name| {
+        flags.insert(T::from_name(&name.to_uppercase()).ok_or_else(|| definition.invalid_value(key, value))?);
+        Ok(flags)
+    })
+}
+
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use super::{recipe::{Recipe, CraftStation, ActiveRecipe}, item::Item, storage::Storage};
+
+const MAX_DEPTH: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct PlanStep {
+    pub recipe: &'static Recipe,
+    /// Times the recipe is crafted
+    pub crafts: u32,
+    /// Part of the result of all the crafts that is not used by the next steps
+    pub output: u32,
+}
+
+
+/// Crafting of a recipe together with the recipes of the missing ingredients
+#[derive(Debug, Clone)]
+pub struct CraftingPlan {
+    /// Steps in dependency order, the last one is the requested recipe
+    pub steps: Vec<PlanStep>,
+    /// Items taken from the storage when the plan is started
+    pub reserved: Vec<Item>,
+}
+
+
+impl CraftingPlan {
+    pub fn new(recipe: &'static Recipe, storage: &dyn Storage, station: &'static CraftStation<'static>) -> Option<Self> {
+        let mut available = HashMap::<u32, u32>::new();
+        storage.storage().iter().filter_map(|possible_item| possible_item.0).for_each(|item| {
+            *available.entry(item.id()).or_default() += item.count;
+        });
+
+        let mut plan = Self { steps: vec![], reserved: vec![] };
+        plan.add_ingredients(recipe, 1, station, &mut available, 0)?;
+        plan.steps.push(PlanStep { recipe, crafts: 1, output: recipe.result.count });
+        Some(plan)
+    }
+
+
+    fn add_ingredients(&mut self, recipe: &'static Recipe, crafts: u32, station: &'static CraftStation<'static>,
+        available: &mut HashMap<u32, u32>, depth: usize) -> Option<()>
+    {
+        if depth > MAX_DEPTH {return None};
+        for ingredient in recipe.ingredients.iter() {
+            let needed = ingredient.count * crafts;
+            let stored = available.entry(ingredient.id()).or_default();
+            let taken = std::cmp::min(*stored, needed);
+            *stored -= taken;
+            self.reserve(Item::new(ingredient.id(), taken));
+
+            let mut missing = needed - taken;
+            // Leftovers of the previous steps
+            for step in self.steps.iter_mut().filter(|step| step.recipe.result.id() == ingredient.id()) {
+                let used = std::cmp::min(missing, step.output);
+                step.output -= used;
+                missing -= used;
+            }
+
+            if missing > 0 {
+                let ingredient_recipe = station.first_by_result(ingredient.id())?;
+                let crafts = missing.div_ceil(ingredient_recipe.result.count);
+                self.add_ingredients(ingredient_recipe, crafts, station, available, depth + 1)?;
+                self.steps.push(PlanStep { recipe: ingredient_recipe, crafts, output: crafts*ingredient_recipe.result.count - missing });
+            }
+        }
+        Some(())
+    }
+
+
+    fn reserve(&mut self, item: Item) {
+        if item.count == 0 {return};
+        match self.reserved.iter_mut().find(|reserved| reserved.id() == item.id()) {
+            Some(reserved) => reserved.add_count(item.count),
+            None => self.reserved.push(item),
+        }
+    }
+
+
+    pub fn recipe(&self) -> &'static Recipe {
+        self.steps.last().expect("Crafting plan without steps").recipe
+    }
+
+    pub fn intermediates(&self) -> &[PlanStep] {
+        &self.steps[..self.steps.len()-1]
+    }
+
+
+    /// Steps follow each other, so each one starts when the previous one is finished
+    pub fn start(&self, storage: &mut dyn Storage) -> Option<Vec<ActiveRecipe>> {
+        if !storage.is_items_exist(&self.reserved) {return None};
+        storage.remove_items(&self.reserved);
+
+        let mut delay = 0;
+        let last = self.steps.len() - 1;
+        Some(self.steps.iter().enumerate().map(|(i, step)| {
+            let output = Item::new(step.recipe.result.id(), step.output);
+            let active_recipe = ActiveRecipe::new_step(delay, step.recipe.clone(), step.crafts, output, i != last);
+            delay += active_recipe.duration();
+            active_recipe
+        }).collect())
+    }
+}
+
+
+/// Items to return when the remaining steps of a plan are cancelled
+pub fn chain_refund(chain: &[ActiveRecipe]) -> Vec<Item> {
+    let mut counts = Vec::<(u32, i64)>::new();
+    let mut add = |id: u32, count: i64| match counts.iter_mut().find(|(i, _)| *i == id) {
+        Some((_, c)) => *c += count,
+        None => counts.push((id, count)),
+    };
+    for active_recipe in chain {
+        active_recipe.ingredients().iter().for_each(|item| add(item.id(), item.count as i64));
+        if active_recipe.intermediate {
+            let used = active_recipe.recipe.result.count*active_recipe.crafts - active_recipe.output.count;
+            add(active_recipe.recipe.result.id(), -(used as i64));
+        }
+    }
+    counts.into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(id, count)| Item::new(id, count as u32))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{player::inventory::PlayerInventory, recipes::{content::load_content, item::Item, recipe::{CraftStation, Recipe, RecipeCategory, RecipeCrafter}, storage::Storage}, save_load::WorldMetadata};
+    use super::{CraftingPlan, chain_refund};
+
+    fn recipe(index: usize, ingredients: &[(u32, u32)], result: (u32, u32)) -> Recipe {
+        Recipe {
+            index,
+            id: index as u32,
+            key: format!("test_{index}"),
+            duration: Duration::from_secs(1),
+            crafter: RecipeCrafter::PLAYER,
+            category: RecipeCategory::ITEM,
+            ingredients: ingredients.iter().map(|(id, count)| Item::new(*id, *count)).collect(),
+            result: Item::new(result.0, result.1),
+        }
+    }
+
+    fn station(recipes: Vec<Recipe>) -> (&'static [Recipe], &'static CraftStation<'static>) {
+        let recipes: &'static [Recipe] = Box::leak(recipes.into_boxed_slice());
+        (recipes, Box::leak(Box::new(CraftStation::new(recipes, RecipeCrafter::PLAYER))))
+    }
+
+    /// Plates from ore, gears from plates and a machine from gears
+    fn two_levels() -> (&'static [Recipe], &'static CraftStation<'static>) {
+        station(vec![recipe(0, &[(0, 1)], (1, 4)), recipe(1, &[(1, 3)], (2, 1)), recipe(2, &[(2, 2)], (3, 1))])
+    }
+
+    /// Recipe, crafts and output of the steps
+    fn outputs(plan: &CraftingPlan) -> Vec<(usize, u32, u32)> {
+        plan.steps.iter().map(|step| (step.recipe.index, step.crafts, step.output)).collect()
+    }
+
+    #[test]
+    fn two_level_plan_with_leftovers() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let (recipes, station) = two_levels();
+        let mut inventory = PlayerInventory::new();
+        inventory.add_by_index(&Item::new(0, 5), 10);
+
+        let plan = CraftingPlan::new(&recipes[2], &inventory, station).unwrap();
+        // Two gears take six plates, two plate crafts leave two of them
+        assert_eq!(outputs(&plan), vec![(0, 2, 2), (1, 2, 0), (2, 1, 1)]);
+        assert_eq!(plan.reserved, vec![Item::new(0, 2)]);
+        assert_eq!(plan.recipe().index, 2);
+
+        // The stored plates go to the gears, one plate craft makes the rest of them
+        inventory.add_by_index(&Item::new(1, 4), 11);
+        let plan = CraftingPlan::new(&recipes[2], &inventory, station).unwrap();
+        assert_eq!(outputs(&plan), vec![(0, 1, 2), (1, 2, 0), (2, 1, 1)]);
+        assert_eq!(plan.reserved, vec![Item::new(1, 4), Item::new(0, 1)]);
+
+        assert!(CraftingPlan::new(&recipes[2], &PlayerInventory::new(), station).is_none());
+    }
+
+    #[test]
+    fn recipe_cycle_is_cut() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let (recipes, station) = station(vec![recipe(0, &[(5, 1)], (6, 1)), recipe(1, &[(6, 1)], (5, 1))]);
+        assert!(CraftingPlan::new(&recipes[0], &PlayerInventory::new(), station).is_none());
+
+        let mut inventory = PlayerInventory::new();
+        inventory.add_by_index(&Item::new(5, 1), 10);
+        assert_eq!(outputs(&CraftingPlan::new(&recipes[0], &inventory, station).unwrap()), vec![(0, 1, 1)]);
+    }
+
+    #[test]
+    fn refund_after_finished_steps() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let (recipes, station) = two_levels();
+        let mut inventory = PlayerInventory::new();
+        inventory.add_by_index(&Item::new(0, 2), 10);
+        let plan = CraftingPlan::new(&recipes[2], &inventory, station).unwrap();
+        let mut chain = plan.start(&mut inventory).unwrap();
+        assert!(inventory.storage().iter().all(|possible_item| possible_item.0.is_none()));
+
+        // The plates are made, the two the gears don't use go to the inventory
+        let mut first = chain.remove(0);
+        first.advance(first.duration() - 1);
+        assert!(!first.update(&mut inventory), "both crafts take their time");
+        first.advance(1);
+        assert!(first.update(&mut inventory));
+        assert_eq!(chain_refund(&chain), vec![Item::new(1, 6)]);
+
+        assert!(inventory.cancel_recipe(&chain));
+        let contents = inventory.storage().iter().filter_map(|possible_item| possible_item.0).collect::<Vec<_>>();
+        assert_eq!(contents, vec![Item::new(1, 8)]);
+    }
+}
@@ -1,4 +1,9 @@
-const STACK_SIZE: u32 = 100;
+use super::items::ITEMS;
+
+#[inline]
+pub fn stack_size(item_id: u32) -> u32 {
+    ITEMS()[item_id as usize].stack_size
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct PossibleItem(pub Option<Item>);
@@ -16,8 +21,9 @@ impl PossibleItem {
         if let Some(item_src) = &mut self.0 {
             item_src.try_add(item)
         } else {
-            self.0 = Some(Item::new(item.id, std::cmp::min(item.count, STACK_SIZE)));
-            if item.count > STACK_SIZE {return Some(Item::new(item.id, item.count - STACK_SIZE))};
+            let stack_size = item.stack_size();
+            self.0 = Some(Item::new(item.id, std::cmp::min(item.count, stack_size)));
+            if item.count > stack_size {return Some(Item::new(item.id, item.count - stack_size))};
             None
         }
     }
@@ -43,11 +49,11 @@ impl PossibleItem {
     pub fn available_space(&self, item_id: u32) -> u32 {
         self.0.as_ref()
             .map(|item| item.available_space(item_id))
-            .unwrap_or(STACK_SIZE)
+            .unwrap_or_else(|| stack_size(item_id))
     }
 
     pub fn free_space(&self, item_id: u32) -> u32 {
-        self.0.as_ref().map_or(STACK_SIZE, |_| 0)
+        self.0.as_ref().map_or_else(|| stack_size(item_id), |_| 0)
     }
 
     pub fn residual_space(&self, item_id: u32) -> u32 {
@@ -79,7 +85,7 @@ impl PossibleItem {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Item {
     id: u32,
     pub count: u32,
@@ -96,7 +102,7 @@ impl Item {
     pub fn try_add(&mut self, item: &Self) -> Option<Item> {
         if self.id != item.id {return Some(Item::from(item))};
         let sum = self.count + item.count;
-        self.count = std::cmp::min(sum, STACK_SIZE);
+        self.count = std::cmp::min(sum, self.stack_size());
         if sum > self.count {return Some(Item::new(self.id, sum - self.count))}
         None
     }
@@ -113,8 +119,7 @@ impl Item {
 
     pub fn available_space(&self, item_id: u32) -> u32 {
         if self.id != item_id {return 0};
-        if self.count < STACK_SIZE {return STACK_SIZE - self.count};
-        0
+        self.stack_size().saturating_sub(self.count)
     }
 
     pub fn contains(&self, item_id: u32) -> u32 {
@@ -137,6 +142,6 @@ impl Item {
     }
 
     pub fn stack_size(&self) -> u32 {
-        STACK_SIZE
+        stack_size(self.id)
     }
 }
\ No newline at end of file
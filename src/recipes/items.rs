@@ -1,29 +1,75 @@
-use std::sync::OnceLock;
+use std::{collections::HashMap, sync::OnceLock};
 
 use super::item_type::ItemType;
 
-static ITEMS_CONTAINER: OnceLock<Vec<ItemType>> = OnceLock::new();
+/// Items of the game with their lookups by the key and by the block
+#[derive(Debug)]
+struct ItemTable {
+    all: Vec<ItemType>,
+    by_key: HashMap<String, u32>,
+    by_block: HashMap<u32, u32>,
+}
+
+impl ItemTable {
+    fn new(all: Vec<ItemType>) -> Self {
+        // The first item of a key or a block is found, like by a search in the list
+        let by_key = all.iter().rev().map(|item| (item.key.clone(), item.id)).collect();
+        let by_block = all.iter().rev().filter_map(|item| Some((item.block_id?, item.id))).collect();
+        Self { all, by_key, by_block }
+    }
+}
+
+static ITEMS_CONTAINER: OnceLock<ItemTable> = OnceLock::new();
+
+fn item_table() -> &'static ItemTable {
+    ITEMS_CONTAINER.get().expect("Items are not loaded")
+}
+
 #[allow(non_snake_case)]
 pub fn ITEMS() -> &'static [ItemType] {
-    ITEMS_CONTAINER.get_or_init(|| {
-        vec![
-            ItemType::new(0, 100, Some(5)),
-            ItemType::new(1, 100, None),
-            ItemType::new(2, 100, None),
-            ItemType::new(3, 100, Some(7)),
-
-            ItemType::new(4, 50, Some(15)),
-            ItemType::new(5, 50, Some(17)),
-            ItemType::new(6, 50, Some(16)),
-            ItemType::new(7, 50, Some(14)),
-
-            ItemType::new(8, 100, Some(13)),
-            ItemType::new(9, 100, Some(4)),
-            ItemType::new(10, 50, Some(10)),
-            ItemType::new(11, 50, Some(11)),
-
-            ItemType::new(12, 50, Some(9)),
-            ItemType::new(13, 50, Some(12)),
-        ]
-    })
-}
\ No newline at end of file
+    &item_table().all
+}
+
+/// Items are loaded with the first world, because their ids are stored in the world metadata.
+/// They are set once, returns false if the items differ from the set ones
+pub(super) fn set_items(items: Vec<ItemType>) -> bool {
+    let mut items = Some(items);
+    let table = ITEMS_CONTAINER.get_or_init(|| ItemTable::new(items.take().unwrap()));
+    items.is_none_or(|items| table.all == items)
+}
+
+pub fn item_by_key(key: &str) -> Option<&'static ItemType> {
+    let table = item_table();
+    table.by_key.get(key).map(|id| &table.all[*id as usize])
+}
+
+pub fn block_item(block_id: u32) -> Option<&'static ItemType> {
+    let table = item_table();
+    table.by_block.get(&block_id).map(|id| &table.all[*id as usize])
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{recipes::content::load_content, save_load::WorldMetadata, voxels::block::blocks::block_by_name};
+
+    use super::{ITEMS, block_item, item_by_key};
+
+    #[test]
+    fn items_are_set_once() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let items = ITEMS().as_ptr();
+        load_content(&mut WorldMetadata::default()).unwrap();
+        assert_eq!(ITEMS().as_ptr(), items);
+        // A world with other ids doesn't change the items of the game
+        let mut other = WorldMetadata::default();
+        other.items.push((String::from("removed_item"), 0));
+        assert!(load_content(&mut other).is_err());
+        assert_eq!(ITEMS().as_ptr(), items);
+
+        assert_eq!(item_by_key("iron_plate").map(|item| item.key.as_str()), Some("iron_plate"));
+        assert!(item_by_key("not_an_item").is_none());
+        let belt = block_by_name("transport_belt").unwrap().id();
+        assert_eq!(block_item(belt).and_then(|item| item.block_id), Some(belt));
+    }
+}
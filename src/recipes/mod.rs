@@ -4,4 +4,6 @@ pub mod recipes;
 pub mod storage;
 pub mod item_interaction;
 pub mod item_type;
-pub mod items;
\ No newline at end of file
+pub mod items;
+pub mod content;
+pub mod crafting_plan;
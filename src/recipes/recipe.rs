@@ -1,12 +1,14 @@
-use std::{time::{Instant, Duration}, collections::HashMap};
+use std::{time::Duration, collections::HashMap};
 
 use bitflags::bitflags;
 
+use crate::world::ticks::duration_ticks;
+
 use super::{storage::Storage, item::Item};
 
 
 bitflags! {
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct RecipeCrafter: u8 {
         const PLAYER = 0b1;
         const ASSEMBLER = 0b10;
@@ -17,7 +19,7 @@ bitflags! {
 }
 
 bitflags! {
-    #[derive(Debug, Clone, Copy)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct RecipeCategory: u8 {
         const ITEM = 0b1;
         const BLOCK = 0b10;
@@ -27,34 +29,65 @@ bitflags! {
 
 #[derive(Debug)]
 pub struct ActiveRecipe {
-    start_time: Instant,
+    /// Simulation ticks since the recipe was started
+    ticks: u32,
+    /// Ticks of the previous steps of a crafting plan, the step waits for them
+    delay: u32,
     pub recipe: Recipe,
+    /// Times a step of a crafting plan crafts the recipe, a single recipe is crafted once
+    pub crafts: u32,
+    /// Part of the result that goes to the storage
+    pub output: Item,
+    /// Step of a crafting plan, the rest of the result is used by the next steps
+    pub intermediate: bool,
 }
 
 impl ActiveRecipe {
-    pub fn new(start_time: Instant, recipe: Recipe) -> Self {
-        Self { start_time, recipe }
+    pub fn new(recipe: Recipe) -> Self {
+        Self { ticks: 0, delay: 0, output: recipe.result, recipe, crafts: 1, intermediate: false }
+    }
+
+    pub fn new_step(delay: u32, recipe: Recipe, crafts: u32, output: Item, intermediate: bool) -> Self {
+        Self { ticks: 0, delay, recipe, crafts, output, intermediate }
+    }
+
+    /// Ticks all the crafts take
+    pub fn duration(&self) -> u32 {
+        duration_ticks(self.recipe.duration) * self.crafts
+    }
+
+    /// Ingredients of all the crafts
+    pub fn ingredients(&self) -> Vec<Item> {
+        self.recipe.ingredients.iter().map(|item| Item::new(item.id(), item.count * self.crafts)).collect()
+    }
+
+    pub fn advance(&mut self, ticks: u32) {
+        self.ticks = self.ticks.saturating_add(ticks);
     }
 
     pub fn cancel(&self, storage: &mut dyn Storage) -> bool {
-        if storage.is_spaces_exist(&self.recipe.ingredients[..]) {
-            storage.add_items(&self.recipe.ingredients[..]);
+        let ingredients = self.ingredients();
+        if storage.is_spaces_exist(&ingredients[..]) {
+            storage.add_items(&ingredients[..]);
             return true;
         }
         false
     }
 
+    /// Progress of the current craft
     pub fn progress(&self) -> f32 {
-        self.start_time.elapsed().as_secs_f32() / self.recipe.duration.as_secs_f32() % 1.0
+        self.ticks.saturating_sub(self.delay) as f32 / duration_ticks(self.recipe.duration).max(1) as f32 % 1.0
     }
 
     pub fn is_finished(&self) -> bool {
-        self.start_time.elapsed() > self.recipe.duration
+        self.ticks >= self.delay + self.duration()
     }
 
     pub fn update(&self, storage: &mut dyn Storage) -> bool {
-        if self.is_finished() && storage.is_space_exist(&self.recipe.result) {
-            storage.add(&self.recipe.result, false);
+        if !self.is_finished() {return false};
+        if self.output.count == 0 {return true};
+        if storage.is_space_exist(&self.output) {
+            storage.add(&self.output, false);
             return true;
         }
         false
@@ -62,10 +95,11 @@ impl ActiveRecipe {
 }
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Recipe {
     pub index: usize,
     pub id: u32,
+    pub key: String,
     pub duration: Duration,
     pub crafter: RecipeCrafter,
     pub category: RecipeCategory,
@@ -78,13 +112,13 @@ impl Recipe {
     pub fn start(&self, storage: &mut dyn Storage) -> Option<ActiveRecipe> {
         if storage.is_items_exist(&self.ingredients[..]) {
             storage.remove_items(&self.ingredients[..]);
-            return Some(ActiveRecipe { start_time: Instant::now(), recipe: self.clone() });
+            return Some(ActiveRecipe::new(self.clone()));
         }
         None
     }
 
     pub fn start_absolute(&self) -> ActiveRecipe {
-        ActiveRecipe { start_time: Instant::now(), recipe: self.clone() }
+        ActiveRecipe::new(self.clone())
     }
 }
 
@@ -114,7 +148,7 @@ impl<'a> CraftStation<'a> {
             });
 
             result_recipe
-                .entry(recipe.id)
+                .entry(recipe.result.id())
                 .and_modify(|v| v.push(recipe))
                 .or_insert(vec![recipe]);
         });
@@ -144,6 +178,7 @@ impl<'a> CraftStation<'a> {
 #[derive(Debug)]
 pub struct Recipes {
     pub all: &'static [Recipe],
+    by_id: HashMap<u32, &'static Recipe>,
     pub furnace: CraftStation<'static>,
     pub assembler: CraftStation<'static>,
     pub player: CraftStation<'static>,
@@ -153,9 +188,14 @@ impl Recipes {
     pub fn new(all: &'static [Recipe]) -> Self {
         Self {
             all,
+            by_id: all.iter().map(|recipe| (recipe.id, recipe)).collect(),
             furnace: CraftStation::new(all, RecipeCrafter::FURNACE),
             player: CraftStation::new(all, RecipeCrafter::PLAYER),
             assembler: CraftStation::new(all, RecipeCrafter::ASSEMBLER)
         }
     }
+
+    pub fn by_id(&self, id: u32) -> Option<&'static Recipe> {
+        self.by_id.get(&id).copied()
+    }
 }
@@ -1,7 +1,7 @@
-use crate::bytes::{BytesCoder, AsFromBytes};
+use crate::bytes::{BytesCoder, AsFromBytes, DecodeError, cast_vec_from_bytes};
 
-use super::{item::{PossibleItem, Item}, recipe::{ActiveRecipe, Recipe}};
-use std::{fmt::Debug, time::Instant};
+use super::{item::{PossibleItem, Item}, recipe::{ActiveRecipe, Recipe}, items::ITEMS, crafting_plan::chain_refund};
+use std::fmt::Debug;
 
 pub trait Storage {
     fn storage(&self) -> & [PossibleItem];
@@ -109,17 +109,50 @@ pub trait Storage {
         self.mut_storage()[index].0 = Some(Item::from(item));
     }
 
+    /// Whether the player can put the item into the slot
+    fn accepts(&self, _index: usize, _item: &Item) -> bool {true}
+
+    /// Puts the held item into the slot, or picks up the slot item if nothing is held
+    fn place_held(&mut self, index: usize, held: &mut PossibleItem) {
+        let Some(item) = held.0 else {
+            held.0 = self.mut_storage()[index].0.take();
+            return;
+        };
+        if !self.accepts(index, &item) {return};
+        match self.storage()[index].0 {
+            Some(slot_item) if slot_item.id() != item.id() => {
+                std::mem::swap(&mut self.mut_storage()[index].0, &mut held.0);
+            },
+            _ => held.0 = self.mut_storage()[index].try_add_item(&item),
+        }
+    }
+
+    /// Picks up half of the slot stack, or puts one held item into the slot
+    fn split_held(&mut self, index: usize, held: &mut PossibleItem) {
+        let Some(item) = held.0 else {
+            let Some(slot_item) = self.storage()[index].0 else {return};
+            held.0 = self.mut_storage()[index].try_take(slot_item.count.div_ceil(2));
+            return;
+        };
+        let one = Item::new(item.id(), 1);
+        if !self.accepts(index, &one) || !self.storage()[index].is_possible_add(&one) {return};
+        self.mut_storage()[index].try_add_item(&one);
+        held.try_sub_item(&one);
+    }
+
     fn start_recipe(&mut self, recipe: &Recipe) -> Option<ActiveRecipe> {
         if self.is_items_exist(&recipe.ingredients[..]) {
             self.remove_items(&recipe.ingredients[..]);
-            return Some(ActiveRecipe::new(Instant::now(), recipe.clone()));
+            return Some(ActiveRecipe::new(recipe.clone()));
         }
         None
     }
 
-    fn cancel_recipe(&mut self, recipe: &Recipe) -> bool {
-        if self.is_spaces_exist(&recipe.ingredients[..]) {
-            self.add_items(&recipe.ingredients[..]);
+    /// Returns the ingredients of the remaining steps of a crafting plan, a single recipe is a plan of one step
+    fn cancel_recipe(&mut self, chain: &[ActiveRecipe]) -> bool {
+        let refund = chain_refund(chain);
+        if self.is_spaces_exist(&refund[..]) {
+            self.add_items(&refund[..]);
             return true;
         }
         false
@@ -127,6 +160,29 @@ pub trait Storage {
 }
 
 
+/// Merges stacks of the same items and orders them by id
+pub fn sort_items(storage: &mut [PossibleItem]) {
+    let mut items: Vec<Item> = vec![];
+    storage.iter_mut().filter_map(|possible_item| possible_item.0.take()).for_each(|item| {
+        match items.iter_mut().find(|i| i.id() == item.id()) {
+            Some(i) => i.add_count(item.count),
+            None => items.push(item),
+        }
+    });
+    items.sort_by_key(|item| item.id());
+
+    let mut slots = storage.iter_mut();
+    for mut item in items {
+        while item.count > 0 {
+            let Some(slot) = slots.next() else {return};
+            let count = std::cmp::min(item.count, item.stack_size());
+            slot.0 = Some(Item::new(item.id(), count));
+            item.count -= count;
+        }
+    }
+}
+
+
 impl Debug for dyn Storage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "Storage: {:?}", self.storage())
@@ -160,12 +216,82 @@ impl<const N: usize> BytesCoder for [PossibleItem; N] {
         bytes.into()
     }
 
-    fn decode_bytes(bytes: &[u8]) -> Self {
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         let mut storage: [PossibleItem; N] = [PossibleItem::new_none(); N];
-        bytes.chunks(12).for_each(|header_bytes| {
-            let header = ItemHeader::from_bytes(header_bytes);
-            storage[header.index as usize] = PossibleItem::new(header.id, header.count);
-        });
-        storage
+        let mut oversized = vec![];
+        for header in cast_vec_from_bytes::<ItemHeader>(bytes)? {
+            let index = header.index as usize;
+            if index >= N || storage[index].0.is_some() {return Err(DecodeError::InvalidValue { name: "slot", value: index as u64 })};
+            if header.id as usize >= ITEMS().len() {return Err(DecodeError::InvalidValue { name: "item id", value: header.id as u64 })};
+            let mut item = Item::new(header.id, header.count);
+            if item.count > item.stack_size() {
+                oversized.push((index, Item::new(item.id(), item.count - item.stack_size())));
+                item.count = item.stack_size();
+            }
+            storage[index] = PossibleItem(Some(item));
+        }
+
+        // Stacks saved with a bigger stack size are split, first into the stacks of the same item
+        // and then into the empty slots. What does not fit is dropped, no slot is over the stack size
+        for (index, mut item) in oversized {
+            for is_same_item in [true, false] {
+                for possible_item in storage.iter_mut() {
+                    if item.count == 0 {break};
+                    let fits = match possible_item.0 {
+                        Some(stack) => is_same_item && stack.id() == item.id(),
+                        None => !is_same_item,
+                    };
+                    if !fits {continue};
+                    item = possible_item.try_add_item(&item).unwrap_or(Item::new(item.id(), 0));
+                }
+            }
+            if item.count > 0 {
+                eprintln!("{} items {} of the slot {} don't fit in the storage and are dropped", item.count, item.id(), index);
+            }
+        }
+        Ok(storage)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::{bytes::{AsFromBytes, BytesCoder}, recipes::{content::load_content, item::{PossibleItem, stack_size}}, save_load::WorldMetadata};
+    use super::ItemHeader;
+
+    fn encode(headers: &[(u32, u32, u32)]) -> Vec<u8> {
+        headers.iter().flat_map(|(index, id, count)| ItemHeader::new(*index, *id, *count).as_bytes().to_vec()).collect()
+    }
+
+    fn counts<const N: usize>(storage: &[PossibleItem; N]) -> Vec<Option<(u32, u32)>> {
+        storage.iter().map(|possible_item| possible_item.0.map(|item| (item.id(), item.count))).collect()
     }
-}
\ No newline at end of file
+
+    fn total<const N: usize>(storage: &[PossibleItem; N]) -> u32 {
+        storage.iter().filter_map(|possible_item| possible_item.0).map(|item| item.count).sum()
+    }
+
+    #[test]
+    fn oversized_stack_split() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let size = stack_size(0);
+        let bytes = encode(&[(0, 0, 2*size + 5), (2, 0, size - 3)]);
+        let storage = <[PossibleItem; 5]>::decode_bytes(&bytes).unwrap();
+        assert_eq!(counts(&storage), vec![Some((0, size)), Some((0, size)), Some((0, size)), Some((0, 2)), None]);
+        assert_eq!(total(&storage), 3*size + 2);
+
+        // What does not fit in the storage is dropped
+        let storage = <[PossibleItem; 2]>::decode_bytes(&encode(&[(1, 0, 5*size)])).unwrap();
+        assert_eq!(counts(&storage), vec![Some((0, size)), Some((0, size))]);
+        assert!(storage.iter().filter_map(|possible_item| possible_item.0).all(|item| item.count <= size));
+        let storage = <[PossibleItem; 2]>::decode_bytes(&storage.encode_bytes()).unwrap();
+        assert_eq!(total(&storage), 2*size);
+    }
+
+    #[test]
+    fn duplicate_slot_rejected() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let size = stack_size(0);
+        assert!(<[PossibleItem; 2]>::decode_bytes(&encode(&[(0, 0, 4*size), (0, 0, 1)])).is_err());
+    }
+}
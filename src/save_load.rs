@@ -1,27 +1,42 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use crate::bytes::{BytesCoder, cast_vec_from_bytes};
+use crate::bytes::{BytesCoder, DecodeError, slice_bytes, cast_vec_from_bytes, cast_bytes_from_slice};
 use crate::player::player::Player;
 use crate::voxels::chunk::Chunk;
-use crate::voxels::chunks::WORLD_HEIGHT;
 use crate::world::chunk_coords::ChunkCoords;
 use crate::bytes::AsFromBytes;
-use crate::UnsafeMutex;
+use crate::definitions::{Definitions, DefinitionError};
+use crate::world::game_mode::GameMode;
+use crate::world::height_range::{HeightRange, DEFAULT_HEIGHT_RANGE};
+
+pub const WORLDS_PATH: &str = "./data/worlds/";
+pub const BACKUPS_PATH: &str = "./data/backups/";
 
 // Must be a power of two
 const REGION_SIZE: usize = 32;
 const REGION_SIZE_BITS: usize = REGION_SIZE - 1;
 const REGION_SQUARE: usize = REGION_SIZE*REGION_SIZE;
 const REGION_BIT_SHIFT: usize = REGION_SIZE.ilog2() as usize;
-const REGION_VOLUME: usize = REGION_SQUARE*WORLD_HEIGHT;
 
 const REGION_MAGIC_NUMBER: u64 = 0x4474_304E_7AD7_835A;
-const REGION_FORMAT_VERSION: u32 = 2;
+/// The version 3 layout, a change of it needs the next version and a conversion:
+/// - sector 0: `WorldRegionsHeader`, 24 bytes with the header checksum at 16 and the bottom at 20
+/// - sector 1: `RegionTableEntry` of 16 bytes for every chunk of the region, x then y then z
+/// - the chunk data, every chunk starts at a sector and is padded to the sector size
+const REGION_FORMAT_VERSION: u32 = 3;
+/// Version 2 stores all chunks one after another, the header ends after the depth
+const REGION_HEADER_SIZE_V2: usize = 16;
+
+/// Region files are allocated in sectors, the header takes the first one and the chunk table follows
+const SECTOR_SIZE: usize = 4096;
 
-const NONE_ENCODED_CHUNK: EncodedChunk = EncodedChunk::None;
+/// Damaged region files and chunks are moved here
+const QUARANTINE_DIR: &str = "quarantine/";
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -47,6 +62,25 @@ impl RegionCoords {
     pub fn filename(&self) -> String {
         self.0.to_string() + "_" + &self.1.to_string() + ".bin"
     }
+
+    /// Region files are stored in the regions directory of the world
+    pub fn path(&self, world: &Path) -> PathBuf {
+        world.join("regions/").join(self.filename())
+    }
+
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let (x, z) = filename.strip_suffix(".bin")?.split_once('_')?;
+        Some(Self(x.parse().ok()?, z.parse().ok()?))
+    }
+
+    /// Coordinates of the chunk at the index of a region of the height range
+    pub fn chunk_coords(&self, index: usize, heights: HeightRange) -> ChunkCoords {
+        let height = heights.height() as usize;
+        let z = index % REGION_SIZE;
+        let y = (index / REGION_SIZE % height) as i32 + heights.bottom;
+        let x = index / (REGION_SIZE*height);
+        ChunkCoords((self.0 << REGION_BIT_SHIFT) + x as i32, y, (self.1 << REGION_BIT_SHIFT) + z as i32)
+    }
 }
 
 impl From<ChunkCoords> for RegionCoords {
@@ -55,21 +89,78 @@ impl From<ChunkCoords> for RegionCoords {
     }
 }
 
+/// Writes a temporary file and renames it over the file, so a crash never leaves a truncated file
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)?;
+    // The rename is only durable once the directory entry is on the disk
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::File::open(parent)?.sync_all()
+}
+
+/// Writes the data of a chunk that can't be loaded to the quarantine directory of the world
+fn quarantine_chunk_data(world: &Path, coords: ChunkCoords, bytes: &[u8]) -> std::io::Result<()> {
+    let path = world.join(QUARANTINE_DIR);
+    fs::create_dir_all(&path)?;
+    fs::write(path.join(format!("chunk_{}_{}_{}.bin", coords.0, coords.1, coords.2)), bytes)
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(bytes);
+    crc.sum()
+}
+
+/// Chunks in a region of the height range
+fn region_volume(heights: HeightRange) -> usize {
+    REGION_SQUARE*heights.height() as usize
+}
+
+fn table_size(heights: HeightRange) -> usize {
+    region_volume(heights)*RegionTableEntry::SIZE
+}
+
+/// The first sector after the header and the chunk table
+fn first_data_sector(heights: HeightRange) -> usize {
+    1 + table_size(heights).div_ceil(SECTOR_SIZE)
+}
+
+/// First run of free sectors long enough for the count, the file grows when there is no such gap
+fn allocate_sectors(used: &mut Vec<bool>, count: usize) -> usize {
+    let start = (0..=used.len())
+        .find(|start| used[*start..(start+count).min(used.len())].iter().all(|used| !used))
+        .unwrap_or(used.len());
+    if used.len() < start+count {used.resize(start+count, false)};
+    used[start..start+count].fill(true);
+    start
+}
+
 pub trait RegionChunkIndex {
-    fn region_chunk_index(&self) -> usize;
+    /// None outside the region of the height range
+    fn region_chunk_index(&self, heights: HeightRange) -> Option<usize>;
 }
 
 impl RegionChunkIndex for ChunkCoords {
-    fn region_chunk_index(&self) -> usize {
+    fn region_chunk_index(&self, heights: HeightRange) -> Option<usize> {
+        if !heights.contains(self.1) {return None};
         let x = self.0 as usize & REGION_SIZE_BITS;
-        let y = self.1 as usize & (WORLD_HEIGHT - 1);
+        let y = (self.1 - heights.bottom) as usize;
         let z = self.2 as usize & REGION_SIZE_BITS;
 
-        (x * WORLD_HEIGHT + y) * REGION_SIZE + z
+        Some((x * heights.height() as usize + y) * REGION_SIZE + z)
+    }
+}
+
+impl RegionChunkIndex for usize {
+    fn region_chunk_index(&self, heights: HeightRange) -> Option<usize> {
+        (*self < region_volume(heights)).then_some(*self)
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub enum EncodedChunk{
     // Fields for special chunks can be added
     #[default]
@@ -77,26 +168,261 @@ pub enum EncodedChunk{
     Some(Box<[u8]>),
 }
 
+
+/// Place of a chunk in the region file, the sector 0 means the region has no such chunk
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct RegionTableEntry {
+    sector: u32,
+    length: u32,
+    /// CRC32 of the chunk data
+    checksum: u32,
+    _reserved: u32,
+}
+impl AsFromBytes for RegionTableEntry {}
+
+impl RegionTableEntry {
+    fn new(sector: usize, data: &[u8]) -> Self {
+        Self { sector: sector as u32, length: data.len() as u32, checksum: checksum(data), _reserved: 0 }
+    }
+
+    fn sectors(&self) -> Range<usize> {
+        let start = self.sector as usize;
+        start..start + (self.length as usize).div_ceil(SECTOR_SIZE).max(1)
+    }
+}
+
+
+/// Region file with the chunk table in memory, chunks are read and written one by one
 #[derive(Debug)]
 pub struct Region {
-    unsaved: bool,
-    chunks: Box<[EncodedChunk; REGION_VOLUME]>
+    path: PathBuf,
+    heights: HeightRange,
+    table: Box<[RegionTableEntry]>,
+    /// Open for the whole life of the region, None until a new region is written
+    file: Option<fs::File>,
+    /// Chunks waiting for the next save, they replace the chunks of the file
+    unsaved: BTreeMap<usize, EncodedChunk>,
+    /// The file is missing or has an older format and is written whole on the next save
+    rewrite: bool,
 }
 
 impl Region {
-    pub fn new_empty() -> Self {
-        Self { chunks: Box::new([NONE_ENCODED_CHUNK; REGION_VOLUME]), unsaved: false }
+    pub fn new_empty(path: PathBuf, heights: HeightRange) -> Self {
+        Self {
+            path,
+            heights,
+            table: vec![RegionTableEntry::default(); region_volume(heights)].into_boxed_slice(),
+            file: None,
+            unsaved: BTreeMap::new(),
+            rewrite: true,
+        }
     }
 
-    pub fn chunk(&self, coords: impl RegionChunkIndex) -> &EncodedChunk {
-        &self.chunks[coords.region_chunk_index()]
+    /// Reads the header and the chunk table of the region of the world,
+    /// a file of an older format or another height range is converted.
+    /// Chunks outside the height range are moved to the quarantine directory of the world
+    pub fn open(world: &Path, coords: RegionCoords, heights: HeightRange) -> Result<Self, DecodeError> {
+        let path = coords.path(world);
+        let file = fs::OpenOptions::new().read(true).write(true).open(&path).map_err(DecodeError::Io)?;
+        let region = Self::decode(path, file)?;
+        if region.heights == heights && !region.rewrite {return Ok(region)};
+
+        let chunks = region.stored_chunks().into_iter()
+            .map(|index| Ok((coords.chunk_coords(index, region.heights), region.chunk(index)?)))
+            .collect::<Result<Vec<(ChunkCoords, EncodedChunk)>, DecodeError>>()?;
+        let mut converted = Self::new_empty(region.path, heights);
+        for (chunk_coords, chunk) in converted.insert_moved(chunks) {
+            eprintln!("Chunk {:?} is outside the heights {}..{} and is quarantined", chunk_coords, heights.bottom, heights.top);
+            let EncodedChunk::Some(bytes) = chunk else {continue};
+            // The converted file replaces the old one, so the chunk must be kept before
+            quarantine_chunk_data(world, chunk_coords, &bytes).map_err(DecodeError::Io)?;
+        }
+        match converted.save() {
+            Ok(()) => println!("Region {} is converted to the version {} and the heights {}..{}",
+                converted.path.display(), REGION_FORMAT_VERSION, heights.bottom, heights.top),
+            Err(err) => eprintln!("Region conversion error: {}", err),
+        }
+        Ok(converted)
+    }
+
+    /// Reads the region as it is in the file without converting or writing it
+    pub fn read(path: PathBuf) -> Result<Self, DecodeError> {
+        let file = fs::File::open(&path).map_err(DecodeError::Io)?;
+        Self::decode(path, file)
+    }
+
+    /// The region has the height range of the file, a file of the version 2 is read whole and has to be rewritten
+    fn decode(path: PathBuf, mut file: fs::File) -> Result<Self, DecodeError> {
+        let mut head = vec![];
+        (&mut file).take(SECTOR_SIZE as u64).read_to_end(&mut head).map_err(DecodeError::Io)?;
+        let magic_number = u64::from_bytes_at(&head, 0)?;
+        if magic_number != REGION_MAGIC_NUMBER {
+            return Err(DecodeError::InvalidValue { name: "magic number", value: magic_number });
+        }
+        let version = u32::from_bytes_at(&head, 8)?;
+        if version == 2 {
+            let bytes = fs::read(&path).map_err(DecodeError::Io)?;
+            return Self::decode_v2(path, &bytes);
+        }
+        if version != REGION_FORMAT_VERSION {
+            return Err(DecodeError::InvalidValue { name: "region format version", value: version as u64 });
+        }
+
+        let header = WorldRegionsHeader::from_bytes_at(&head, 0)?;
+        if header.checksum != header.header_checksum() {
+            return Err(DecodeError::InvalidValue { name: "region header checksum", value: header.checksum as u64 });
+        }
+        let file_heights = header.heights()?;
+        let mut table = vec![0; table_size(file_heights)];
+        file.seek(SeekFrom::Start(SECTOR_SIZE as u64)).map_err(DecodeError::Io)?;
+        file.read_exact(&mut table).map_err(DecodeError::Io)?;
+        let table = cast_vec_from_bytes::<RegionTableEntry>(&table)?;
+        if let Some(entry) = table.iter().find(|entry| entry.sector != 0 && (entry.sector as usize) < first_data_sector(file_heights)) {
+            return Err(DecodeError::InvalidValue { name: "chunk sector", value: entry.sector as u64 });
+        }
+        Ok(Self { path, heights: file_heights, table: table.into_boxed_slice(), file: Some(file), unsaved: BTreeMap::new(), rewrite: false })
+    }
+
+    /// Puts the chunks of a region of another height range at their indices in this region,
+    /// returns the chunks outside the height range
+    fn insert_moved(&mut self, chunks: Vec<(ChunkCoords, EncodedChunk)>) -> Vec<(ChunkCoords, EncodedChunk)> {
+        chunks.into_iter().filter_map(|(coords, chunk)| match coords.region_chunk_index(self.heights) {
+            Some(index) => {self.unsaved.insert(index, chunk); None},
+            None => Some((coords, chunk)),
+        }).collect()
+    }
+
+    /// Version 2 stores a length table and all chunks one after another, they are kept unsaved.
+    /// Its chunks start at zero
+    fn decode_v2(path: PathBuf, bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut header = [0; WorldRegionsHeader::SIZE];
+        header[..REGION_HEADER_SIZE_V2].copy_from_slice(slice_bytes(bytes, 0..REGION_HEADER_SIZE_V2)?);
+        let mut header = WorldRegionsHeader::from_bytes(&header)?;
+        header.bottom = 0;
+        let file_heights = header.heights()?;
+        let offsets_end = REGION_HEADER_SIZE_V2+std::mem::size_of::<u32>()*region_volume(file_heights);
+        let offsets = cast_vec_from_bytes::<u32>(slice_bytes(bytes, REGION_HEADER_SIZE_V2..offsets_end)?)?;
+        let mut chunk_offset = offsets_end;
+        let mut chunks = vec![];
+        for (i, offset) in offsets.into_iter().enumerate().filter(|(_, offset)| *offset != 0) {
+            let chunk_bytes = slice_bytes(bytes, chunk_offset..offset as usize+chunk_offset)?;
+            chunk_offset += offset as usize;
+            chunks.push((i, EncodedChunk::Some(chunk_bytes.into())));
+        }
+        let mut region = Self::new_empty(path, file_heights);
+        region.unsaved.extend(chunks);
+        Ok(region)
+    }
+
+    fn read_entry(&self, entry: &RegionTableEntry) -> std::io::Result<Box<[u8]>> {
+        let mut file = self.file.as_ref().ok_or(std::io::ErrorKind::NotFound)?;
+        file.seek(SeekFrom::Start((entry.sector as usize*SECTOR_SIZE) as u64))?;
+        let mut bytes = vec![0; entry.length as usize];
+        file.read_exact(&mut bytes)?;
+        Ok(bytes.into_boxed_slice())
+    }
+
+    /// Reads only the sectors of the chunk, a chunk with a wrong checksum is an error
+    pub fn chunk(&self, coords: impl RegionChunkIndex) -> Result<EncodedChunk, DecodeError> {
+        let Some(index) = coords.region_chunk_index(self.heights) else {return Ok(EncodedChunk::None)};
+        if let Some(chunk) = self.unsaved.get(&index) {return Ok(chunk.clone())};
+        let entry = self.table[index];
+        if entry.sector == 0 {return Ok(EncodedChunk::None)};
+        let bytes = self.read_entry(&entry).map_err(DecodeError::Io)?;
+        let checksum = checksum(&bytes);
+        if checksum != entry.checksum {
+            return Err(DecodeError::InvalidValue { name: "chunk checksum", value: checksum as u64 });
+        }
+        Ok(EncodedChunk::Some(bytes))
+    }
+
+    pub fn heights(&self) -> HeightRange {self.heights}
+
+    /// Indices of the chunks the region has
+    pub fn stored_chunks(&self) -> Vec<usize> {
+        (0..region_volume(self.heights)).filter(|index| match self.unsaved.get(index) {
+            Some(chunk) => matches!(chunk, EncodedChunk::Some(_)),
+            None => self.table[*index].sector != 0,
+        }).collect()
     }
 
     pub fn save_chunk(&mut self, coords: impl RegionChunkIndex, data: Box<[u8]>) {
-        if let Some(chunk) = self.chunks.get_mut(coords.region_chunk_index()) {
-            *chunk = EncodedChunk::Some(data);
-            self.unsaved = true;
+        let Some(index) = coords.region_chunk_index(self.heights) else {return};
+        self.unsaved.insert(index, EncodedChunk::Some(data));
+    }
+
+    /// Forgets the chunk and returns its data without checking it
+    pub fn remove_chunk(&mut self, coords: impl RegionChunkIndex) -> Option<Box<[u8]>> {
+        let index = coords.region_chunk_index(self.heights)?;
+        match self.unsaved.insert(index, EncodedChunk::None) {
+            Some(EncodedChunk::Some(bytes)) => Some(bytes),
+            Some(EncodedChunk::None) => None,
+            None => Some(self.table[index]).filter(|entry| entry.sector != 0).and_then(|entry| self.read_entry(&entry).ok()),
+        }
+    }
+
+    /// Writes the unsaved chunks to free sectors and then points the table at them,
+    /// after a crash the table points at either the old or the new data of a chunk
+    pub fn save(&mut self) -> std::io::Result<()> {
+        if self.unsaved.is_empty() {return Ok(())};
+        if self.rewrite {return self.write_file()};
+
+        let mut file = self.file.as_ref().ok_or(std::io::ErrorKind::NotFound)?;
+        let mut used = vec![true; first_data_sector(self.heights)];
+        self.table.iter().filter(|entry| entry.sector != 0).for_each(|entry| {
+            let sectors = entry.sectors();
+            if used.len() < sectors.end {used.resize(sectors.end, false)};
+            used[sectors].fill(true);
+        });
+
+        let mut entries = Vec::with_capacity(self.unsaved.len());
+        for (index, chunk) in self.unsaved.iter() {
+            let entry = match chunk {
+                EncodedChunk::None => RegionTableEntry::default(),
+                EncodedChunk::Some(data) => {
+                    let sector = allocate_sectors(&mut used, data.len().div_ceil(SECTOR_SIZE).max(1));
+                    let mut padded = data.to_vec();
+                    padded.resize(padded.len().div_ceil(SECTOR_SIZE).max(1)*SECTOR_SIZE, 0);
+                    file.seek(SeekFrom::Start((sector*SECTOR_SIZE) as u64))?;
+                    file.write_all(&padded)?;
+                    RegionTableEntry::new(sector, data)
+                },
+            };
+            entries.push((*index, entry));
+        }
+        // The chunks have to be on the disk before the table points at them
+        file.sync_data()?;
+
+        for (index, entry) in entries.iter() {
+            file.seek(SeekFrom::Start((SECTOR_SIZE + index*RegionTableEntry::SIZE) as u64))?;
+            file.write_all(entry.as_bytes())?;
+        }
+        file.sync_data()?;
+        entries.into_iter().for_each(|(index, entry)| self.table[index] = entry);
+        self.unsaved.clear();
+        Ok(())
+    }
+
+    /// Writes the whole file, the table is empty for new and converted regions
+    fn write_file(&mut self) -> std::io::Result<()> {
+        let mut table = vec![RegionTableEntry::default(); region_volume(self.heights)];
+        let mut bytes = vec![0; first_data_sector(self.heights)*SECTOR_SIZE];
+        for (index, chunk) in self.unsaved.iter() {
+            let EncodedChunk::Some(data) = chunk else {continue};
+            table[*index] = RegionTableEntry::new(bytes.len() / SECTOR_SIZE, data);
+            bytes.extend(data.iter());
+            bytes.resize(bytes.len().next_multiple_of(SECTOR_SIZE), 0);
         }
+        bytes[..WorldRegionsHeader::size()].copy_from_slice(WorldRegionsHeader::new(self.heights).as_bytes());
+        bytes[SECTOR_SIZE..SECTOR_SIZE+table_size(self.heights)].copy_from_slice(cast_bytes_from_slice(&table));
+        write_atomic(&self.path, &bytes)?;
+
+        self.file = Some(fs::OpenOptions::new().read(true).write(true).open(&self.path)?);
+        self.table = table.into_boxed_slice();
+        self.unsaved.clear();
+        self.rewrite = false;
+        Ok(())
     }
 }
 
@@ -109,29 +435,94 @@ pub struct WorldRegionsHeader {
     width: u8,
     height: u8,
     depth: u8,
+    /// Checksum of the header with this field zeroed, the heights of a damaged header would misplace the chunks
+    checksum: u32,
+    /// The lowest chunk y, zero in the files written before the height range was configurable
+    bottom: i32,
+}
+impl AsFromBytes for WorldRegionsHeader {
+    fn validate(bytes: &[u8]) -> Result<(), DecodeError> {
+        let format_type = bytes[std::mem::offset_of!(WorldRegionsHeader, format_type)];
+        if format_type > RegionFormatType::Blueprint as u8 {
+            return Err(DecodeError::InvalidValue { name: "region format type", value: format_type as u64 });
+        }
+        Ok(())
+    }
+}
+
+impl WorldRegionsHeader {
+    fn new(heights: HeightRange) -> Self {
+        let header = Self {
+            magic_number: REGION_MAGIC_NUMBER,
+            format_version: REGION_FORMAT_VERSION,
+            format_type: RegionFormatType::Region,
+            width: REGION_SIZE as u8,
+            height: heights.height() as u8,
+            depth: REGION_SIZE as u8,
+            checksum: 0,
+            bottom: heights.bottom,
+        };
+        Self { checksum: header.header_checksum(), ..header }
+    }
+
+    fn header_checksum(&self) -> u32 {
+        checksum(Self { checksum: 0, ..*self }.as_bytes())
+    }
+
+    fn heights(&self) -> Result<HeightRange, DecodeError> {
+        if self.width as usize != REGION_SIZE || self.depth as usize != REGION_SIZE {
+            return Err(DecodeError::InvalidValue { name: "region size", value: self.width as u64 });
+        }
+        HeightRange::new(self.bottom, self.bottom + self.height as i32)
+            .ok_or(DecodeError::InvalidValue { name: "region height", value: self.height as u64 })
+    }
 }
-impl AsFromBytes for WorldRegionsHeader {}
 
 #[derive(Debug)]
 pub struct WorldRegions {
     path: PathBuf,
+    heights: HeightRange,
     pub regions: HashMap<RegionCoords, Region>
 }
 
 impl WorldRegions {
     pub fn new(path: impl Into<PathBuf>) -> Self {
-        Self { path: path.into(), regions: HashMap::new() }
+        Self { path: path.into(), heights: HeightRange::default(), regions: HashMap::new() }
     }
 
-    pub fn chunk(&mut self, coords: ChunkCoords) -> &EncodedChunk {
+    pub fn chunk(&mut self, coords: ChunkCoords) -> Result<EncodedChunk, DecodeError> {
         self.get_or_create_region(coords.into())
             .chunk(coords)
     }
 
 
-    pub fn save_chunk(&mut self, chunk: &Chunk) {
-        self.get_or_create_region(chunk.xyz.into())
-            .save_chunk(chunk.xyz, chunk.encode_bytes());
+    /// Decodes the chunk or generates a new one, a damaged chunk is quarantined and generated again
+    pub fn load_chunk(&mut self, coords: ChunkCoords, seed: u64) -> Chunk {
+        let result = self.chunk(coords).and_then(|chunk| match chunk {
+            EncodedChunk::None => Ok(Chunk::new(coords.0, coords.1, coords.2, seed)),
+            EncodedChunk::Some(bytes) => Chunk::decode_at(&bytes, coords),
+        });
+        result.unwrap_or_else(|err| {
+            self.quarantine_chunk(coords, &err);
+            Chunk::new(coords.0, coords.1, coords.2, seed)
+        })
+    }
+
+
+    /// Moves the chunk data to the quarantine directory of the world, the region forgets it
+    fn quarantine_chunk(&mut self, coords: ChunkCoords, error: &DecodeError) {
+        eprintln!("Chunk {:?} is damaged and will be generated again: {}", coords, error);
+        let Some(bytes) = self.get_or_create_region(coords.into()).remove_chunk(coords) else {return};
+        if let Err(err) = quarantine_chunk_data(&self.path, coords, &bytes) {
+            eprintln!("Chunk quarantine error: {}", err);
+        }
+    }
+
+
+    /// Keeps the encoded chunk until the region is saved
+    pub fn save_chunk(&mut self, coords: ChunkCoords, data: Box<[u8]>) {
+        self.get_or_create_region(coords.into())
+            .save_chunk(coords, data);
     }
 
     pub fn get_or_create_region(&mut self, coords: RegionCoords) -> &mut Region {
@@ -150,59 +541,41 @@ impl WorldRegions {
 
     pub fn save_region(&mut self, coords: RegionCoords) {
         let Some(region) = self.regions.get_mut(&coords) else {return};
-        if !region.unsaved {return};
-        let header = WorldRegionsHeader {
-            magic_number: REGION_MAGIC_NUMBER,
-            format_version: REGION_FORMAT_VERSION,
-            format_type: RegionFormatType::Region,
-            width: REGION_SIZE as u8,
-            height: WORLD_HEIGHT as u8,
-            depth: REGION_SIZE as u8,
-        };
-
-
-        let mut bytes = Vec::<u8>::new();
-        bytes.extend(header.as_bytes());
-        region.chunks.iter()
-            .map(|chunk| if let EncodedChunk::Some(b) = chunk {b.len() as u32} else {0})
-            .for_each(|b| bytes.extend(b.as_bytes()));
-        region.chunks.iter()
-            .filter_map(|chunk| if let EncodedChunk::Some(b) = chunk {Some(b)} else {None})
-            .for_each(|b| bytes.extend(b.as_ref()));
-
-            
-        if let Err(err) = fs::write(self.path.join("regions/").join(coords.filename()), bytes) {
+        if let Err(err) = region.save() {
             eprintln!("Region write error: {}", err);
-        } else {
-            region.unsaved = false;
         }
     }
 
 
+    /// A damaged region file is moved to the quarantine directory and its chunks are generated again
     pub fn load_region(&mut self, coords: RegionCoords) {
-        let mut region = Region::new_empty();
-        let file = fs::read(self.path.join("regions/").join(&coords.filename()));
-        if let Ok(bytes) = file {
-            let header = WorldRegionsHeader::from_bytes(&bytes[0..WorldRegionsHeader::size()]);
-            let volume = header.width as usize * header.height as usize * header.width as usize;
-            let offsets_end = WorldRegionsHeader::size()+std::mem::size_of::<u32>()*volume;
-            let offsets = cast_vec_from_bytes::<u32>(&bytes[WorldRegionsHeader::size()..offsets_end]);
-            let mut chunk_offset = offsets_end;
-            for (i, offset) in offsets.into_iter().enumerate().filter(|(_, offset)| *offset != 0) {
-                let Some(chunk) = region.chunks.get_mut(i) else {continue};
-                *chunk = EncodedChunk::Some(bytes[chunk_offset..offset as usize+chunk_offset].into());
-                chunk_offset += offset as usize;
-            }
-        }
+        let path = coords.path(&self.path);
+        let region = if !path.exists() {
+            Region::new_empty(path, self.heights)
+        } else {
+            Region::open(&self.path, coords, self.heights).unwrap_or_else(|err| {
+                eprintln!("Region {} is damaged and will be generated again: {}", coords.filename(), err);
+                let quarantine = self.path.join(QUARANTINE_DIR);
+                if let Err(err) = fs::create_dir_all(&quarantine).and_then(|_| fs::rename(&path, quarantine.join(coords.filename()))) {
+                    eprintln!("Region quarantine error: {}", err);
+                }
+                Region::new_empty(path, self.heights)
+            })
+        };
 
         self.regions.insert(coords, region);
     }
 
-    /// Only safe access in an UnsafeMutex
     pub fn change_path(&mut self, path: PathBuf) {
         self.path = path;
         self.regions = HashMap::new();
     }
+
+    /// Regions are opened again with the height range of the world
+    pub fn set_heights(&mut self, heights: HeightRange) {
+        self.heights = heights;
+        self.regions = HashMap::new();
+    }
 }
 
 
@@ -217,30 +590,113 @@ impl PlayerSave {
 
     pub fn load_player(&self) -> Option<Player> {
         match fs::read(self.path.as_path()) {
-            Ok(bytes) => Some(Player::decode_bytes(&bytes)),
+            Ok(bytes) => Player::decode_bytes(&bytes)
+                .map_err(|err| eprintln!("Player data is damaged, the player starts again: {}", err)).ok(),
             Err(_) => {None},
         }
     }
 
     pub fn save_player(&self, player: &Player) {
-        if let Err(err) = fs::write(self.path.as_path(), player.encode_bytes()) {
+        if let Err(err) = write_atomic(&self.path, &player.encode_bytes()) {
             eprintln!("Player write error: {}", err);
         }
     }
 }
 
+/// Settings of the world and the content ids assigned in it, new keys are only appended
+#[derive(Debug, Default, Clone)]
+pub struct WorldMetadata {
+    pub name: String,
+    pub seed: u64,
+    pub mode: GameMode,
+    /// Chunk heights of the world, worlds without them keep the heights they were created with
+    pub heights: HeightRange,
+    pub items: Vec<(String, u32)>,
+    pub recipes: Vec<(String, u32)>,
+}
+
+impl WorldMetadata {
+    fn parse_ids(definitions: &Definitions, section: &str) -> Result<Vec<(String, u32)>, DefinitionError> {
+        let Some(definition) = definitions.sections.iter().find(|d| d.key == section) else {return Ok(vec![])};
+        definition.values.iter().map(|(key, value)| {
+            value.parse::<u32>().map(|id| (key.clone(), id)).map_err(|_| definition.invalid_value(key, value))
+        }).collect()
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("[world]\nname = {}\nseed = {}\nmode = {}\nbottom = {}\ntop = {}\n\n",
+            self.name, self.seed, self.mode.name(), self.heights.bottom, self.heights.top);
+        for (section, ids) in [("items", &self.items), ("recipes", &self.recipes)] {
+            text += &format!("[{section}]\n");
+            ids.iter().for_each(|(key, id)| text += &format!("{key} = {id}\n"));
+            text += "\n";
+        }
+        text
+    }
+}
+
+
+pub struct MetadataSave {
+    path: PathBuf,
+}
+
+impl MetadataSave {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path: path.join("world.txt") }
+    }
+
+    pub fn load_metadata(&self) -> Result<WorldMetadata, DefinitionError> {
+        let dir_name = self.path.parent().and_then(|p| p.file_name())
+            .map_or(String::new(), |name| name.to_string_lossy().to_string());
+        if !self.path.exists() {return Ok(WorldMetadata { name: dir_name, ..Default::default() })};
+        let definitions = Definitions::load(&self.path)?;
+        let world = definitions.sections.iter().find(|d| d.key == "world");
+        let bottom = world.map(|w| w.parse("bottom")).transpose()?.flatten().unwrap_or(HeightRange::default().bottom);
+        let top = world.map(|w| w.parse("top")).transpose()?.flatten().unwrap_or(HeightRange::default().top);
+        let Some(heights) = HeightRange::new(bottom, top) else {
+            return Err(definitions.root.error(format!("invalid heights {}..{}", bottom, top)));
+        };
+        Ok(WorldMetadata {
+            name: world.and_then(|w| w.get("name")).map_or(dir_name, |name| name.to_string()),
+            seed: world.map(|w| w.parse("seed")).transpose()?.flatten().unwrap_or_default(),
+            mode: world.map(|w| w.parse("mode")).transpose()?.flatten().unwrap_or_default(),
+            heights,
+            items: WorldMetadata::parse_ids(&definitions, "items")?,
+            recipes: WorldMetadata::parse_ids(&definitions, "recipes")?,
+        })
+    }
+
+    pub fn save_metadata(&self, metadata: &WorldMetadata) {
+        if let Err(err) = write_atomic(&self.path, metadata.to_text().as_bytes()) {
+            eprintln!("World metadata write error: {}", err);
+        }
+    }
+}
+
 pub struct WorldSave {
-    pub regions: Arc<UnsafeMutex<WorldRegions>>,
-    pub player: Arc<UnsafeMutex<PlayerSave>>
+    pub regions: Arc<Mutex<WorldRegions>>,
+    pub player: Arc<Mutex<PlayerSave>>,
+    pub metadata: MetadataSave,
 }
 
 impl WorldSave {
     pub fn new(path: PathBuf) -> Self {
         Self {
-            regions: Arc::new(UnsafeMutex::new(WorldRegions::new(path.clone()))),
-            player: Arc::new(UnsafeMutex::new(PlayerSave::new(path))),
+            regions: Arc::new(Mutex::new(WorldRegions::new(path.clone()))),
+            player: Arc::new(Mutex::new(PlayerSave::new(path.clone()))),
+            metadata: MetadataSave::new(path),
         }
     }
+
+    pub fn change_path(&mut self, path: PathBuf) {
+        self.regions.lock().unwrap().change_path(path.clone());
+        *self.player.lock().unwrap() = PlayerSave::new(path.clone());
+        self.metadata = MetadataSave::new(path);
+    }
+
+    pub fn set_heights(&mut self, heights: HeightRange) {
+        self.regions.lock().unwrap().set_heights(heights);
+    }
 }
 
 pub struct Save {
@@ -254,4 +710,297 @@ impl Save {
             .expect("Error creating directory");
         Self { world: WorldSave::new(path) }
     }
-}
\ No newline at end of file
+
+    /// Switches to another world, only safe when no thread uses the regions
+    pub fn change_world(&mut self, world_path: impl Into<PathBuf>) {
+        let path: PathBuf = world_path.into();
+        std::fs::create_dir_all(path.join("regions/"))
+            .expect("Error creating directory");
+        self.world.change_path(path);
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct WorldEntry {
+    pub path: PathBuf,
+    pub metadata: WorldMetadata,
+    pub backups: Vec<BackupEntry>,
+}
+
+/// Copy of a world, named by the time it was taken
+#[derive(Debug, Clone)]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    /// Seconds since the Unix epoch
+    pub time: u64,
+}
+
+/// Worlds in the worlds directory sorted by name, worlds with broken metadata are skipped
+pub fn list_worlds() -> Vec<WorldEntry> {
+    let Ok(dirs) = fs::read_dir(WORLDS_PATH) else {return vec![]};
+    let mut worlds = dirs
+        .filter_map(|dir| dir.ok().map(|d| d.path()))
+        .filter(|path| path.is_dir())
+        .filter_map(|path| match MetadataSave::new(path.clone()).load_metadata() {
+            Ok(metadata) => Some(WorldEntry { backups: list_backups(&path), path, metadata }),
+            Err(err) => {eprintln!("World metadata error: {}", err); None},
+        })
+        .collect::<Vec<WorldEntry>>();
+    worlds.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+    worlds
+}
+
+/// Creates the directory and the metadata of a new world
+pub fn create_world(name: &str, seed: u64, mode: GameMode) -> std::io::Result<PathBuf> {
+    // '#' starts a comment in the metadata file
+    let name = name.replace(['#', '\n', '\r'], "");
+    let dir_name = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' {c} else {'_'})
+        .collect::<String>();
+    let dir_name = if dir_name.is_empty() {String::from("world")} else {dir_name};
+    let path = unique_world_path(&dir_name);
+    fs::create_dir_all(path.join("regions/"))?;
+    let metadata = WorldMetadata { name, seed, mode, heights: DEFAULT_HEIGHT_RANGE, ..Default::default() };
+    write_atomic(&path.join("world.txt"), metadata.to_text().as_bytes())?;
+    Ok(path)
+}
+
+/// Free directory in the worlds directory, a number is appended to taken names
+fn unique_world_path(dir_name: &str) -> PathBuf {
+    let root = PathBuf::from(WORLDS_PATH);
+    (0..).map(|i| if i == 0 {root.join(dir_name)} else {root.join(format!("{}_{}", dir_name, i))})
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Copies the world files, the destination must not exist
+pub fn copy_world(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            copy_world(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Numbers are used as is, any other text is hashed, an empty text gives a random seed
+pub fn parse_seed(text: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let text = text.trim();
+    if text.is_empty() {
+        return std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    }
+    text.parse().unwrap_or_else(|_| {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        text.hash(&mut hasher);
+        hasher.finish()
+    })
+}
+
+/// Deletes the world and its backups
+pub fn delete_world(path: &Path) -> std::io::Result<()> {
+    fs::remove_dir_all(path)?;
+    let backups = backups_path(path);
+    if backups.exists() {fs::remove_dir_all(backups)?};
+    Ok(())
+}
+
+
+fn backups_path(world_path: &Path) -> PathBuf {
+    PathBuf::from(BACKUPS_PATH).join(world_path.file_name().unwrap_or_default())
+}
+
+/// Backups of the world, the newest first
+pub fn list_backups(world_path: &Path) -> Vec<BackupEntry> {
+    let Ok(dirs) = fs::read_dir(backups_path(world_path)) else {return vec![]};
+    let mut backups = dirs
+        .filter_map(|dir| dir.ok().map(|d| d.path()))
+        .filter_map(|path| Some(BackupEntry { time: path.file_name()?.to_str()?.parse().ok()?, path }))
+        .collect::<Vec<BackupEntry>>();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.time));
+    backups
+}
+
+/// Takes a backup of the world and deletes the oldest backups over the count
+pub fn backup_world(world_path: &Path, count: usize) -> std::io::Result<()> {
+    if count == 0 || !world_path.exists() {return Ok(())};
+    let time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let path = backups_path(world_path).join(time.to_string());
+    if !path.exists() {copy_world(world_path, &path)?};
+    list_backups(world_path).iter().skip(count).try_for_each(|backup| fs::remove_dir_all(&backup.path))
+}
+
+/// Replaces the world with a copy of the backup, the world must not be opened
+pub fn restore_backup(world_path: &Path, backup: &BackupEntry) -> std::io::Result<()> {
+    let temp_path = world_path.with_extension("restore");
+    if temp_path.exists() {fs::remove_dir_all(&temp_path)?};
+    copy_world(&backup.path, &temp_path)?;
+    fs::remove_dir_all(world_path)?;
+    fs::rename(&temp_path, world_path)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, mem::offset_of};
+    use crate::{bytes::{AsFromBytes, cast_bytes_from_slice}, test_utils::world_path, world::{height_range::HeightRange, chunk_coords::ChunkCoords}};
+    use super::{Region, RegionCoords, RegionTableEntry, WorldRegionsHeader, EncodedChunk, REGION_MAGIC_NUMBER, REGION_FORMAT_VERSION, REGION_SIZE,
+        SECTOR_SIZE, QUARANTINE_DIR, region_volume, first_data_sector};
+
+    const COORDS: RegionCoords = RegionCoords(0, 0);
+
+    fn chunk_bytes(seed: u8, len: usize) -> Box<[u8]> {
+        (0..len).map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+    }
+
+    fn stored(region: &Region) -> Vec<(usize, Box<[u8]>)> {
+        region.stored_chunks().into_iter().map(|index| match region.chunk(index).unwrap() {
+            EncodedChunk::Some(bytes) => (index, bytes),
+            EncodedChunk::None => panic!("chunk {index} is listed but missing"),
+        }).collect()
+    }
+
+    #[test]
+    fn save_reopen() {
+        let heights = HeightRange::new(0, 2).unwrap();
+        let world = world_path("save_reopen");
+        let path = COORDS.path(&world);
+        let mut region = Region::new_empty(path.clone(), heights);
+        region.save_chunk(0usize, chunk_bytes(1, 100));
+        region.save_chunk(33usize, chunk_bytes(2, SECTOR_SIZE + 1));
+        region.save_chunk(2047usize, chunk_bytes(3, SECTOR_SIZE));
+        region.save().unwrap();
+        let saved = stored(&region);
+        assert_eq!(saved.len(), 3);
+        assert_eq!(stored(&Region::open(&world, COORDS, heights).unwrap()), saved);
+
+        let mut region = Region::open(&world, COORDS, heights).unwrap();
+        region.save_chunk(33usize, chunk_bytes(4, 10));
+        region.remove_chunk(0usize);
+        region.save().unwrap();
+        let reopened = Region::open(&world, COORDS, heights).unwrap();
+        assert_eq!(stored(&reopened), vec![(33, chunk_bytes(4, 10)), (2047, chunk_bytes(3, SECTOR_SIZE))]);
+        fs::remove_dir_all(&world).unwrap();
+    }
+
+    #[test]
+    fn version_3_layout() {
+        assert_eq!((WorldRegionsHeader::SIZE, offset_of!(WorldRegionsHeader, checksum), offset_of!(WorldRegionsHeader, bottom)), (24, 16, 20));
+        assert_eq!((RegionTableEntry::SIZE, offset_of!(RegionTableEntry, length), offset_of!(RegionTableEntry, checksum)), (16, 4, 8));
+
+        let heights = HeightRange::new(-1, 2).unwrap();
+        let world = world_path("version_3_layout");
+        let path = COORDS.path(&world);
+        let mut region = Region::new_empty(path.clone(), heights);
+        region.save_chunk(ChunkCoords(0, 1, 2), chunk_bytes(1, 100));
+        region.save().unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(u32::from_bytes_at(&bytes, 8).unwrap(), 3);
+        assert_eq!(i32::from_bytes_at(&bytes, 20).unwrap(), -1);
+        // The y 1 is two rows of z above the bottom -1
+        let entry = SECTOR_SIZE + (2*REGION_SIZE + 2)*RegionTableEntry::SIZE;
+        let (sector, length) = (u32::from_bytes_at(&bytes, entry).unwrap() as usize, u32::from_bytes_at(&bytes, entry + 4).unwrap());
+        assert_eq!((sector, length), (first_data_sector(heights), 100));
+        assert_eq!(&bytes[sector*SECTOR_SIZE..sector*SECTOR_SIZE + 100], &*chunk_bytes(1, 100));
+        assert_eq!(bytes.len() % SECTOR_SIZE, 0);
+        fs::remove_dir_all(&world).unwrap();
+    }
+
+    #[test]
+    fn damaged_header() {
+        let heights = HeightRange::new(0, 2).unwrap();
+        let world = world_path("damaged_header");
+        let path = COORDS.path(&world);
+        let mut region = Region::new_empty(path.clone(), heights);
+        region.save_chunk(5usize, chunk_bytes(1, 100));
+        region.save().unwrap();
+        assert!(Region::read(path.clone()).is_ok());
+
+        // A changed height would read the chunks from the wrong places
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[14] += 1;
+        fs::write(&path, bytes).unwrap();
+        assert!(Region::read(path).is_err());
+        fs::remove_dir_all(&world).unwrap();
+    }
+
+    #[test]
+    fn growing_chunk_moves() {
+        let heights = HeightRange::new(0, 2).unwrap();
+        let world = world_path("growing_chunk_moves");
+        let path = COORDS.path(&world);
+        let mut region = Region::new_empty(path.clone(), heights);
+        region.save_chunk(0usize, chunk_bytes(1, 100));
+        region.save_chunk(1usize, chunk_bytes(2, 100));
+        region.save().unwrap();
+        let (first, second) = (region.table[0].sector, region.table[1].sector);
+        assert_eq!(second, first + 1);
+
+        region.save_chunk(0usize, chunk_bytes(3, 3*SECTOR_SIZE));
+        region.save().unwrap();
+        assert!(region.table[0].sector > second, "the grown chunk doesn't fit before the next one");
+        assert_eq!(region.table[1].sector, second);
+        // The freed sector is used again
+        region.save_chunk(2usize, chunk_bytes(4, 100));
+        region.save().unwrap();
+        assert_eq!(region.table[2].sector, first);
+
+        let reopened = Region::open(&world, COORDS, heights).unwrap();
+        assert_eq!(stored(&reopened), vec![(0, chunk_bytes(3, 3*SECTOR_SIZE)), (1, chunk_bytes(2, 100)), (2, chunk_bytes(4, 100))]);
+        fs::remove_dir_all(&world).unwrap();
+    }
+
+    #[test]
+    fn version_2_conversion() {
+        let heights = HeightRange::new(0, 2).unwrap();
+        let world = world_path("version_2_conversion");
+        let path = COORDS.path(&world);
+        let chunks = [(5usize, chunk_bytes(1, 300)), (1500, chunk_bytes(2, 2*SECTOR_SIZE))];
+        let mut lengths = vec![0u32; region_volume(heights)];
+        chunks.iter().for_each(|(index, bytes)| lengths[*index] = bytes.len() as u32);
+        let mut bytes = vec![];
+        bytes.extend(REGION_MAGIC_NUMBER.as_bytes());
+        bytes.extend(2u32.as_bytes());
+        bytes.extend([0, 32, heights.height() as u8, 32]);
+        bytes.extend(cast_bytes_from_slice(&lengths));
+        chunks.iter().for_each(|(_, chunk)| bytes.extend(chunk.iter()));
+        fs::write(&path, bytes).unwrap();
+
+        let region = Region::open(&world, COORDS, heights).unwrap();
+        assert_eq!(stored(&region), chunks.to_vec());
+        let converted = fs::read(&path).unwrap();
+        assert_eq!(u32::from_bytes_at(&converted, 8).unwrap(), REGION_FORMAT_VERSION);
+        assert_eq!(stored(&Region::open(&world, COORDS, heights).unwrap()), chunks.to_vec());
+        fs::remove_dir_all(&world).unwrap();
+    }
+
+    #[test]
+    fn shrinking_heights_quarantine() {
+        let heights = HeightRange::new(-2, 2).unwrap();
+        let world = world_path("shrinking_heights_quarantine");
+        let mut region = Region::new_empty(COORDS.path(&world), heights);
+        region.save_chunk(ChunkCoords(1, -2, 3), chunk_bytes(1, 100));
+        region.save_chunk(ChunkCoords(1, 0, 3), chunk_bytes(2, 100));
+        region.save_chunk(ChunkCoords(1, 1, 3), chunk_bytes(3, 100));
+        region.save().unwrap();
+
+        let shrunk = HeightRange::new(0, 2).unwrap();
+        let region = Region::open(&world, COORDS, shrunk).unwrap();
+        assert_eq!(region.heights(), shrunk);
+        let kept = |coords: ChunkCoords| match region.chunk(coords).unwrap() {
+            EncodedChunk::Some(bytes) => bytes,
+            EncodedChunk::None => panic!("chunk {coords:?} is missing"),
+        };
+        assert_eq!(kept(ChunkCoords(1, 0, 3)), chunk_bytes(2, 100));
+        assert_eq!(kept(ChunkCoords(1, 1, 3)), chunk_bytes(3, 100));
+        assert_eq!(region.stored_chunks().len(), 2);
+        let quarantined = fs::read(world.join(QUARANTINE_DIR).join("chunk_1_-2_3.bin")).unwrap();
+        assert_eq!(quarantined.into_boxed_slice(), chunk_bytes(1, 100));
+        fs::remove_dir_all(&world).unwrap();
+    }
+}
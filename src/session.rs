@@ -0,0 +1,122 @@
+use std::{sync::{Arc, Mutex, RwLock, Condvar, mpsc::{Sender, Receiver}}, path::PathBuf, time::Duration};
+
+use crate::{
+    save_load::{Save, WorldMetadata}, player::{player::{Player, SPAWN_POSITION}, mining::BlockMining}, world::{World, global_coords::GlobalCoords, chunk_coords::ChunkCoords, alerts::AlertService, ticks::SimulationTicks},
+    meshes::Meshes, threads::{self, save::SaveState, scheduler::Scheduler, world_loader::CHUNK_POOL}, graphic::render::RenderResult,
+    recipes::{content::load_content, item::Item, items::item_by_key, storage::Storage}, definitions::DefinitionError, camera::camera_controller::CameraController,
+    settings::Settings};
+
+use nalgebra_glm as glm;
+
+/// Longest wait for the world workers when the world is closed
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Items of a new player by their keys in the item definitions
+const STARTING_KIT: [(&str, u32); 4] = [("iron_ore", 100), ("iron_ingot", 100), ("iron_plate", 100), ("rock", 100)];
+/// Inventory slot of the first item of the kit
+const STARTING_KIT_SLOT: usize = 10;
+
+
+/// Everything that belongs to the opened world
+pub struct Session {
+    pub metadata: WorldMetadata,
+    pub player: Player,
+    pub world: Arc<RwLock<World>>,
+    pub meshes: Meshes,
+    pub mining: BlockMining,
+    pub alerts: Arc<Mutex<AlertService>>,
+    pub ticks: Arc<SimulationTicks>,
+    pub save_condvar: Arc<(Mutex<SaveState>, Condvar)>,
+    pub translate_sender: Sender<Vec<(usize, usize)>>,
+    pub translate_recv: Receiver<Vec<(usize, usize)>>,
+    pub render_recv: Receiver<RenderResult>,
+    pub scheduler: Scheduler,
+}
+
+
+impl Session {
+    /// Switches the save to the world and starts the world threads,
+    /// the simulation of a replay waits for the replayed frames
+    pub fn open(save: &mut Save, path: PathBuf, settings: &Settings, is_replay: bool) -> Result<Self, DefinitionError> {
+        save.change_world(path);
+        let mut metadata = save.world.metadata.load_metadata()?;
+        load_content(&mut metadata)?;
+        save.world.metadata.save_metadata(&metadata);
+        save.world.set_heights(metadata.heights);
+
+        let mut player = save.world.player.lock().unwrap().load_player().unwrap_or_else(|| new_player(settings));
+        player.apply_settings(settings);
+
+        let c: ChunkCoords = GlobalCoords::from(player.camera().position_tuple()).into();
+        let render_distance = settings.render_distance;
+        let ox = c.0 - render_distance/2;
+        let oz = c.2 - render_distance/2;
+        let (oy, height) = metadata.heights.window(c.1, settings.render_height);
+        let world = Arc::new(RwLock::new(
+            World::new(render_distance, height, render_distance, ChunkCoords(ox, oy, oz), metadata.seed, metadata.heights)));
+        let mut scheduler = Scheduler::new();
+        let save_condvar = Arc::new((Mutex::new(SaveState::Saved), Condvar::new()));
+        let alerts = Arc::new(Mutex::new(AlertService::new()));
+        let ticks = Arc::new(SimulationTicks::new(is_replay.then_some(0), scheduler.token()));
+        let (translate_sender, translate_recv) = std::sync::mpsc::channel::<Vec<(usize, usize)>>();
+        let (render_sender, render_recv) = std::sync::mpsc::channel::<RenderResult>();
+
+        scheduler.add_pool(CHUNK_POOL, 1);
+        threads::save::spawn(&mut scheduler, world.clone(), save.world.regions.clone(), save_condvar.clone());
+        threads::world_loader::spawn(&mut scheduler, world.clone(), save.world.regions.clone());
+        threads::renderer::spawn(&mut scheduler, world.clone(), render_sender);
+        threads::voxel_data_updater::spawn(&mut scheduler, world.clone(), alerts.clone(), ticks.clone());
+
+        Ok(Self {
+            metadata,
+            player,
+            world,
+            meshes: Meshes::new(),
+            mining: BlockMining::new(),
+            alerts,
+            ticks,
+            save_condvar,
+            translate_sender,
+            translate_recv,
+            render_recv,
+            scheduler,
+        })
+    }
+
+
+    /// Wakes the save loop and saves the player
+    pub fn save(&self, save: &Save) {
+        threads::save::request_save(&self.save_condvar);
+        save.world.player.lock().unwrap().save_player(&self.player);
+    }
+
+
+    /// Stops the world workers and saves everything
+    pub fn close(mut self, save: &Save) {
+        self.scheduler.cancel();
+        let (save_state, cvar) = &*self.save_condvar;
+        drop(save_state.lock().unwrap());
+        cvar.notify_one();
+        if self.scheduler.shutdown(SHUTDOWN_TIMEOUT) {
+            threads::save::save_world(&self.world, &save.world.regions);
+        } else {
+            eprintln!("The world is saved while some workers are still running");
+            threads::save::save_locked_world(&self.world.write().unwrap(), &save.world.regions);
+        }
+        save.world.player.lock().unwrap().save_player(&self.player);
+    }
+}
+
+
+/// Player at the spawn with the starting kit, the content must be loaded
+pub fn new_player(settings: &Settings) -> Player {
+    let camera = CameraController::new(glm::make_vec3(&SPAWN_POSITION), settings.fov, settings.near, settings.far);
+    let mut player = Player::new(camera, glm::make_vec3(&SPAWN_POSITION));
+    let binding = player.inventory();
+    let mut inventory = binding.lock().unwrap();
+    for (index, (key, count)) in (STARTING_KIT_SLOT..).zip(STARTING_KIT) {
+        let Some(item) = item_by_key(key) else {continue};
+        _ = inventory.add_by_index(&Item::new(item.id, count), index);
+    }
+    drop(inventory);
+    player
+}
@@ -0,0 +1,121 @@
+use std::{fs, path::Path};
+
+use crate::{definitions::{Definitions, DefinitionError}, input_event::actions::Bindings, save_load::write_atomic};
+
+pub const SETTINGS_PATH: &str = "./data/settings.txt";
+
+pub const DEFAULT_FOV: f32 = 1.2;
+pub const DEFAULT_NEAR: f32 = 0.1;
+pub const DEFAULT_FAR: f32 = 1000.0;
+pub const DEFAULT_SENSITIVITY: f32 = 0.3;
+
+pub const MIN_RENDER_DISTANCE: i32 = 4;
+pub const MAX_RENDER_DISTANCE: i32 = 64;
+pub const MIN_RENDER_HEIGHT: i32 = 2;
+pub const MAX_RENDER_HEIGHT: i32 = 32;
+pub const MAX_BACKUPS: usize = 20;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    /// Width and depth of the loaded area in chunks
+    pub render_distance: i32,
+    /// Height of the loaded area in chunks, limited by the heights of the world
+    pub render_height: i32,
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+    /// Upper limit, the supported sample count is used
+    pub msaa: u32,
+    pub sensitivity: f32,
+    pub window_width: u32,
+    pub window_height: u32,
+    pub music: String,
+    /// Backups kept of every world, a new one is taken when the world is opened
+    pub backups: usize,
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            render_distance: 30,
+            render_height: 16,
+            fov: DEFAULT_FOV,
+            near: DEFAULT_NEAR,
+            far: DEFAULT_FAR,
+            msaa: 4,
+            sensitivity: DEFAULT_SENSITIVITY,
+            window_width: 1150,
+            window_height: 700,
+            music: String::from("./audio/music/Kyle Gabler - Years of Work.mp3"),
+            backups: 3,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+
+impl Settings {
+    /// Missing keys keep the default values, the bad sections and values are reported and skipped
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, DefinitionError> {
+        let mut settings = Self::default();
+        if !path.as_ref().exists() {return Ok(settings)};
+        let definitions = Definitions::load(path)?;
+        let mut errors = vec![];
+        for definition in definitions.sections.iter() {
+            match definition.key.as_str() {
+                "video" => {
+                    if let Some(v) = skip_bad(definition.parse("render_distance"), &mut errors) {settings.render_distance = v};
+                    if let Some(v) = skip_bad(definition.parse("render_height"), &mut errors) {settings.render_height = v};
+                    if let Some(v) = skip_bad(definition.parse("fov"), &mut errors) {settings.fov = v};
+                    if let Some(v) = skip_bad(definition.parse("near"), &mut errors) {settings.near = v};
+                    if let Some(v) = skip_bad(definition.parse("far"), &mut errors) {settings.far = v};
+                    if let Some(v) = skip_bad(definition.parse("msaa"), &mut errors) {settings.msaa = v};
+                    if let Some(v) = skip_bad(definition.parse("window_width"), &mut errors) {settings.window_width = v};
+                    if let Some(v) = skip_bad(definition.parse("window_height"), &mut errors) {settings.window_height = v};
+                },
+                "controls" => {
+                    if let Some(v) = skip_bad(definition.parse("sensitivity"), &mut errors) {settings.sensitivity = v};
+                },
+                "audio" => {
+                    if let Some(v) = definition.get("music") {settings.music = v.to_string()};
+                },
+                "saves" => {
+                    if let Some(v) = skip_bad(definition.parse("backups"), &mut errors) {settings.backups = v};
+                },
+                "bindings" => settings.bindings = Bindings::parse(definition, &mut errors),
+                _ => errors.push(definition.error("unknown section")),
+            }
+        }
+        errors.iter().for_each(|error| eprintln!("Skipped setting: {error}"));
+        settings.render_distance = settings.render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+        settings.render_height = settings.render_height.clamp(MIN_RENDER_HEIGHT, MAX_RENDER_HEIGHT);
+        settings.msaa = settings.msaa.max(1);
+        settings.backups = settings.backups.min(MAX_BACKUPS);
+        Ok(settings)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) {
+        let text = format!(
+            "[video]\nrender_distance = {}\nrender_height = {}\nfov = {}\nnear = {}\nfar = {}\nmsaa = {}\nwindow_width = {}\nwindow_height = {}\n\n\
+            [controls]\nsensitivity = {}\n\n\
+            [audio]\nmusic = {}\n\n\
+            [saves]\nbackups = {}\n\n\
+            [bindings]\n{}",
+            self.render_distance, self.render_height, self.fov, self.near, self.far, self.msaa, self.window_width, self.window_height,
+            self.sensitivity,
+            self.music,
+            self.backups,
+            self.bindings.to_text());
+        if let Some(parent) = path.as_ref().parent() {let _ = fs::create_dir_all(parent);}
+        if let Err(err) = write_atomic(path.as_ref(), text.as_bytes()) {
+            eprintln!("Settings write error: {}", err);
+        }
+    }
+}
+
+/// The value or None when it is bad, the error is kept to be reported
+fn skip_bad<T>(result: Result<Option<T>, DefinitionError>, errors: &mut Vec<DefinitionError>) -> Option<T> {
+    result.unwrap_or_else(|error| {errors.push(error); None})
+}
@@ -0,0 +1,23 @@
+use std::{fs, path::PathBuf, sync::RwLock};
+
+use crate::{voxels::{block::{blocks::BLOCKS, block_behaviour::BlockBehaviour}, chunk::Chunk}, world::World};
+
+/// First block with the behaviour, the content must be loaded
+pub fn block_id(behaviour: BlockBehaviour) -> u32 {
+    BLOCKS().iter().position(|block| block.behaviour() == behaviour).expect("The block is defined") as u32
+}
+
+/// Generates the missing chunks of the column
+pub fn load_column(world: &RwLock<World>, cx: i32, cz: i32) {
+    let missing = world.read().unwrap().missing_chunks(cx, cz);
+    let chunks = missing.iter().map(|c| Chunk::new(c.0, c.1, c.2, 0)).collect();
+    world.write().unwrap().add_chunks(chunks);
+}
+
+/// Empty world directory with the regions directory
+pub fn world_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("manufactory_test_{}_{}", name, std::process::id()));
+    if dir.exists() {fs::remove_dir_all(&dir).unwrap()};
+    fs::create_dir_all(dir.join("regions/")).unwrap();
+    dir
+}
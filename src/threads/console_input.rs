@@ -0,0 +1,11 @@
+use std::{thread::{self, JoinHandle}, sync::mpsc::Sender, io::BufRead};
+
+/// Reads console commands from stdin, the thread is not joined because it blocks on reading
+pub fn spawn(sender: Sender<String>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {break};
+            if sender.send(line).is_err() {break};
+        }
+    })
+}
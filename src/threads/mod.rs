@@ -1,4 +1,6 @@
 pub mod renderer;
 pub mod world_loader;
 pub mod voxel_data_updater;
-pub mod save;
\ No newline at end of file
+pub mod save;
+pub mod scheduler;
+pub mod console_input;
@@ -1,29 +1,66 @@
-use std::{sync::{Mutex, Arc, mpsc::Sender}, time::{Duration, Instant}, thread::{JoinHandle}};
+use std::{collections::HashMap, sync::{Arc, Mutex, RwLock, mpsc::Sender}, thread, time::Duration};
 
-use crate::{world::{World, chunk_coords::ChunkCoords, global_coords::GlobalCoords}, graphic::render::{RenderResult, render}, unsafe_mutex::UnsafeMutex, WORLD_EXIT};
+use crate::{world::{World, chunk_coords::ChunkCoords}, graphic::render::{RenderResult, render, snapshot::ChunkSnapshot}};
 
+use super::scheduler::{Scheduler, CancellationToken};
+
+pub const MESHING_POOL: &str = "meshing";
+/// Pause of the renderer when every chunk is rendered or the queue is full
+const IDLE_PAUSE: Duration = Duration::from_millis(16);
+/// Snapshots queued per meshing worker, the rest waits for the next nearest chunks
+const QUEUED_PER_WORKER: usize = 4;
+
+
+/// The last mesh queued for a chunk, a mesh of an older snapshot that finishes later is dropped
+struct QueuedMesh {
+    version: u64,
+    token: CancellationToken,
+}
+
+
+/// Snapshots the modified chunks nearest to the player and meshes them on the meshing pool
 pub fn spawn(
-    world: Arc<UnsafeMutex<World>>,
+    scheduler: &mut Scheduler,
+    world: Arc<RwLock<World>>,
     sender: Sender<RenderResult>
-) -> JoinHandle<()> {
-    let thread = std::thread::Builder::new().name("renderer".to_owned()).stack_size(32 * 1024 * 1024);
-    thread.spawn(move || {loop {
-        if unsafe { WORLD_EXIT } {break};
-        let mut world = unsafe {world.lock_unsafe()}.unwrap();
-
-        let ox = world.chunks.ox;
-        let oz = world.chunks.oz;
-        let width = world.chunks.width;
-        let depth = world.chunks.depth;
-        
-        if let Some(chunk) = world.chunks.find_unrendered() {
+) {
+    let workers = thread::available_parallelism().map_or(1, |count| count.get().saturating_sub(2).max(1));
+    let jobs = scheduler.add_pool(MESHING_POOL, workers);
+    let queued = Arc::new(Mutex::new(HashMap::<ChunkCoords, QueuedMesh>::new()));
+    let mut version = 0;
+    scheduler.add_loop("renderer", move |_| {
+        let world = world.read().unwrap();
+
+        let (ox, oy, oz) = (world.chunks.ox, world.chunks.oy, world.chunks.oz);
+        let (width, height, depth) = (world.chunks.width, world.chunks.height, world.chunks.depth);
+        let center = (ox + width/2, oy + height/2, oz + depth/2);
+        let mut is_queued = false;
+        loop {
+            let mut queued_g = queued.lock().unwrap();
+            if queued_g.len() >= workers*QUEUED_PER_WORKER {break};
+            let Some(chunk) = world.chunks.find_unrendered() else {break};
             chunk.modify(false);
-            if let Some(result) = render(chunk.xyz.nindex(width, depth, ox, oz), &world) {
-                let _ = sender.send(result);
+            let xyz = chunk.xyz;
+            let snapshot = ChunkSnapshot::new(&world.chunks, chunk);
+
+            version += 1;
+            let chunk_index = xyz.nindex(width, depth, ox, oy, oz);
+            let priority = ((xyz.0 - center.0).pow(2) + (xyz.1 - center.1).pow(2) + (xyz.2 - center.2).pow(2)) as u32;
+            let (queued, sender, version) = (queued.clone(), sender.clone(), version);
+            // The job waits for the map until its mesh is added
+            let token = jobs.submit(priority, move |_| {
+                let result = render(chunk_index, &snapshot);
+                let mut queued = queued.lock().unwrap();
+                if queued.get(&xyz).is_some_and(|mesh| mesh.version == version) {
+                    queued.remove(&xyz);
+                    let _ = sender.send(result);
+                }
+            });
+            if let Some(older) = queued_g.insert(xyz, QueuedMesh { version, token }) {
+                older.token.cancel();
             }
-        } else {
-            drop(world);
-            std::thread::sleep(Duration::from_millis(16));
+            is_queued = true;
         }
-    }}).unwrap()
-}
\ No newline at end of file
+        if is_queued {Duration::ZERO} else {IDLE_PAUSE}
+    });
+}
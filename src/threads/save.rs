@@ -1,46 +1,77 @@
-use std::{thread::{self, JoinHandle}, sync::{Arc, Mutex, Condvar}, time::Duration};
+use std::{sync::{Arc, Mutex, RwLock, Condvar}, time::Duration};
 
-use crate::{world::World, unsafe_mutex::UnsafeMutex, save_load::WorldRegions};
+use crate::{world::{World, chunk_coords::ChunkCoords}, save_load::WorldRegions, bytes::BytesCoder};
+
+use super::scheduler::Scheduler;
+
+/// The world is saved at least this often
+const AUTOSAVE_INTERVAL: Duration = Duration::new(60, 0);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SaveState {
     Unsaved,
     Saved,
-    WorldExit,
 }
 
+/// Wakes the save loop, the loop notifies the condvar again when it takes the request
+pub fn request_save(save_condvar: &(Mutex<SaveState>, Condvar)) {
+    *save_condvar.0.lock().unwrap() = SaveState::Unsaved;
+    save_condvar.1.notify_all();
+}
+
+/// Saves the world when it is requested and by the interval,
+/// the save loop stops without saving, the last save is done after the other workers stopped
 pub fn spawn(
-    world: Arc<UnsafeMutex<World>>,
-    world_regions: Arc<UnsafeMutex<WorldRegions>>,
+    scheduler: &mut Scheduler,
+    world: Arc<RwLock<World>>,
+    world_regions: Arc<Mutex<WorldRegions>>,
     save_condvar: Arc<(Mutex<SaveState>, Condvar)>
-) -> JoinHandle<()> {
-    thread::spawn(move || {
-        loop {
-            let (lock, cvar) = &*save_condvar;
-            let (mut save_state, _) = cvar.wait_timeout(lock.lock().unwrap(), Duration::new(60, 0)).unwrap();
-
-            
-            let mut world = unsafe {world.lock_unsafe()}.unwrap();
-            let mut world_regions = unsafe {world_regions.lock_unsafe()}.unwrap();
-
-            let mut chunks_awaiting_deletion = world.chunks.chunks_awaiting_deletion.lock().unwrap();
-            chunks_awaiting_deletion.iter().for_each(|chunk| {
-                world_regions.save_chunk(chunk);
-            });
-            chunks_awaiting_deletion.clear();
-            drop(chunks_awaiting_deletion);
-
-            world.chunks.chunks.iter_mut().for_each(|chunk| {
-                let Some(chunk) = chunk else {return};
-                if !chunk.unsaved {return};
-                world_regions.save_chunk(chunk);
-                chunk.unsaved = false;
-            });
-            world_regions.save_all_regions();
-
-
-            if *save_state == SaveState::WorldExit {break};
-            *save_state = SaveState::Saved;
-        }
-    })
-}
\ No newline at end of file
+) {
+    scheduler.add_loop("save", move |token| {
+        let (lock, cvar) = &*save_condvar;
+        let (mut save_state, _) = cvar.wait_timeout_while(lock.lock().unwrap(), AUTOSAVE_INTERVAL,
+            |state| *state == SaveState::Saved && !token.is_cancelled()).unwrap();
+        if token.is_cancelled() {return Duration::ZERO};
+        // A request during the save waits for the next pass instead of the whole save
+        *save_state = SaveState::Saved;
+        drop(save_state);
+        cvar.notify_all();
+
+        save_world(&world, &world_regions);
+        Duration::ZERO
+    });
+}
+
+/// Writes the unsaved chunks and the chunks that left the loaded area.
+/// The chunks are encoded while the world is only read, the world is released before the regions are written
+pub fn save_world(world: &RwLock<World>, world_regions: &Mutex<WorldRegions>) {
+    let world_g = world.read().unwrap();
+    let encoded = encode_world(&world_g);
+    // The loader reads the regions, so they are taken before a chunk that left the area could be loaded again
+    let mut world_regions = world_regions.lock().unwrap();
+    drop(world_g);
+    write_regions(&mut world_regions, encoded);
+}
+
+/// Saves the world the caller holds the write lock of, the workers that didn't stop can't change it during the save
+pub fn save_locked_world(world: &World, world_regions: &Mutex<WorldRegions>) {
+    let encoded = encode_world(world);
+    write_regions(&mut world_regions.lock().unwrap(), encoded);
+}
+
+fn encode_world(world: &World) -> Vec<(ChunkCoords, Box<[u8]>)> {
+    let mut encoded: Vec<(ChunkCoords, Box<[u8]>)> = world.chunks.chunks_awaiting_deletion.lock().unwrap()
+        .drain(..)
+        .map(|chunk| (chunk.xyz, chunk.encode_bytes()))
+        .collect();
+    encoded.extend(world.chunks.chunks.iter().flatten()
+        // A change during the encoding marks the chunk again
+        .filter(|chunk| chunk.take_unsaved())
+        .map(|chunk| (chunk.xyz, chunk.encode_bytes())));
+    encoded
+}
+
+fn write_regions(world_regions: &mut WorldRegions, encoded: Vec<(ChunkCoords, Box<[u8]>)>) {
+    encoded.into_iter().for_each(|(coords, data)| world_regions.save_chunk(coords, data));
+    world_regions.save_all_regions();
+}
@@ -0,0 +1,406 @@
+use std::{any::Any, cmp::Ordering as CmpOrdering, collections::BinaryHeap, fmt::Display, panic::{self, AssertUnwindSafe}, sync::{Arc, Mutex, Condvar, atomic::{AtomicBool, Ordering}}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+/// Meshing of a chunk recurses deeply
+const WORKER_STACK_SIZE: usize = 32 * 1024 * 1024;
+/// Weight of the newest sample in the average times
+const AVERAGE_WEIGHT: f64 = 0.1;
+
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    parent: Option<CancellationToken>,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+/// Stop flag shared by the workers and the jobs, a child token is also cancelled with its parent
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<TokenState>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {Self::default()}
+
+    pub fn child(&self) -> Self {
+        Self { state: Arc::new(TokenState { parent: Some(self.clone()), ..Default::default() }) }
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Release);
+        let root = self.root();
+        let _lock = root.lock.lock().unwrap();
+        root.condvar.notify_all();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Acquire) || self.state.parent.as_ref().is_some_and(|parent| parent.is_cancelled())
+    }
+
+    /// Sleeps until the timeout or the cancellation, returns true if cancelled
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        let root = self.root();
+        let mut lock = root.lock.lock().unwrap();
+        while !self.is_cancelled() {
+            let now = Instant::now();
+            if now >= deadline {break};
+            lock = root.condvar.wait_timeout(lock, deadline - now).unwrap().0;
+        }
+        self.is_cancelled()
+    }
+
+    /// Every token of a tree waits on the condvar of the root
+    fn root(&self) -> &TokenState {
+        match &self.state.parent {
+            Some(parent) => parent.root(),
+            None => &self.state,
+        }
+    }
+}
+
+
+/// Time spent by a pool or a loop
+#[derive(Debug, Clone, Default)]
+pub struct WorkerStats {
+    pub name: &'static str,
+    /// Jobs waiting in the queue, loops have none
+    pub queued: usize,
+    /// Finished jobs or loop iterations
+    pub done: u64,
+    /// Average time a job waits in the queue
+    pub wait: Duration,
+    /// Average time of a job or a loop iteration
+    pub run: Duration,
+}
+
+impl WorkerStats {
+    fn add(&mut self, wait: Duration, run: Duration) {
+        let average = |old: Duration, new: Duration| if self.done == 0 {new} else {
+            Duration::from_secs_f64(old.as_secs_f64()*(1.0 - AVERAGE_WEIGHT) + new.as_secs_f64()*AVERAGE_WEIGHT)
+        };
+        self.wait = average(self.wait, wait);
+        self.run = average(self.run, run);
+        self.done += 1;
+    }
+}
+
+impl Display for WorkerStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} queued {} done {} wait {:.1}ms run {:.1}ms", self.name, self.queued, self.done,
+            self.wait.as_secs_f64()*1000.0, self.run.as_secs_f64()*1000.0)
+    }
+}
+
+
+type JobFn = Box<dyn FnOnce(&CancellationToken) + Send>;
+
+struct Job {
+    priority: u32,
+    /// Jobs of the same priority run in the order they were submitted
+    sequence: u64,
+    queued: Instant,
+    token: CancellationToken,
+    run: JobFn,
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {self.cmp(other) == CmpOrdering::Equal}
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {Some(self.cmp(other))}
+}
+
+impl Ord for Job {
+    /// The heap pops the lowest priority number first
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        (other.priority, other.sequence).cmp(&(self.priority, self.sequence))
+    }
+}
+
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: BinaryHeap<Job>,
+    sequence: u64,
+}
+
+struct Pool {
+    queue: Mutex<JobQueue>,
+    condvar: Condvar,
+    stats: Mutex<WorkerStats>,
+    workers: usize,
+}
+
+impl Pool {
+    fn new(name: &'static str, workers: usize) -> Self {
+        Self {
+            queue: Mutex::new(JobQueue::default()),
+            condvar: Condvar::new(),
+            stats: Mutex::new(WorkerStats { name, ..Default::default() }),
+            workers,
+        }
+    }
+
+    /// Waits for the next job that is not cancelled, None when the worker has to stop
+    fn next_job(&self, token: &CancellationToken) -> Option<Job> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if token.is_cancelled() {return None};
+            match queue.jobs.pop() {
+                Some(job) if job.token.is_cancelled() => continue,
+                Some(job) => return Some(job),
+                None => queue = self.condvar.wait(queue).unwrap(),
+            }
+        }
+    }
+
+    fn stats(&self) -> WorkerStats {
+        let queued = self.queue.lock().unwrap().jobs.len();
+        WorkerStats { queued, ..self.stats.lock().unwrap().clone() }
+    }
+}
+
+
+/// Submits jobs to a pool of the scheduler, can be moved to other threads
+#[derive(Clone)]
+pub struct JobSender {
+    pool: Arc<Pool>,
+    token: CancellationToken,
+}
+
+impl JobSender {
+    /// A lower priority runs first, the returned token cancels the job if it has not started yet
+    pub fn submit(&self, priority: u32, job: impl FnOnce(&CancellationToken) + Send + 'static) -> CancellationToken {
+        let token = self.token.child();
+        let mut queue = self.pool.queue.lock().unwrap();
+        queue.sequence += 1;
+        let sequence = queue.sequence;
+        queue.jobs.push(Job { priority, sequence, queued: Instant::now(), token: token.clone(), run: Box::new(job) });
+        self.pool.condvar.notify_one();
+        token
+    }
+
+    pub fn workers(&self) -> usize {self.pool.workers}
+
+    /// Runs the jobs on the pool and returns their results in order.
+    /// The caller runs the jobs no worker has started, so a busy or stopped pool never blocks it
+    pub fn run_all<T: Send + 'static>(&self, priority: u32, jobs: Vec<PoolJob<T>>) -> Vec<T> {
+        let count = jobs.len();
+        let scope = Arc::new(Scope {
+            slots: jobs.into_iter().map(|job| Mutex::new(Some(job))).collect(),
+            results: Mutex::new((0..count).map(|_| None).collect()),
+            running: Mutex::new(0),
+            condvar: Condvar::new(),
+        });
+        // The caller starts with the first job
+        for index in 1..count {
+            let scope = scope.clone();
+            self.submit(priority, move |_| scope.run(index));
+        }
+        (0..count).for_each(|index| scope.run(index));
+
+        let mut running = scope.running.lock().unwrap();
+        while *running > 0 {running = scope.condvar.wait(running).unwrap()};
+        drop(running);
+        let results = std::mem::take(&mut *scope.results.lock().unwrap());
+        results.into_iter().map(|result| match result.expect("Every job is run") {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }).collect()
+    }
+}
+
+
+pub type PoolJob<T> = Box<dyn FnOnce() -> T + Send>;
+
+/// Jobs of one run_all call, each job is taken once by a worker or by the caller
+struct Scope<T> {
+    slots: Vec<Mutex<Option<PoolJob<T>>>>,
+    results: Mutex<Vec<Option<thread::Result<T>>>>,
+    /// Jobs taken and not finished yet
+    running: Mutex<usize>,
+    condvar: Condvar,
+}
+
+impl<T> Scope<T> {
+    fn run(&self, index: usize) {
+        let job = {
+            let mut slot = self.slots[index].lock().unwrap();
+            // Counted before the slot is seen empty, so the caller waits for this job
+            if slot.is_some() {*self.running.lock().unwrap() += 1};
+            slot.take()
+        };
+        let Some(job) = job else {return};
+        let result = panic::catch_unwind(AssertUnwindSafe(job));
+        self.results.lock().unwrap()[index] = Some(result);
+        *self.running.lock().unwrap() -= 1;
+        self.condvar.notify_all();
+    }
+}
+
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+
+/// Workers that have not returned yet, the shutdown waits until there are none
+#[derive(Debug, Default)]
+struct Running {
+    count: Mutex<usize>,
+    condvar: Condvar,
+}
+
+/// Counts the worker out when its thread ends, by a return or by a panic
+struct RunningGuard(Arc<Running>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        *self.0.count.lock().unwrap() -= 1;
+        self.0.condvar.notify_all();
+    }
+}
+
+
+/// Named worker pools and loops of an opened world, they stop together with the scheduler
+pub struct Scheduler {
+    token: CancellationToken,
+    pools: Vec<(&'static str, Arc<Pool>)>,
+    loops: Vec<Arc<Pool>>,
+    threads: Vec<(&'static str, JoinHandle<()>)>,
+    running: Arc<Running>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self { token: CancellationToken::new(), pools: vec![], loops: vec![], threads: vec![], running: Arc::default() }
+    }
+
+    pub fn token(&self) -> CancellationToken {self.token.clone()}
+
+    /// Starts the workers of a pool that runs the submitted jobs by priority
+    pub fn add_pool(&mut self, name: &'static str, workers: usize) -> JobSender {
+        let pool = Arc::new(Pool::new(name, workers.max(1)));
+        for _ in 0..workers.max(1) {
+            let (pool, token) = (pool.clone(), self.token.clone());
+            self.spawn(name, move || {
+                while let Some(job) = pool.next_job(&token) {
+                    let wait = job.queued.elapsed();
+                    let now = Instant::now();
+                    // A failed job is reported and the worker goes on with the next one
+                    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| (job.run)(&job.token))) {
+                        eprintln!("Job of the pool {} panicked: {}", name, panic_message(&*payload));
+                    }
+                    pool.stats.lock().unwrap().add(wait, now.elapsed());
+                }
+            });
+        }
+        self.pools.push((name, pool.clone()));
+        JobSender { pool, token: self.token.clone() }
+    }
+
+    pub fn sender(&self, name: &str) -> Option<JobSender> {
+        self.pools.iter().find(|(pool_name, _)| *pool_name == name)
+            .map(|(_, pool)| JobSender { pool: pool.clone(), token: self.token.clone() })
+    }
+
+    /// Runs the iteration until the scheduler stops, the iteration returns the pause before the next one
+    pub fn add_loop(&mut self, name: &'static str, mut iteration: impl FnMut(&CancellationToken) -> Duration + Send + 'static) {
+        let pool = Arc::new(Pool::new(name, 1));
+        let token = self.token.clone();
+        self.loops.push(pool.clone());
+        self.spawn(name, move || {
+            while !token.is_cancelled() {
+                let now = Instant::now();
+                let pause = iteration(&token);
+                pool.stats.lock().unwrap().add(Duration::ZERO, now.elapsed());
+                if token.sleep(pause) {break};
+            }
+        });
+    }
+
+    fn spawn(&mut self, name: &'static str, worker: impl FnOnce() + Send + 'static) {
+        *self.running.count.lock().unwrap() += 1;
+        let guard = RunningGuard(self.running.clone());
+        let thread = thread::Builder::new().name(name.to_owned()).stack_size(WORKER_STACK_SIZE)
+            .spawn(move || {
+                let _guard = guard;
+                worker();
+            }).expect("Failed to spawn a worker");
+        self.threads.push((name, thread));
+    }
+
+    pub fn stats(&self) -> Vec<WorkerStats> {
+        self.pools.iter().map(|(_, pool)| pool).chain(self.loops.iter())
+            .map(|pool| pool.stats())
+            .collect()
+    }
+
+    /// Stops the workers, the running jobs and iterations are finished first
+    pub fn cancel(&self) {
+        self.token.cancel();
+        for (_, pool) in self.pools.iter() {
+            let _queue = pool.queue.lock().unwrap();
+            pool.condvar.notify_all();
+        }
+    }
+
+    /// Cancels the workers and waits for them, the workers still running after the timeout are left behind.
+    /// Returns false if some of them did not stop in time
+    pub fn shutdown(&mut self, timeout: Duration) -> bool {
+        self.cancel();
+        let deadline = Instant::now() + timeout;
+        let mut count = self.running.count.lock().unwrap();
+        while *count > 0 {
+            let now = Instant::now();
+            if now >= deadline {break};
+            count = self.running.condvar.wait_timeout(count, deadline - now).unwrap().0;
+        }
+        // The threads of the returned workers are only ending, the join doesn't wait long
+        let is_returned = *count == 0;
+        drop(count);
+        let mut is_stopped = true;
+        for (name, thread) in self.threads.drain(..) {
+            if is_returned || thread.is_finished() {
+                let _ = thread.join();
+            } else {
+                eprintln!("Worker {} did not stop in {:?}", name, timeout);
+                is_stopped = false;
+            }
+        }
+        is_stopped
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::Scheduler;
+
+    #[test]
+    fn pool_survives_panic() {
+        let mut scheduler = Scheduler::new();
+        let jobs = scheduler.add_pool("test", 1);
+        let (sender, receiver) = mpsc::channel();
+        jobs.submit(0, |_| panic!("failed job"));
+        jobs.submit(0, move |_| sender.send(()).unwrap());
+        // The only worker runs the next job after the panic
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(()));
+        assert!(scheduler.shutdown(Duration::from_secs(5)));
+    }
+}
@@ -1,27 +1,242 @@
-use std::{sync::Arc, thread::{self, JoinHandle}, time::{Instant, Duration}};
-
-use crate::{voxels::chunks::Chunks, world::World, unsafe_mutex::UnsafeMutex, WORLD_EXIT};
-
-pub fn spawn(world: Arc<UnsafeMutex<World>>) -> JoinHandle<()> {
-    thread::spawn(move || {
-        loop {
-            if unsafe { WORLD_EXIT } {break};
-            let mut world = unsafe {world.lock_unsafe()}.unwrap();
-            let now = Instant::now();
-            let ptr = &mut world.chunks as *mut Chunks;
-            for chunk in world.chunks.chunks.iter_mut() {
-                let Some(chunk) = chunk.as_mut() else {continue};
-
-                if !chunk.voxels_data.is_empty() {
-                    chunk.unsaved = true;
-                }
-
-                for vd in chunk.voxels_data.values() {
-                    vd.update(ptr)
-                }
+use std::{sync::{Arc, Mutex, RwLock}, thread, time::{Instant, Duration}};
+
+use itertools::iproduct;
+
+use crate::{voxels::{chunks::Chunks, voxel_data::machine_status::MachineStatus},
+    world::{World, alerts::AlertService, ticks::{SimulationTicks, TICK_DURATION}, chunk_coords::ChunkCoords, global_coords::GlobalCoords}};
+
+use super::scheduler::{Scheduler, JobSender, PoolJob};
+
+/// Pause while a replay holds the next tick
+const HELD_PAUSE: Duration = Duration::from_millis(1);
+/// Pool of the machine updates, the updater waits for the columns of a step before the next one
+pub const MACHINE_POOL: &str = "machines";
+
+/// The machines only read the world, every machine changes its own state behind its lock
+pub fn spawn(scheduler: &mut Scheduler, world: Arc<RwLock<World>>, alerts: Arc<Mutex<AlertService>>, ticks: Arc<SimulationTicks>) {
+    let workers = thread::available_parallelism().map_or(1, |count| count.get());
+    let jobs = scheduler.add_pool(MACHINE_POOL, workers);
+    scheduler.add_loop("voxel_data_updater", move |_| {
+        if !ticks.start_tick() {return HELD_PAUSE};
+        let now = Instant::now();
+        update_voxels_data(&world, &mut alerts.lock().unwrap(), Some(&jobs));
+        ticks.finish_tick();
+        TICK_DURATION.saturating_sub(now.elapsed())
+    });
+}
+
+/// Status of a machine reported after its update
+type Report = (GlobalCoords, u32, MachineStatus);
+
+/// Step of the column, the columns of a step update in parallel.
+/// A machine only reaches the voxels next to it at its height, so a column only changes the columns around it
+/// and two of them may change the same voxel in the column between them. The columns of a step are two columns
+/// apart, and every column around comes in an earlier step if its chunks have lower indices and in a later one if higher,
+/// so the result is the same as the serial update of the chunks by their indices
+fn column_step(cx: i32, cz: i32) -> usize {
+    (2*cz + cx) as usize
+}
+
+/// One tick of every machine of the loaded chunks, the columns of a step are split between the workers of the pool.
+/// The steps go in order, a column from the bottom and the machines of a chunk by their index,
+/// so the update with a pool, without it and the update of the chunks by their indices give the same result.
+/// Every batch reads the world for itself, a replay only changes the world between the ticks
+pub fn update_voxels_data(world: &Arc<RwLock<World>>, alerts: &mut AlertService, jobs: Option<&JobSender>) {
+    let world_g = world.read().unwrap();
+    let chunks = &world_g.chunks;
+    let mut steps: Vec<Vec<Vec<ChunkCoords>>> = vec![vec![]; column_step(chunks.width, chunks.depth)];
+    for (cz, cx) in iproduct!(0..chunks.depth, 0..chunks.width) {
+        let column = (0..chunks.height)
+            .filter_map(|cy| chunks.local_chunk(ChunkCoords(cx, cy, cz)))
+            .filter(|chunk| !chunk.voxels_data.is_empty())
+            .map(|chunk| chunk.xyz)
+            .collect::<Vec<ChunkCoords>>();
+        if column.is_empty() {continue};
+        steps[column_step(cx, cz)].push(column);
+    }
+    drop(world_g);
+
+    for columns in steps.into_iter().filter(|columns| !columns.is_empty()) {
+        let batch = columns.len().div_ceil(jobs.map_or(1, |jobs| jobs.workers()));
+        let reports = match jobs {
+            Some(jobs) if batch < columns.len() => {
+                let batches = columns.chunks(batch).map(|batch| {
+                    let (world, batch) = (world.clone(), batch.to_vec());
+                    Box::new(move || update_columns(&world.read().unwrap().chunks, &batch)) as PoolJob<Vec<Report>>
+                }).collect();
+                jobs.run_all(0, batches).into_iter().flatten().collect()
+            },
+            _ => update_columns(&world.read().unwrap().chunks, &columns),
+        };
+        reports.into_iter().for_each(|(coords, id, status)| alerts.report(coords, id, status));
+    }
+    alerts.end_pass();
+}
+
+/// The chunks unloaded since the columns were found are skipped
+fn update_columns(chunks: &Chunks, columns: &[Vec<ChunkCoords>]) -> Vec<Report> {
+    let mut reports = vec![];
+    for chunk in columns.iter().flatten().filter_map(|coords| chunks.chunk(*coords)) {
+        let mut indices = chunk.voxels_data.keys().copied().collect::<Vec<usize>>();
+        indices.sort_unstable();
+        for vd in indices.iter().filter_map(|index| chunk.voxels_data.get(index)) {
+            vd.update(chunks);
+            if let Some(status) = vd.additionally.status() {
+                reports.push((vd.global_coords, vd.id, status));
             }
-            drop(world);
-            thread::sleep(Duration::from_millis(100u64.saturating_sub(now.elapsed().as_millis() as u64)));
         }
-    })
-}
\ No newline at end of file
+        chunk.mark_changed_machines();
+    }
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::{Arc, RwLock}, time::Duration};
+
+    use itertools::iproduct;
+
+    use crate::{direction::Direction, bytes::BytesCoder, recipes::{content::load_content, item::Item, recipes::RECIPES},
+        save_load::WorldMetadata, threads::scheduler::Scheduler, test_utils::{block_id, load_column},
+        voxels::{block::block_behaviour::BlockBehaviour, voxel_data::transport_belt::TransportBeltSide},
+        world::{World, alerts::AlertService, chunk_coords::ChunkCoords, global_coords::GlobalCoords, height_range::HeightRange, ticks::duration_ticks}};
+
+    use super::{update_voxels_data, MACHINE_POOL};
+
+    /// A loop of belts across the borders of the chunks, every belt of the loop is fed by another belt
+    fn belt_world() -> World {
+        let belt = block_id(BlockBehaviour::TransportBelt);
+        let world = RwLock::new(World::new(3, 2, 3, ChunkCoords(0, 0, 0), 0, HeightRange::new(0, 2).unwrap()));
+        for (cx, cz) in iproduct!(0..3, 0..3) {load_column(&world, cx, cz)};
+        let mut world = world.into_inner().unwrap();
+
+        let (min, max, y) = (31, 64, 40);
+        let mut belts = vec![];
+        for i in min..max {
+            belts.push((GlobalCoords(i, y, min), (1, 0)));
+            belts.push((GlobalCoords(max, y, i), (0, 1)));
+            belts.push((GlobalCoords(i+1, y, max), (-1, 0)));
+            belts.push((GlobalCoords(min, y, i+1), (0, -1)));
+        }
+        let feeders = belts.iter().map(|(coords, (dx, dz))| {
+            let inward = (-dz, *dx);
+            (GlobalCoords(coords.0 - inward.0, y, coords.2 - inward.1), inward)
+        }).collect::<Vec<_>>();
+        for (index, (coords, (dx, dz))) in belts.iter().chain(feeders.iter()).enumerate() {
+            world.set_voxel(coords, belt, &Direction::new(*dx as f32, 0.0, *dz as f32));
+            let belt = world.chunks.voxel_data(*coords).and_then(|vd| vd.additionally.transport_belt()).unwrap();
+            if index % 3 == 0 {belt.lock().unwrap().put(&Item::new(0, 1), TransportBeltSide::Left);}
+            if index % 2 == 0 {belt.lock().unwrap().put(&Item::new(0, 1), TransportBeltSide::Right);}
+        }
+        world
+    }
+
+    /// The encoded machines ordered by their coordinates
+    fn machines(world: &World) -> Vec<Box<[u8]>> {
+        let mut machines = world.chunks.chunks.iter().flatten()
+            .flat_map(|chunk| chunk.voxels_data.values())
+            .collect::<Vec<_>>();
+        machines.sort_by_key(|vd| (vd.global_coords.0, vd.global_coords.1, vd.global_coords.2));
+        machines.iter().map(|vd| vd.encode_bytes()).collect()
+    }
+
+    /// A box feeds a furnace through a manipulator, the belt loop runs next to them
+    fn furnace_world() -> Arc<RwLock<World>> {
+        let (voxel_box, manipulator, furnace) = (
+            block_id(BlockBehaviour::VoxelBox), block_id(BlockBehaviour::Manipulator), block_id(BlockBehaviour::Furnace));
+        let mut world = belt_world();
+        for (x, id) in [(10, voxel_box), (11, manipulator), (12, furnace)] {
+            world.set_voxel(&GlobalCoords(x, 40, 8), id, &Direction::new_x());
+        }
+        let ore = RECIPES().furnace.all()[0].ingredients[0].id();
+        let storage = world.chunks.voxel_data(GlobalCoords(10, 40, 8)).and_then(|vd| vd.additionally.storage()).unwrap();
+        storage.lock().unwrap().add(&Item::new(ore, 20), false);
+        Arc::new(RwLock::new(world))
+    }
+
+    #[test]
+    fn replay_ticks_are_same() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let duration = duration_ticks(RECIPES().furnace.all()[0].duration);
+        let replays = [furnace_world(), furnace_world()];
+        for world in replays.iter() {
+            let mut alerts = AlertService::new();
+            for _ in 0..3*duration + 10 {update_voxels_data(world, &mut alerts, None)};
+        }
+        let (first, second) = (replays[0].read().unwrap(), replays[1].read().unwrap());
+        assert_eq!(machines(&first), machines(&second));
+
+        // A tick is not a moment of the clock, the furnace smelts as fast as the ticks go
+        let furnace = first.chunks.voxel_data(GlobalCoords(12, 40, 8)).and_then(|vd| vd.additionally.storage()).unwrap();
+        assert!(furnace.lock().unwrap().storage()[1].0.is_some_and(|item| item.count >= 2));
+    }
+
+    /// Lines of belts full of items along the x axis, a color has two columns of them for the pool
+    fn belt_line(xs: std::ops::Range<i32>) -> Arc<RwLock<World>> {
+        let world = RwLock::new(World::new(3, 2, 3, ChunkCoords(0, 0, 0), 0, HeightRange::new(0, 2).unwrap()));
+        for (cx, cz) in iproduct!(0..3, 0..3) {load_column(&world, cx, cz)};
+        let mut world = world.into_inner().unwrap();
+        for (x, z) in iproduct!(xs, [5, 69]) {
+            let coords = GlobalCoords(x, 40, z);
+            world.set_voxel(&coords, block_id(BlockBehaviour::TransportBelt), &Direction::new_x());
+            let belt = world.chunks.voxel_data(coords).and_then(|vd| vd.additionally.transport_belt()).unwrap();
+            belt.lock().unwrap().put(&Item::new(0, 1), TransportBeltSide::Left);
+        }
+        Arc::new(RwLock::new(world))
+    }
+
+    /// The serial update, the chunks by their indices and the machines of a chunk by their index
+    fn update_by_chunk_index(world: &RwLock<World>) {
+        let chunks = &world.read().unwrap().chunks;
+        for chunk in chunks.chunks.iter().flatten() {
+            let mut indices = chunk.voxels_data.keys().copied().collect::<Vec<usize>>();
+            indices.sort_unstable();
+            indices.iter().for_each(|index| chunk.voxels_data[index].update(chunks));
+        }
+    }
+
+    #[test]
+    fn parallel_update_is_serial() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let mut scheduler = Scheduler::new();
+        let jobs = scheduler.add_pool(MACHINE_POOL, 4);
+        let mut alerts = AlertService::new();
+        // The loop passes the items into every neighbour column, the lines cross the columns of both parities
+        let layouts: [fn() -> Arc<RwLock<World>>; 3] = [|| Arc::new(RwLock::new(belt_world())), || belt_line(16..48), || belt_line(48..80)];
+        for layout in layouts {
+            let (by_index, parallel) = (layout(), layout());
+            for _ in 0..40 {
+                update_by_chunk_index(&by_index);
+                update_voxels_data(&parallel, &mut alerts, Some(&jobs));
+            }
+            let (by_index, parallel) = (by_index.read().unwrap(), parallel.read().unwrap());
+            assert_eq!(machines(&by_index), machines(&parallel));
+            assert_ne!(machines(&parallel), machines(&layout().read().unwrap()));
+        }
+        assert!(scheduler.shutdown(Duration::from_secs(1)));
+        assert!(scheduler.stats().iter().any(|stats| stats.name == MACHINE_POOL && stats.done > 0));
+    }
+
+    #[test]
+    fn idle_chunks_stay_saved() {
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let world = RwLock::new(World::new(3, 2, 3, ChunkCoords(0, 0, 0), 0, HeightRange::new(0, 2).unwrap()));
+        for cx in 0..2 {load_column(&world, cx, 0)};
+        let world = Arc::new(world);
+        let (box_coords, belt_coords) = (GlobalCoords(5, 40, 5), GlobalCoords(40, 40, 5));
+        let mut world_w = world.write().unwrap();
+        world_w.set_voxel(&box_coords, block_id(BlockBehaviour::VoxelBox), &Direction::new_x());
+        world_w.set_voxel(&belt_coords, block_id(BlockBehaviour::TransportBelt), &Direction::new_x());
+        let belt = world_w.chunks.voxel_data(belt_coords).and_then(|vd| vd.additionally.transport_belt()).unwrap();
+        belt.lock().unwrap().put(&Item::new(0, 1), TransportBeltSide::Left);
+        drop(world_w);
+
+        let mut alerts = AlertService::new();
+        update_voxels_data(&world, &mut alerts, None);
+        world.read().unwrap().chunks.chunks.iter().flatten().for_each(|chunk| {chunk.take_unsaved();});
+        update_voxels_data(&world, &mut alerts, None);
+        let chunks = &world.read().unwrap().chunks;
+        assert!(!chunks.chunk(box_coords).unwrap().unsaved());
+        assert!(chunks.chunk(belt_coords).unwrap().unsaved());
+    }
+}
@@ -1,26 +1,56 @@
-use std::{thread::{self, JoinHandle}, sync::{Arc, Mutex}, time::{Duration, Instant}};
+use std::{collections::HashSet, sync::{Arc, Mutex, RwLock}, time::Duration};
 
-use itertools::iproduct;
+use crate::{world::World, save_load::WorldRegions};
 
-use crate::{world::{World, chunk_coords::ChunkCoords, global_coords::GlobalCoords}, voxels::{chunks::WORLD_HEIGHT, chunk::{CHUNK_SIZE, Chunk}}, unsafe_mutex::UnsafeMutex, save_load::{WorldRegions, EncodedChunk}, WORLD_EXIT, bytes::BytesCoder};
+use super::scheduler::Scheduler;
 
+/// Pool of the jobs that change the chunks of the world, one worker keeps them in order
+pub const CHUNK_POOL: &str = "chunks";
+/// Translation of the loaded area runs before the queued columns
+pub const TRANSLATE_PRIORITY: u32 = 0;
+/// Columns queued at once, the loader waits for them before it looks further
+const MAX_PENDING_COLUMNS: usize = 16;
+/// Pause of the loader when every column is loaded
+const IDLE_PAUSE: Duration = Duration::from_millis(200);
+/// Pause of the loader while the queued columns are loading
+const PENDING_PAUSE: Duration = Duration::from_millis(16);
 
+
+/// Queues the missing columns to the chunk pool, the columns near the player load first.
+/// The chunks of a column are read while the world is only read, the world is locked for writing to add them
 pub fn spawn(
-    world: Arc<UnsafeMutex<World>>,
-    world_regions: Arc<UnsafeMutex<WorldRegions>>
-) -> JoinHandle<()> {
-    thread::spawn(move || {
-        loop {
-            if unsafe { WORLD_EXIT } {break};
-            let mut world = unsafe {world.lock_unsafe()}.unwrap();
-            
-            if let Some((cx, cz)) = world.chunks.find_unloaded() {
-                let mut regions = unsafe {world_regions.lock_unsafe()}.unwrap();
-                world.load_column_of_chunks(&mut regions, cx, cz);
-            } else {
-                drop(world);
-                thread::sleep(Duration::from_millis(200));
-            }
+    scheduler: &mut Scheduler,
+    world: Arc<RwLock<World>>,
+    world_regions: Arc<Mutex<WorldRegions>>
+) {
+    let jobs = scheduler.sender(CHUNK_POOL).expect("The chunk pool is added before the loader");
+    let pending = Arc::new(Mutex::new(HashSet::<(i32, i32)>::new()));
+    scheduler.add_loop("world_loader", move |_| {
+        let world_g = world.read().unwrap();
+        let mut pending_g = pending.lock().unwrap();
+        let center_x = world_g.chunks.ox + world_g.chunks.width/2;
+        let center_z = world_g.chunks.oz + world_g.chunks.depth/2;
+        while pending_g.len() < MAX_PENDING_COLUMNS {
+            let Some((cx, cz)) = world_g.chunks.find_unloaded(&pending_g) else {break};
+            pending_g.insert((cx, cz));
+            let distance = ((cx - center_x).pow(2) + (cz - center_z).pow(2)) as u32;
+            let (world, world_regions, pending) = (world.clone(), world_regions.clone(), pending.clone());
+            jobs.submit(TRANSLATE_PRIORITY + 1 + distance, move |_| {
+                // The area could move since the column was queued
+                let (missing, seed) = {
+                    let world = world.read().unwrap();
+                    (world.missing_chunks(cx, cz), world.chunks.seed)
+                };
+                if !missing.is_empty() {
+                    let chunks = {
+                        let mut regions = world_regions.lock().unwrap();
+                        missing.into_iter().map(|coords| regions.load_chunk(coords, seed)).collect()
+                    };
+                    world.write().unwrap().add_chunks(chunks);
+                }
+                pending.lock().unwrap().remove(&(cx, cz));
+            });
         }
-    })
-}
\ No newline at end of file
+        if pending_g.is_empty() {IDLE_PAUSE} else {PENDING_PAUSE}
+    });
+}
@@ -0,0 +1,48 @@
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockBehaviour {
+    #[default]
+    Default,
+    Ore,
+    MultiBlockPart,
+    Manipulator,
+    Cowboy,
+    VoxelBox,
+    Furnace,
+    Drill,
+    AssemblingMachine,
+    TransportBelt,
+}
+
+
+impl BlockBehaviour {
+    pub fn is_additional_data(&self) -> bool {
+        !matches!(self, Self::Default | Self::Ore)
+    }
+
+    pub fn is_multiblock(&self) -> bool {
+        matches!(self, Self::MultiBlockPart | Self::Drill | Self::AssemblingMachine)
+    }
+}
+
+
+impl FromStr for BlockBehaviour {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "default" => Self::Default,
+            "ore" => Self::Ore,
+            "multiblock_part" => Self::MultiBlockPart,
+            "manipulator" => Self::Manipulator,
+            "cowboy" => Self::Cowboy,
+            "box" => Self::VoxelBox,
+            "furnace" => Self::Furnace,
+            "drill" => Self::Drill,
+            "assembling_machine" => Self::AssemblingMachine,
+            "transport_belt" => Self::TransportBelt,
+            _ => return Err(()),
+        })
+    }
+}
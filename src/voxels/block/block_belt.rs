@@ -1,22 +1,28 @@
 use crate::world::coords::Coords;
 
-use super::{interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
+use super::{block_behaviour::BlockBehaviour, interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
 
 pub struct BlockBelt {
     pub id: u32,
+    pub name: String,
+    pub behaviour: BlockBehaviour,
     pub emission: [u8; 3],
     pub light_permeability: LightPermeability,
     pub block_type: BlockType,
     pub is_additional_data: bool,
+    pub hardness: f32,
 }
 
 impl BlockInteraction for BlockBelt {
     fn id(&self) -> u32 {self.id}
+    fn name(&self) -> &str {&self.name}
+    fn behaviour(&self) -> BlockBehaviour {self.behaviour}
     fn emission(&self) -> &[u8; 3] {&self.emission}
     #[inline]
     fn light_permeability(&self) -> LightPermeability {self.light_permeability}
     fn block_type(&self) -> &BlockType {&self.block_type}
     fn is_additional_data(&self) -> bool {self.is_additional_data}
+    fn hardness(&self) -> f32 {self.hardness}
 
     fn min_point(&self) -> &Coords {
         &Coords(0.0, 0.0, 0.0)
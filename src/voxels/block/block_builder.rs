@@ -1,6 +1,6 @@
-use crate::graphic::complex_object::ComplexObject;
+use crate::{graphic::complex_object::{ComplexObject, new_transport_belt}, definitions::{Definition, DefinitionError}};
 
-use super::{block_type::BlockType, interaction::BlockInteraction, block_default::BlockDefault, block_player::BlockPlayer, light_permeability::LightPermeability};
+use super::{block_type::BlockType, interaction::BlockInteraction, block_default::BlockDefault, block_player::BlockPlayer, light_permeability::LightPermeability, block_behaviour::BlockBehaviour, block_ore::BlockOre, multiblock::MultiBlock, block_belt::BlockBelt, blocks::BLOCK_TEXTURES};
 
 pub enum BlockTraitType {
     Default,
@@ -11,11 +11,16 @@ pub enum BlockTraitType {
 pub struct BlockBuilder {
     pub trait_type: BlockTraitType,
     pub id: u32,
+    pub name: String,
+    pub behaviour: BlockBehaviour,
     pub emission: Option<[u8; 3]>,
     pub light_permeability: Option<LightPermeability>,
     pub block_type: Option<BlockType>,
-    pub item_id: Option<u32>,
     pub is_additional_data: Option<bool>,
+    pub hardness: Option<f32>,
+    pub size: Option<[usize; 3]>,
+    pub ore: Option<String>,
+    pub drops: Vec<(String, u32)>,
 }
 
 
@@ -24,13 +29,80 @@ impl BlockBuilder {
         BlockBuilder {
             trait_type: BlockTraitType::Default,
             id,
+            name: String::new(),
+            behaviour: BlockBehaviour::Default,
             emission: None,
             light_permeability: None,
             block_type: None,
-            item_id: None,
             is_additional_data: None,
+            hardness: None,
+            size: None,
+            ore: None,
+            drops: vec![],
         }
     }
+
+
+    pub fn from_definition(definition: &Definition) -> Result<Self, DefinitionError> {
+        let mut builder = Self::new(definition.parse_required("id")?)
+            .name(definition.key.clone())
+            .behaviour(definition.parse("behaviour")?.unwrap_or_default());
+
+        if let Some(hardness) = definition.parse("hardness")? {builder = builder.hardness(hardness)};
+
+        let emission = definition.parse_list::<u8>("emission")?;
+        if !emission.is_empty() {
+            let [r, g, b] = emission[..] else {return Err(definition.invalid_value("emission", &emission.len().to_string()))};
+            builder = builder.emission([r, g, b]);
+        }
+
+        if let Some(value) = definition.get("light_permeability") {
+            let mut lp = LightPermeability::NONE;
+            for side in value.split('|').map(|s| s.trim()) {
+                lp |= LightPermeability::from_name(&side.to_uppercase())
+                    .ok_or_else(|| definition.invalid_value("light_permeability", value))?;
+            }
+            builder = builder.light_permeability(lp);
+        }
+
+        let faces = definition.list("faces").iter()
+            .map(|name| BLOCK_TEXTURES().layer(name).ok_or_else(|| definition.invalid_value("faces", name)))
+            .collect::<Result<Vec<u32>, DefinitionError>>()?;
+        if let Some(name) = definition.get("complex_object") {
+            let texture = faces.first().copied().unwrap_or(0);
+            builder = builder.set_complex_object(match name {
+                "transport_belt" => new_transport_belt(texture),
+                _ => return Err(definition.invalid_value("complex_object", name)),
+            });
+        } else if let Some(name) = definition.get("model") {
+            builder = builder.model_name(name.to_string());
+        } else if let Some(name) = definition.get("animated_model") {
+            builder = builder.animated_model_name(name.to_string());
+        } else if !faces.is_empty() {
+            builder = builder.faces(&faces);
+        }
+
+        let size = definition.parse_list::<usize>("multiblock")?;
+        if !size.is_empty() {
+            let [width, height, depth] = size[..] else {return Err(definition.invalid_value("multiblock", &size.len().to_string()))};
+            builder = builder.size([width, height, depth]);
+        }
+
+        builder.ore = definition.get("ore").map(|key| key.to_string());
+        builder.drops = definition.key_counts("drops")?;
+        if builder.behaviour == BlockBehaviour::Ore && builder.ore.is_none() {
+            return Err(definition.missing_key("ore"));
+        }
+        if builder.behaviour != BlockBehaviour::Ore && (builder.ore.is_some() || !builder.drops.is_empty()) {
+            return Err(definition.error("\"ore\" and \"drops\" are only supported by the ore behaviour"));
+        }
+
+        Ok(builder)
+    }
+
+    pub fn name(mut self, name: String) -> Self {self.name = name; self}
+    pub fn behaviour(mut self, behaviour: BlockBehaviour) -> Self {self.behaviour = behaviour; self}
+    pub fn size(mut self, size: [usize; 3]) -> Self {self.size = Some(size); self}
     pub fn emission(mut self, emission: [u8; 3]) -> Self {self.emission = Some(emission); self}
     pub fn light_permeability(mut self, light_permeability: LightPermeability) -> Self {
         self.light_permeability = Some(light_permeability);
@@ -66,6 +138,8 @@ impl BlockBuilder {
         self
     }
 
+    pub fn hardness(mut self, hardness: f32) -> Self {self.hardness = Some(hardness); self}
+
     pub fn set_lp_none(mut self) -> Self {
         self.light_permeability = Some(LightPermeability::NONE);
         self
@@ -78,15 +152,21 @@ impl BlockBuilder {
 
     pub fn build(self) -> Box<dyn BlockInteraction + Sync + Send> {
         let id = self.id;
+        let name = self.name;
+        let behaviour = self.behaviour;
         let emission = self.emission.unwrap_or([0, 0, 0]);
         let light_permeability = self.light_permeability.unwrap_or(LightPermeability::default());
         let block_type = self.block_type.unwrap_or(BlockType::None);
-        let item_id = self.item_id.unwrap_or(0);
-        let is_additional_data = self.is_additional_data.unwrap_or(false);
+        let is_additional_data = self.is_additional_data.unwrap_or(behaviour.is_additional_data());
+        let hardness = self.hardness.unwrap_or(0.5);
+        let [width, height, depth] = self.size.unwrap_or([1, 1, 1]);
 
-        match self.trait_type {
-            BlockTraitType::Default => Box::new(BlockDefault {id, emission, light_permeability, block_type, is_additional_data}),
-            BlockTraitType::Player => Box::new(BlockPlayer {id, item_id, emission, light_permeability, block_type, is_additional_data}),
+        match (behaviour, self.trait_type) {
+            (BlockBehaviour::Ore, _) => Box::new(BlockOre {id, name, behaviour, ore: self.ore.unwrap_or_default(), drops: self.drops, emission, light_permeability, block_type, is_additional_data, hardness}),
+            (BlockBehaviour::TransportBelt, _) => Box::new(BlockBelt {id, name, behaviour, emission, light_permeability, block_type, is_additional_data, hardness}),
+            (b, _) if b.is_multiblock() => Box::new(MultiBlock {id, name, behaviour, emission, light_permeability, block_type, is_additional_data, hardness, width, height, depth}),
+            (_, BlockTraitType::Default) => Box::new(BlockDefault {id, name, behaviour, emission, light_permeability, block_type, is_additional_data, hardness}),
+            (_, BlockTraitType::Player) => Box::new(BlockPlayer {id, name, behaviour, emission, light_permeability, block_type, is_additional_data, hardness}),
         }
     }
 }
\ No newline at end of file
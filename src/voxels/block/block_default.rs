@@ -1,20 +1,26 @@
-use super::{interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
+use super::{block_behaviour::BlockBehaviour, interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
 
 
 pub struct BlockDefault {
     pub id: u32,
+    pub name: String,
+    pub behaviour: BlockBehaviour,
     pub emission: [u8; 3],
     pub light_permeability: LightPermeability,
     pub block_type: BlockType,
     pub is_additional_data: bool,
+    pub hardness: f32,
 }
 
 
 impl BlockInteraction for BlockDefault {
     fn id(&self) -> u32 {self.id}
+    fn name(&self) -> &str {&self.name}
+    fn behaviour(&self) -> BlockBehaviour {self.behaviour}
     fn emission(&self) -> &[u8; 3] {&self.emission}
     #[inline]
     fn light_permeability(&self) -> LightPermeability {self.light_permeability}
     fn block_type(&self) -> &BlockType {&self.block_type}
     fn is_additional_data(&self) -> bool {self.is_additional_data}
+    fn hardness(&self) -> f32 {self.hardness}
 }
\ No newline at end of file
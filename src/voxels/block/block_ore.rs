@@ -1,34 +1,42 @@
-use crate::{recipes::{item::Item, storage::Storage}, world::{World, global_coords::GlobalCoords}, player::player::Player};
+use crate::recipes::{item::Item, items::item_by_key};
 
-use super::{interaction::{BlockInteraction, BlockItem}, block_type::BlockType, light_permeability::LightPermeability};
+use super::{block_behaviour::BlockBehaviour, interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
 
 pub struct BlockOre {
-    pub item_id: u32,
+    pub ore: String,
+    pub drops: Vec<(String, u32)>,
     pub id: u32,
+    pub name: String,
+    pub behaviour: BlockBehaviour,
     pub emission: [u8; 3],
     pub light_permeability: LightPermeability,
     pub block_type: BlockType,
     pub is_additional_data: bool,
+    pub hardness: f32,
 }
 
 impl BlockInteraction for BlockOre {
     fn id(&self) -> u32 {self.id}
+    fn name(&self) -> &str {&self.name}
+    fn behaviour(&self) -> BlockBehaviour {self.behaviour}
     fn emission(&self) -> &[u8; 3] {&self.emission}
     #[inline]
     fn light_permeability(&self) -> LightPermeability {self.light_permeability}
     fn block_type(&self) -> &BlockType {&self.block_type}
     fn is_additional_data(&self) -> bool {self.is_additional_data}
+    fn hardness(&self) -> f32 {self.hardness}
 
-    fn on_block_break(&self, world: &mut World, player: &mut Player, xyz: &GlobalCoords) {
-        world.break_voxel(xyz);
-        player.inventory().lock().unwrap().add(&Item::new(self.item_id(), 1), true);
+    fn drops(&self) -> Vec<Item> {
+        self.drops.iter()
+            .filter_map(|(key, count)| item_by_key(key).map(|item| Item::new(item.id, *count)))
+            .collect()
     }
 
     fn ore(&self) -> Option<Item> {
-        Some(Item::new(self.item_id(), 1))
+        item_by_key(&self.ore).map(|item| Item::new(item.id, 1))
     }
-}
 
-impl BlockItem for BlockOre {
-    fn item_id(&self) -> u32 {self.item_id}
+    fn item_keys(&self) -> Vec<&str> {
+        std::iter::once(self.ore.as_str()).chain(self.drops.iter().map(|(key, _)| key.as_str())).collect()
+    }
 }
\ No newline at end of file
@@ -1,23 +1,24 @@
-use super::{interaction::{BlockInteraction, BlockItem}, block_type::BlockType, light_permeability::LightPermeability};
+use super::{block_behaviour::BlockBehaviour, interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
 
 pub struct BlockPlayer {
-    pub item_id: u32,
     pub id: u32,
+    pub name: String,
+    pub behaviour: BlockBehaviour,
     pub emission: [u8; 3],
     pub light_permeability: LightPermeability,
     pub block_type: BlockType,
     pub is_additional_data: bool,
+    pub hardness: f32,
 }
 
 impl BlockInteraction for BlockPlayer {
     fn id(&self) -> u32 {self.id}
+    fn name(&self) -> &str {&self.name}
+    fn behaviour(&self) -> BlockBehaviour {self.behaviour}
     fn emission(&self) -> &[u8; 3] {&self.emission}
     #[inline]
     fn light_permeability(&self) -> LightPermeability {self.light_permeability}
     fn block_type(&self) -> &BlockType {&self.block_type}
     fn is_additional_data(&self) -> bool {self.is_additional_data}
+    fn hardness(&self) -> f32 {self.hardness}
 }
-
-impl BlockItem for BlockPlayer {
-    fn item_id(&self) -> u32 {self.item_id}
-}
\ No newline at end of file
@@ -1,84 +1,73 @@
 use std::sync::OnceLock;
 
-use crate::graphic::complex_object::new_transport_belt;
+use crate::definitions::{Definitions, DefinitionError};
 
-use super::{interaction::BlockInteraction, light_permeability::LightPermeability, block_ore::BlockOre, multiblock::MultiBlock, block_type::BlockType, block_builder::BlockBuilder, block_belt::BlockBelt};
+use super::{interaction::BlockInteraction, block_builder::BlockBuilder, block_behaviour::BlockBehaviour};
+
+const BLOCKS_PATH: &str = "./assets/data/blocks.txt";
+const BLOCK_TEXTURES_PATH: &str = "./assets/data/block_textures.txt";
+
+pub const MULTIBLOCK_PART_ID: u32 = 1;
 
 static BLOCKS_CONTAINER: OnceLock<Vec<Box<(dyn BlockInteraction + Send + Sync)>>> = OnceLock::new();
 #[allow(non_snake_case)]
 pub fn BLOCKS() -> &'static Vec<Box<(dyn BlockInteraction + Send + Sync)>> {
     BLOCKS_CONTAINER.get_or_init(|| {
-        let blocks = vec![
-            //Air
-            BlockBuilder::new(0).build(),
-            //Special Block
-            Box::new(MultiBlock {
-                id: 1,
-                emission: [0, 0, 0],
-                light_permeability: LightPermeability::ALL,
-                block_type: BlockType::None,
-                is_additional_data: true,
-                width: 1,
-                height: 1,
-                depth: 1
-            }),
-
-            BlockBuilder::new(2).faces(&[1]).set_lp_none().build(),
-            BlockBuilder::new(3).faces(&[2]).set_lp_none().build(),
-            BlockBuilder::new(4).faces(&[3]).set_lp_none().emission([15, 15, 15]).build(),
-            Box::new(BlockOre{
-                item_id: 0,
-                id: 5,
-                emission: [0, 0, 0],
-                light_permeability: LightPermeability::NONE,
-                block_type: BlockType::Block { faces: [4, 4, 4, 4, 4, 4] },
-                is_additional_data: false,
-            }),
-            BlockBuilder::new(6).faces(&[6,6,6,5,6,6]).light_permeability(LightPermeability::Y).build(),
-            Box::new(BlockOre{
-                item_id: 3,
-                id: 7,
-                emission: [0, 0, 0],
-                light_permeability: LightPermeability::NONE,
-                block_type: BlockType::Block { faces: [9, 9, 9, 9, 9, 9] },
-                is_additional_data: false,
-            }),
-            BlockBuilder::new(8).faces(&[7]).set_lp_none().build(),
-            BlockBuilder::new(9).animated_model_name(String::from("manipulator")).set_additional_data_true().build(),
-            BlockBuilder::new(10).model_name(String::from("monkey")).emission([15, 10, 1]).build(),
-            BlockBuilder::new(11).model_name(String::from("astronaut")).build(),
-            BlockBuilder::new(12).animated_model_name(String::from("cowboy")).set_additional_data_true().build(),
-            BlockBuilder::new(13).faces(&[8]).set_additional_data_true().set_lp_none().build(),
-            BlockBuilder::new(14).model_name(String::from("furnace")).set_additional_data_true().build(),
-            Box::new(MultiBlock {
-                id: 15,
-                emission: [0, 0, 0],
-                light_permeability: LightPermeability::ALL,
-                block_type: BlockType::Model { name: String::from("drill") },
-                is_additional_data: true,
-                width: 2,
-                height: 1,
-                depth: 2
-            }),
-            Box::new(MultiBlock {
-                id: 16,
-                emission: [0, 0, 0],
-                light_permeability: LightPermeability::ALL,
-                block_type: BlockType::Model { name: String::from("assembler") },
-                is_additional_data: true,
-                width: 2,
-                height: 2,
-                depth: 2
-            }),
-            Box::new(BlockBelt {
-                id: 17,
-                emission: [0, 0, 0],
-                light_permeability: LightPermeability::ALL,
-                block_type: BlockType::ComplexObject { cp: new_transport_belt() },
-                is_additional_data: true,
-            }),
-        ];
-
-        blocks
+        load_blocks(BLOCKS_PATH).unwrap_or_else(|e| panic!("Failed to load blocks: {e}"))
     })
-}
\ No newline at end of file
+}
+
+
+pub fn block_by_name(name: &str) -> Option<&'static (dyn BlockInteraction + Send + Sync)> {
+    BLOCKS().iter().find(|block| block.name() == name).map(|block| block.as_ref())
+}
+
+
+#[derive(Debug)]
+pub struct BlockTextures {
+    names: Vec<String>,
+    paths: Vec<String>,
+}
+
+impl BlockTextures {
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        self.names.iter().position(|n| n == name).map(|i| i as u32)
+    }
+
+    pub fn paths(&self) -> Vec<&str> {
+        self.paths.iter().map(|p| p.as_str()).collect()
+    }
+}
+
+static BLOCK_TEXTURES_CONTAINER: OnceLock<BlockTextures> = OnceLock::new();
+#[allow(non_snake_case)]
+pub fn BLOCK_TEXTURES() -> &'static BlockTextures {
+    BLOCK_TEXTURES_CONTAINER.get_or_init(|| {
+        let definitions = Definitions::load(BLOCK_TEXTURES_PATH)
+            .unwrap_or_else(|e| panic!("Failed to load block textures: {e}"));
+        let (names, paths) = definitions.root.values.into_iter().unzip();
+        BlockTextures { names, paths }
+    })
+}
+
+
+fn load_blocks(path: &str) -> Result<Vec<Box<(dyn BlockInteraction + Send + Sync)>>, DefinitionError> {
+    let definitions = Definitions::load(path)?;
+    let mut blocks: Vec<Option<Box<(dyn BlockInteraction + Send + Sync)>>> = vec![];
+    for definition in definitions.sections.iter() {
+        let block = BlockBuilder::from_definition(definition)?.build();
+        let id = block.id() as usize;
+        if blocks.len() <= id {blocks.resize_with(id + 1, || None)};
+        if blocks[id].is_some() {return Err(definition.error(format!("duplicate block id {id}")))};
+        blocks[id] = Some(block);
+    }
+
+    let blocks = blocks.into_iter().enumerate().map(|(id, block)| {
+        block.ok_or_else(|| definitions.root.error(format!("missing block with id {id}")))
+    }).collect::<Result<Vec<_>, DefinitionError>>()?;
+
+    if blocks.get(MULTIBLOCK_PART_ID as usize).map(|b| b.behaviour()) != Some(BlockBehaviour::MultiBlockPart) {
+        return Err(definitions.root.error(format!("block {MULTIBLOCK_PART_ID} must have the multiblock_part behaviour")));
+    }
+    Ok(blocks)
+}
@@ -1,9 +1,11 @@
-use crate::{player::player::Player, direction::Direction, world::{World, global_coords::GlobalCoords, coords::Coords}, recipes::item::Item};
+use crate::{player::player::Player, direction::Direction, world::{World, global_coords::GlobalCoords, coords::Coords}, recipes::{item::Item, items::block_item}};
 
-use super::{block_type::BlockType, light_permeability::LightPermeability};
+use super::{block_behaviour::BlockBehaviour, block_type::BlockType, light_permeability::LightPermeability};
 
 pub trait BlockInteraction {
     fn id(&self) -> u32;
+    fn name(&self) -> &str;
+    fn behaviour(&self) -> BlockBehaviour;
     fn emission(&self) -> &[u8; 3];
     fn light_permeability(&self) -> LightPermeability;
     fn block_type(&self) -> &BlockType;
@@ -18,10 +20,20 @@ pub trait BlockInteraction {
     fn is_voxel_size(&self) -> bool {false}
 
     fn ore(&self) -> Option<Item> {None}
+    /// Item keys referenced by the block definition
+    fn item_keys(&self) -> Vec<&str> {vec![]}
 
+    /// Seconds of holding the break button needed to mine the block
+    fn hardness(&self) -> f32 {0.5}
 
-    fn on_block_break(&self, world: &mut World, _: &mut Player, xyz: &GlobalCoords) {
-        world.break_voxel(xyz);
+    /// Items the player receives after mining the block
+    fn drops(&self) -> Vec<Item> {
+        block_item(self.id()).map_or(vec![], |item| vec![Item::new(item.id, 1)])
+    }
+
+
+    fn on_block_break(&self, world: &mut World, player: &mut Player, xyz: &GlobalCoords) {
+        if give_drop(player, &self.drops()) {world.break_voxel(xyz)};
     }
     fn on_block_set(&self, world: &mut World, _: &mut Player, xyz: &GlobalCoords, dir: &Direction) -> bool {
         if world.voxel(xyz).map(|v| v.id == 0).unwrap_or(true) {
@@ -32,6 +44,7 @@ pub trait BlockInteraction {
     }
 }
 
-pub trait BlockItem {
-    fn item_id(&self) -> u32;
+/// The block is broken only if its drop fits in the inventory, there are no items lying in the world
+pub fn give_drop(player: &mut Player, drop: &[Item]) -> bool {
+    player.inventory().lock().unwrap().add_all(drop)
 }
\ No newline at end of file
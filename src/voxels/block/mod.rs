@@ -7,4 +7,5 @@ pub mod blocks;
 pub mod light_permeability;
 pub mod block_ore;
 pub mod multiblock;
-pub mod block_belt;
\ No newline at end of file
+pub mod block_belt;
+pub mod block_behaviour;
\ No newline at end of file
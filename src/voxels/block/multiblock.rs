@@ -1,14 +1,17 @@
 use crate::{world::{World, global_coords::GlobalCoords}, player::player::Player, direction::Direction};
 
-use super::{interaction::BlockInteraction, block_type::BlockType, light_permeability::LightPermeability};
+use super::{block_behaviour::BlockBehaviour, interaction::{BlockInteraction, give_drop}, blocks::BLOCKS, block_type::BlockType, light_permeability::LightPermeability};
 
 
 pub struct MultiBlock {
     pub id: u32,
+    pub name: String,
+    pub behaviour: BlockBehaviour,
     pub emission: [u8; 3],
     pub light_permeability: LightPermeability,
     pub block_type: BlockType,
     pub is_additional_data: bool,
+    pub hardness: f32,
     pub width: usize,
     pub height: usize,
     pub depth: usize,
@@ -17,17 +20,22 @@ pub struct MultiBlock {
 
 impl BlockInteraction for MultiBlock {
     fn id(&self) -> u32 {self.id}
+    fn name(&self) -> &str {&self.name}
+    fn behaviour(&self) -> BlockBehaviour {self.behaviour}
     fn emission(&self) -> &[u8; 3] {&self.emission}
     fn light_permeability(&self) -> LightPermeability {self.light_permeability}
     fn block_type(&self) -> &BlockType {&self.block_type}
     fn is_additional_data(&self) -> bool {self.is_additional_data}
+    fn hardness(&self) -> f32 {self.hardness}
 
     fn is_multiblock(&self) -> bool {true}
     fn width(&self) -> usize {self.width}
     fn height(&self) -> usize {self.height}
     fn depth(&self) -> usize {self.depth}
 
-    fn on_block_break(&self, world: &mut World, _: &mut Player, xyz: &GlobalCoords) {
+    fn on_block_break(&self, world: &mut World, player: &mut Player, xyz: &GlobalCoords) {
+        let Some(id) = world.chunks.voxel_data(*xyz).map(|vd| vd.id) else {return};
+        if !give_drop(player, &BLOCKS()[id as usize].drops()) {return};
         if let Some(xyz) = world.chunks.remove_multiblock_structure(*xyz) {
             xyz.iter().for_each(|c| {
                 world.light.on_block_break(&mut world.chunks, c.0, c.1, c.2);
@@ -39,7 +47,7 @@ impl BlockInteraction for MultiBlock {
         // FIX THIS SHIT
         let mut width = self.width as i32;
         let mut depth = self.depth as i32;
-        if self.id() == 15 {
+        if self.behaviour == BlockBehaviour::Drill {
             let d = dir.simplify_to_one_greatest(true, false, true);
             if d[2] < 0 {width = -(self.width as i32)};
             if d[2] < 0 {depth = -(self.depth as i32)};
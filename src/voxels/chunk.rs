@@ -1,9 +1,9 @@
-use std::{collections::HashMap, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::{SystemTime, UNIX_EPOCH}};
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}, sync::{Arc, OnceLock, atomic::{AtomicBool, AtomicU64, Ordering}}, time::{SystemTime, UNIX_EPOCH}};
 
 use itertools::iproduct;
-use crate::{light::light_map::{LightMap, Light}, direction::Direction, world::{local_coords::LocalCoords, chunk_coords::ChunkCoords}, bytes::{AsFromBytes, BytesCoder}};
+use crate::{light::light_map::{LightMap, Light}, direction::Direction, world::{local_coords::LocalCoords, chunk_coords::ChunkCoords}, bytes::{AsFromBytes, BytesCoder, DecodeError, slice_bytes}};
 
-use super::{voxel::{self, Voxel}, voxel_data::{VoxelData, VoxelAdditionalData}, block::blocks::BLOCKS};
+use super::{voxel::Voxel, palette::PaletteVoxels, voxel_data::{VoxelData, VoxelAdditionalData}, block::blocks::{BLOCKS, block_by_name}};
 use std::io::prelude::*;
 use flate2::{Compression, read::ZlibDecoder};
 use flate2::write::ZlibEncoder;
@@ -14,56 +14,99 @@ pub const _CHUNK_SQUARE: usize = CHUNK_SIZE.pow(2);
 pub const CHUNK_VOLUME: usize = CHUNK_SIZE.pow(3);
 pub const CHUNK_BIT_SHIFT: usize = CHUNK_SIZE.ilog2() as usize;
 pub const CHUNK_BITS: usize = CHUNK_SIZE - 1_usize;
-pub const COMPRESSION_TYPE: CompressionType = CompressionType::Zlib;
+pub const COMPRESSION_TYPE: CompressionType = CompressionType::Palette;
 
 #[derive(Clone, Copy, Debug)]
 #[repr(u8)]
 pub enum CompressionType {
     None = 0b000000,
     Zlib = 0b000001,
+    /// Block id palette with bit packed indices, see PaletteVoxels
+    Palette = 0b000010,
 }
 
 impl From<u8> for CompressionType {
     fn from(value: u8) -> Self {
         match value {
             1 => Self::Zlib,
+            2 => Self::Palette,
             _ => Self::None
         }
     }
 }
 
+/// Blocks of the generated terrain by their names in the block definitions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerrainBlocks {
+    pub rock: u32,
+    pub ore: u32,
+}
+
+static TERRAIN_BLOCKS: OnceLock<TerrainBlocks> = OnceLock::new();
+
+/// The blocks must be loaded
+pub fn terrain_blocks() -> TerrainBlocks {
+    *TERRAIN_BLOCKS.get_or_init(|| {
+        let id = |name| block_by_name(name).unwrap_or_else(|| panic!("The terrain block \"{name}\" is not defined")).id();
+        TerrainBlocks { rock: id("rock"), ore: id("iron_ore") }
+    })
+}
+
+/// Rock with iron ore scattered through it, fills the world below zero
+fn underground_id(x: i32, y: i32, z: i32, seed: u64, blocks: &TerrainBlocks) -> u32 {
+    let mut hash = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (z as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash ^= hash >> 31;
+    if hash.is_multiple_of(24) {blocks.ore} else {blocks.rock}
+}
+
+
 #[derive(Debug)]
 pub struct Chunk {
-    pub voxels: [voxel::Voxel; CHUNK_VOLUME],
+    voxels: PaletteVoxels,
     pub voxels_data: HashMap<usize, VoxelData>,
     modified: AtomicBool,
-    pub unsaved: bool,
+    unsaved: AtomicBool,
+    /// Hash of the encoded machines at the last check
+    machines_hash: AtomicU64,
     pub lightmap: LightMap,
     pub xyz: ChunkCoords,
 }
 
 
 impl Chunk {
-    pub fn new(pos_x: i32, pos_y: i32, pos_z: i32) -> Chunk {
-        let mut voxels = [Voxel::new(0); CHUNK_VOLUME];
+    pub fn new(pos_x: i32, pos_y: i32, pos_z: i32, seed: u64) -> Chunk {
+        Self::generate(pos_x, pos_y, pos_z, seed, &terrain_blocks())
+    }
+
+    fn generate(pos_x: i32, pos_y: i32, pos_z: i32, seed: u64, blocks: &TerrainBlocks) -> Chunk {
+        let mut ids = vec![0; CHUNK_VOLUME];
         let voxels_data = HashMap::new();
+        let phase = (seed % 1024) as f64;
 
         for (y, z, x) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
             let real_x = x as i32 + pos_x*CHUNK_SIZE as i32;
             let real_y = y as i32 + pos_y*CHUNK_SIZE as i32;
             let real_z = z as i32 + pos_z*CHUNK_SIZE as i32;
 
-            if real_y as f64 <= ((real_x as f64 *0.3).sin() * 0.5 + 0.5) * 10. {
-                voxels[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x].id = 7;
+            if real_y < 0 {
+                ids[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x] = underground_id(real_x, real_y, real_z, seed, blocks);
+                continue;
+            }
+            if real_y as f64 <= ((real_x as f64 *0.3 + phase).sin() * 0.5 + 0.5) * 10. {
+                ids[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x] = blocks.rock;
             }
             if real_y <= 2 {
-                voxels[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x].id = 5;
+                ids[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x] = blocks.ore;
             }
             if z == 0 && y == 16 {
-                voxels[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x].id = 7;
+                ids[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x] = blocks.rock;
             }
             if x == 0 && y == 0 {
-                voxels[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x].id = 7;
+                ids[(y*CHUNK_SIZE+z)*CHUNK_SIZE+x] = blocks.rock;
             }
 
             // if real_z == 200 {
@@ -72,17 +115,18 @@ impl Chunk {
         }
 
         Chunk {
-            voxels,
+            voxels: PaletteVoxels::from_ids(&ids),
             xyz: ChunkCoords(pos_x, pos_y, pos_z),
+            machines_hash: AtomicU64::new(machines_hash(&voxels_data)),
             voxels_data,
-            unsaved: true,
+            unsaved: AtomicBool::new(true),
             modified: AtomicBool::new(true),
             lightmap: LightMap::new(),
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.voxels.iter().all(|voxel| { voxel.id == 0 })
+        self.voxels.is_filled_with(0)
     }
 
 
@@ -90,16 +134,14 @@ impl Chunk {
         self.voxel(coords).id == 0
     }
 
-    pub unsafe fn get_unchecked_voxel(&self, local_coords: LocalCoords) -> &Voxel {
+    /// # Safety
+    /// The coordinates must be inside the chunk
+    pub unsafe fn get_unchecked_voxel(&self, local_coords: LocalCoords) -> Voxel {
         self.voxels.get_unchecked(local_coords.index())
     } 
 
-    pub fn voxel(&self, local_coords: LocalCoords) -> &Voxel {
-        &self.voxels[local_coords.index()]
-    }
-
-    fn mut_voxel(&mut self, local_coords: LocalCoords) -> &mut Voxel {
-        &mut self.voxels[local_coords.index()]
+    pub fn voxel(&self, local_coords: LocalCoords) -> Voxel {
+        self.voxels.get(local_coords.index())
     }
 
     #[inline]
@@ -108,13 +150,36 @@ impl Chunk {
     }
 
     #[inline]
-    pub fn modify(&mut self, value: bool) {
+    pub fn modify(&self, value: bool) {
         self.modified.store(value, Ordering::Release);
     }
 
+    #[inline]
+    pub fn unsaved(&self) -> bool {
+        self.unsaved.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn set_unsaved(&self, value: bool) {
+        self.unsaved.store(value, Ordering::Release);
+    }
+
+    /// Clears the flag, returns true if the chunk was unsaved
+    #[inline]
+    pub fn take_unsaved(&self) -> bool {
+        self.unsaved.swap(false, Ordering::AcqRel)
+    }
+
+    /// Marks the chunk unsaved if its machines changed since the last check, so idle machines don't resave the chunk.
+    /// A change by a machine of another chunk is found at the next check
+    pub fn mark_changed_machines(&self) {
+        let hash = machines_hash(&self.voxels_data);
+        if self.machines_hash.swap(hash, Ordering::AcqRel) != hash {self.set_unsaved(true)};
+    }
+
     pub fn set_voxel_id(&mut self, local_coords: LocalCoords, id: u32, direction: Option<&Direction>) {
         self.voxels_data.remove(&local_coords.index());
-        self.mut_voxel(local_coords).id = id;
+        self.voxels.set(local_coords.index(), id);
         if BLOCKS()[id as usize].is_additional_data() {
             self.voxels_data.insert(local_coords.index(), VoxelData {
                 id,
@@ -149,6 +214,15 @@ impl Chunk {
         self.voxels_data.insert(local_coords.index(), voxel_data)
     }
 
+    /// Decodes the chunk saved at the coordinates, a chunk saved under others is an error
+    pub fn decode_at(data: &[u8], coords: ChunkCoords) -> Result<Self, DecodeError> {
+        let chunk = Self::decode_bytes(data)?;
+        if chunk.xyz != coords {
+            return Err(DecodeError::Mismatch { name: "chunk coordinates", expected: format!("{:?}", coords), found: format!("{:?}", chunk.xyz) });
+        }
+        Ok(chunk)
+    }
+
     #[inline]
     pub fn get_light(&self, local_coords: LocalCoords) -> Light {
         self.lightmap.get_light(local_coords.into())
@@ -157,11 +231,15 @@ impl Chunk {
 
 impl AsFromBytes for [Voxel; CHUNK_VOLUME] {}
 impl BytesCoder for [Voxel; CHUNK_VOLUME] {
-    fn decode_bytes(bytes: &[u8]) -> Self {
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         let mut decoder = ZlibDecoder::new(bytes);
         let mut buf = Vec::new();
-        decoder.read_to_end(&mut buf).unwrap();
-        <[Voxel; CHUNK_VOLUME]>::from_bytes(&buf)
+        decoder.read_to_end(&mut buf).map_err(DecodeError::Decompression)?;
+        let voxels = <[Voxel; CHUNK_VOLUME]>::from_bytes(&buf)?;
+        if let Some(voxel) = voxels.iter().find(|voxel| voxel.id as usize >= BLOCKS().len()) {
+            return Err(DecodeError::InvalidValue { name: "block id", value: voxel.id as u64 });
+        }
+        Ok(voxels)
     }
 
     fn encode_bytes(&self) -> Box<[u8]> {
@@ -188,19 +266,20 @@ impl BytesCoder for HashMap<usize, VoxelData> {
         bytes.into()
     }
 
-    fn decode_bytes(bytes: &[u8]) -> Self {
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         let mut h = HashMap::<usize, VoxelData>::new();
         let mut offset: usize = 0;
         while offset < bytes.len() {
             let key_end = offset + u32::size();
-            let key = u32::from_bytes(&bytes[offset..key_end]) as usize;
+            let key = u32::from_bytes_at(bytes, offset)? as usize;
+            if key >= CHUNK_VOLUME {return Err(DecodeError::InvalidValue { name: "voxel index", value: key as u64 })};
             let len_end = key_end+u32::size();
-            let len = u32::from_bytes(&bytes[key_end..len_end]) as usize;
-            let vd = VoxelData::decode_bytes(&bytes[len_end..len_end+len]);
+            let len = u32::from_bytes_at(bytes, key_end)? as usize;
+            let vd = VoxelData::decode_bytes(slice_bytes(bytes, len_end..len_end+len)?)?;
             h.insert(key, vd);
             offset = len_end+len;
         }
-        h
+        Ok(h)
     }
 }
 
@@ -214,7 +293,15 @@ pub struct CompressChunk {
     pub voxel_data_len: u32,
     pub compression_type: CompressionType,
 }
-impl AsFromBytes for CompressChunk {}
+impl AsFromBytes for CompressChunk {
+    fn validate(bytes: &[u8]) -> Result<(), DecodeError> {
+        let compression_type = bytes[std::mem::offset_of!(CompressChunk, compression_type)];
+        if compression_type > CompressionType::Palette as u8 {
+            return Err(DecodeError::InvalidValue { name: "compression type", value: compression_type as u64 });
+        }
+        Ok(())
+    }
+}
 
 impl BytesCoder for Chunk {
     fn encode_bytes(&self) -> Box<[u8]> {
@@ -234,30 +321,126 @@ impl BytesCoder for Chunk {
         bytes.extend(voxel_data.as_ref());
         bytes.into()
     }
-    fn decode_bytes(data: &[u8]) -> Self {
-        let compress = CompressChunk::from_bytes(&data[0..CompressChunk::size()]);
+    fn decode_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let compress = CompressChunk::from_bytes_at(data, 0)?;
         let voxel_end = CompressChunk::size() + compress.voxel_len as usize;
         let voxel_data_end = voxel_end + compress.voxel_data_len as usize;
-        let voxels = <[Voxel; CHUNK_VOLUME]>::decode_bytes(&data[CompressChunk::size()..voxel_end]);
-        let voxels_data = <HashMap::<usize, VoxelData>>::decode_bytes(&data[voxel_end..voxel_data_end]);
+        let voxel_bytes = slice_bytes(data, CompressChunk::size()..voxel_end)?;
+        let voxels = match compress.compression_type {
+            CompressionType::Palette => PaletteVoxels::decode_bytes(voxel_bytes)?,
+            CompressionType::Zlib => PaletteVoxels::from_ids(
+                &<[Voxel; CHUNK_VOLUME]>::decode_bytes(voxel_bytes)?.iter().map(|voxel| voxel.id).collect::<Vec<u32>>()),
+            CompressionType::None => return Err(DecodeError::InvalidValue { name: "compression type", value: 0 }),
+        };
+        let voxels_data = <HashMap::<usize, VoxelData>>::decode_bytes(slice_bytes(data, voxel_end..voxel_data_end)?)?;
 
-        Self {
+        Ok(Self {
             voxels,
+            machines_hash: AtomicU64::new(machines_hash(&voxels_data)),
             voxels_data,
             modified: AtomicBool::new(true),
-            unsaved: false,
+            unsaved: AtomicBool::new(false),
             lightmap: LightMap::new(),
             xyz: compress.xyz,
-        }
+        })
     }
 }
 
+/// The machines are hashed by their index, the order of the map changes between the runs
+fn machines_hash(voxels_data: &HashMap<usize, VoxelData>) -> u64 {
+    let mut indices = voxels_data.keys().copied().collect::<Vec<usize>>();
+    indices.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    indices.iter().for_each(|index| voxels_data[index].encode_bytes().hash(&mut hasher));
+    hasher.finish()
+}
+
 #[cfg(test)]
 mod test {
-    use crate::voxels::chunk::CHUNK_SIZE;
+    use std::mem::offset_of;
+    use itertools::iproduct;
+    use crate::{voxels::{chunk::{Chunk, CompressChunk, TerrainBlocks, CHUNK_SIZE, terrain_blocks}, voxel_data::VoxelData, block::{blocks::BLOCKS, block_behaviour::BlockBehaviour}}, world::{chunk_coords::ChunkCoords, local_coords::LocalCoords}, bytes::{BytesCoder, AsFromBytes, DecodeError}};
+
+    /// Chunk with a box in it, so that the voxel data is saved too
+    fn chunk_with_box() -> (Chunk, LocalCoords) {
+        let box_id = BLOCKS().iter().position(|b| b.behaviour() == BlockBehaviour::VoxelBox).unwrap() as u32;
+        let mut chunk = Chunk::new(1, 0, -2, 7);
+        let coords = LocalCoords(3, 4, 5);
+        chunk.set_voxel_id(coords, box_id, None);
+        (chunk, coords)
+    }
 
     #[test]
     fn correct_chunk_size() {
         assert!(CHUNK_SIZE > 1 && (CHUNK_SIZE & CHUNK_SIZE-1) == 0 && CHUNK_SIZE <= 32);
     }
+
+    #[test]
+    fn underground_generation() {
+        let TerrainBlocks { rock, ore } = terrain_blocks();
+        let chunk = Chunk::new(0, -1, 0, 7);
+        let ids = (0..CHUNK_SIZE).flat_map(|y| (0..CHUNK_SIZE).map(move |x| LocalCoords(x as u8, y as u8, 3)))
+            .map(|coords| chunk.voxel(coords).id).collect::<Vec<u32>>();
+        assert!(ids.iter().all(|id| *id == rock || *id == ore), "the underground is solid rock and ore");
+        let ores = ids.iter().filter(|id| **id == ore).count();
+        assert!(ores > 0 && ores < ids.len() / 4);
+        assert_eq!(Chunk::new(0, -1, 0, 7).voxel(LocalCoords(1, 2, 3)).id, chunk.voxel(LocalCoords(1, 2, 3)).id);
+
+        // The generation places the blocks it is given
+        let blocks = TerrainBlocks { rock: 2, ore: 3 };
+        let chunk = Chunk::generate(0, -1, 0, 7, &blocks);
+        assert!(iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE).map(|(y, x)| chunk.voxel(LocalCoords(x as u8, y as u8, 3)).id)
+            .all(|id| id == blocks.rock || id == blocks.ore));
+    }
+
+    #[test]
+    fn chunk_decode() {
+        let (chunk, coords) = chunk_with_box();
+        let bytes = chunk.encode_bytes();
+        let decoded = Chunk::decode_at(&bytes, chunk.xyz).unwrap();
+        assert_eq!(decoded.voxel(coords).id, chunk.voxel(coords).id);
+        assert_eq!(decoded.voxels_data().len(), 1);
+        assert!(matches!(Chunk::decode_at(&bytes, ChunkCoords(1, 1, -2)), Err(DecodeError::Mismatch { .. })));
+    }
+
+    #[test]
+    fn truncated_chunk() {
+        let bytes = chunk_with_box().0.encode_bytes();
+        for len in 0..bytes.len() {
+            assert!(Chunk::decode_bytes(&bytes[..len]).is_err(), "decoded {len} of {} bytes", bytes.len());
+        }
+    }
+
+    #[test]
+    fn corrupt_chunk() {
+        let bytes = chunk_with_box().0.encode_bytes();
+        for i in 0..bytes.len() {
+            let mut corrupt = bytes.to_vec();
+            corrupt[i] ^= 0xFF;
+            let _ = Chunk::decode_bytes(&corrupt);
+        }
+        for offset in [offset_of!(CompressChunk, voxel_len), offset_of!(CompressChunk, voxel_data_len), offset_of!(CompressChunk, compression_type)] {
+            let mut corrupt = bytes.to_vec();
+            corrupt[offset..offset + u32::size()].fill(0xFF);
+            assert!(Chunk::decode_bytes(&corrupt).is_err());
+        }
+    }
+
+    #[test]
+    fn damaged_voxel_data() {
+        let (chunk, coords) = chunk_with_box();
+        let bytes = chunk.voxel_data(coords).unwrap().encode_bytes();
+        assert!(VoxelData::decode_bytes(&bytes).is_ok());
+        for len in 0..bytes.len() {
+            assert!(VoxelData::decode_bytes(&bytes[..len]).is_err());
+        }
+        for i in 0..bytes.len() {
+            let mut corrupt = bytes.to_vec();
+            corrupt[i] ^= 0xFF;
+            let _ = VoxelData::decode_bytes(&corrupt);
+        }
+        let mut unknown_block = bytes.to_vec();
+        unknown_block[..u32::size()].copy_from_slice(u32::MAX.as_bytes());
+        assert!(VoxelData::decode_bytes(&unknown_block).is_err());
+    }
 }
\ No newline at end of file
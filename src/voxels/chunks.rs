@@ -1,21 +1,21 @@
-use std::{collections::HashMap, sync::{Arc, Mutex}};
+use std::{collections::HashMap, sync::{Arc, Mutex, atomic::AtomicBool}};
 
 use itertools::iproduct;
 
-use crate::{direction::Direction, world::{global_coords::GlobalCoords, local_coords::LocalCoords, chunk_coords::ChunkCoords}, vec_none, unsafe_mutex::UnsafeMutex, save_load::{WorldRegions, EncodedChunk}, bytes::BytesCoder, light::light_map::Light};
+use crate::{direction::Direction, world::{global_coords::GlobalCoords, local_coords::LocalCoords, chunk_coords::ChunkCoords, height_range::HeightRange}, vec_none, save_load::WorldRegions, light::light_map::Light};
 
-use super::{chunk::{Chunk, CHUNK_SIZE}, voxel::Voxel, voxel_data::{VoxelAdditionalData, VoxelData, multiblock::MultiBlock}};
-
-pub const WORLD_HEIGHT: usize = 256 / CHUNK_SIZE; // In chunks
+use super::{chunk::{Chunk, CHUNK_SIZE}, voxel::Voxel, block::blocks::MULTIBLOCK_PART_ID, voxel_data::{VoxelAdditionalData, VoxelData, multiblock::MultiBlock}};
 
 #[derive(Debug)]
 pub struct Chunks {
-    pub is_translate: bool,
+    /// Set while a translation of the loaded area is queued
+    pub is_translate: AtomicBool,
     pub chunks: Vec<Option<Box<Chunk>>>,
-    pub chunks_awaiting_deletion: Arc<Mutex<Vec<Box<Chunk>>>>,
+    pub chunks_awaiting_deletion: Arc<Mutex<Vec<Chunk>>>,
     pub volume: i32,
     pub width_with_offset: i32, //Needed to optimize the function (is_in_area)
     pub depth_with_offset: i32, //Needed to optimize the function (is_in_area)
+    pub height_with_offset: i32, //Needed to optimize the function (is_in_area)
     pub width: i32,
     pub height: i32,
     pub depth: i32,
@@ -26,10 +26,17 @@ pub struct Chunks {
     pub ox: i32,
     pub oy: i32,
     pub oz: i32,
+
+    /// Seed of the world generation
+    pub seed: u64,
+    /// The loaded area never leaves the range
+    pub heights: HeightRange,
 }
 
 impl Chunks {
-    pub fn new(width: i32, height: i32, depth: i32, ox: i32, oy: i32, oz: i32) -> Chunks {
+    /// The origin is the lowest chunk of the loaded area
+    pub fn new(width: i32, height: i32, depth: i32, origin: ChunkCoords, seed: u64, heights: HeightRange) -> Chunks {
+        let ChunkCoords(ox, oy, oz) = origin;
         let volume = width*height*depth;
         let mut chunks: Vec<Option<Box<Chunk>>> = vec![];
         for _ in 0..volume { chunks.push(None); }
@@ -41,6 +48,7 @@ impl Chunks {
             width,
             width_with_offset: width+ox,
             depth_with_offset: depth+oz,
+            height_with_offset: height+oy,
             height,
             depth,
             ox,
@@ -48,30 +56,35 @@ impl Chunks {
             oz,
             translate_x: 0,
             translate_z: 0,
-            is_translate: false
+            is_translate: AtomicBool::new(false),
+            seed,
+            heights,
         }
     }
 
 
     pub fn load_chunk(&mut self, coords: ChunkCoords) {
-        let index = coords.nindex(self.width, self.depth, self.ox, self.oz);
+        let index = coords.nindex(self.width, self.depth, self.ox, self.oy, self.oz);
         if self.chunks[index].is_some() {return};
-        self.chunks[index] = Some(Box::new(Chunk::new(coords.0, coords.1, coords.2)));
+        self.chunks[index] = Some(Box::new(Chunk::new(coords.0, coords.1, coords.2, self.seed)));
     }
 
+    /// Moves and reallocates the grid, returns the old and the new indices of the kept chunks
     /// ONLY SAFE ACCESS
-    pub fn translate(&mut self, ox: i32, oz: i32) -> Vec<(usize, usize)> {
+    pub fn resize(&mut self, width: i32, height: i32, depth: i32, ox: i32, oy: i32, oz: i32) -> Vec<(usize, usize)> {
         let mut indices = Vec::<(usize, usize)>::new();
-        let mut new_chunks: Vec<Option<Box<Chunk>>> = vec_none!(self.chunks.len());
+        let mut new_chunks: Vec<Option<Box<Chunk>>> = vec_none!((width*height*depth) as usize);
 
         let dx = ox - self.ox;
+        let dy = oy - self.oy;
         let dz = oz - self.oz;
         for (cz, cx, cy) in iproduct!(0..self.depth, 0..self.width, 0..self.height) {
             let nx = cx - dx;
+            let ny = cy - dy;
             let nz = cz - dz;
-            if nx < 0 || nz < 0 || nx >= self.width || nz >= self.depth {continue};
+            if nx < 0 || ny < 0 || nz < 0 || nx >= width || ny >= height || nz >= depth {continue};
 
-            let new_index = ChunkCoords(nx, cy, nz).index_without_offset(self.width, self.depth);
+            let new_index = ChunkCoords(nx, ny, nz).index_without_offset(width, depth);
             let old_index = ChunkCoords(cx, cy, cz).index_without_offset(self.width, self.depth);
             
             indices.push((old_index, new_index));
@@ -80,27 +93,30 @@ impl Chunks {
 
         for chunk in self.chunks.iter_mut() {
             let Some(chunk) = chunk.take() else {continue};
-            if chunk.unsaved {self.chunks_awaiting_deletion.lock().unwrap().push(chunk)}
+            if chunk.unsaved() {self.chunks_awaiting_deletion.lock().unwrap().push(*chunk)}
         }
 
         self.chunks = new_chunks;
+        self.width = width;
+        self.height = height;
+        self.depth = depth;
+        self.volume = width*height*depth;
         self.ox = ox;
+        self.oy = oy;
         self.oz = oz;
         self.width_with_offset = self.width + ox;
+        self.height_with_offset = self.height + oy;
         self.depth_with_offset = self.depth + oz;
         indices
     }
 
 
-    pub fn load_all(&mut self, world_regions: Arc<UnsafeMutex<WorldRegions>>) {
+    pub fn load_all(&mut self, world_regions: &Mutex<WorldRegions>) {
         for (cy, cz, cx) in iproduct!(0..self.height, 0..self.depth, 0..self.width) {
             let index = ChunkCoords(cx, cy, cz).index_without_offset(self.width, self.depth);
             let Some(chunk) = self.chunks.get_mut(index) else {continue};
-            let mut world_regions = unsafe {world_regions.lock_unsafe()}.unwrap();
-            *chunk = match world_regions.chunk(ChunkCoords(cx+self.ox, cy+self.oy, cz+self.oz)) {
-                EncodedChunk::None => Some(Box::new(Chunk::new(cx+self.ox, cy+self.oy, cz+self.oz))),
-                EncodedChunk::Some(b) => Some(Box::new(Chunk::decode_bytes(b))),
-            }
+            let mut world_regions = world_regions.lock().unwrap();
+            *chunk = Some(Box::new(world_regions.load_chunk(ChunkCoords(cx+self.ox, cy+self.oy, cz+self.oz), self.seed)));
         }
     }
 
@@ -133,16 +149,16 @@ impl Chunks {
         let chunk = &self.chunks[index];
         if chunk.is_some() { return false; }
 
-        self.chunks[index] = Some(Box::new(Chunk::new(near_x+self.ox, near_y+self.oy, near_z+self.oz)));
+        self.chunks[index] = Some(Box::new(Chunk::new(near_x+self.ox, near_y+self.oy, near_z+self.oz, self.seed)));
 
         true
     }
 
-    pub fn voxel(&self, chunk_coords: ChunkCoords, local_coords: LocalCoords) -> Option<&Voxel> {
+    pub fn voxel(&self, chunk_coords: ChunkCoords, local_coords: LocalCoords) -> Option<Voxel> {
         self.chunk(chunk_coords).map(|c| c.voxel(local_coords))
     }
 
-    pub fn voxel_global(&self, coords: GlobalCoords) -> Option<&Voxel> {
+    pub fn voxel_global(&self, coords: GlobalCoords) -> Option<Voxel> {
         self.voxel(coords.into(), coords.into())
     }
 
@@ -163,7 +179,7 @@ impl Chunks {
         let z_offset = (local.2 == (CHUNK_SIZE-1) as u8) as i32 - (local.2 == 0) as i32;
         chunk.set_voxel_id(local, id, direction);
         chunk.modify(true);
-        chunk.unsaved = true;
+        chunk.set_unsaved(true);
         
         if x_offset != 0 {
             if let Some(chunk) = self.mut_chunk((coords.0+x_offset, coords.1, coords.2)) {chunk.modify(true)};
@@ -181,7 +197,7 @@ impl Chunks {
     #[inline]
     pub fn is_in_area(&self, chunk_coords: ChunkCoords) -> bool {
         chunk_coords.0 >= self.ox && chunk_coords.0 < self.width_with_offset &&
-        chunk_coords.1 >= 0 && chunk_coords.1 < self.height &&
+        chunk_coords.1 >= self.oy && chunk_coords.1 < self.height_with_offset &&
         chunk_coords.2 >= self.oz && chunk_coords.2 < self.depth_with_offset
     }
 
@@ -198,7 +214,7 @@ impl Chunks {
     pub fn chunk<T: Into<ChunkCoords>>(&self, coords: T) -> Option<&Chunk> {
         let coords: ChunkCoords = coords.into();
         if !self.is_in_area(coords) { return None; }
-        let index = coords.nindex(self.width, self.depth, self.ox, self.oz);
+        let index = coords.nindex(self.width, self.depth, self.ox, self.oy, self.oz);
         // It's safe because we checked the coordinates
         unsafe {self.chunks.get_unchecked(index).as_ref().map(|c| c.as_ref())}
     }
@@ -206,7 +222,7 @@ impl Chunks {
     pub fn mut_chunk<T: Into<ChunkCoords>>(&mut self, coords: T) -> Option<&mut Chunk> {
         let coords: ChunkCoords = coords.into();
         if !self.is_in_area(coords) { return None; }
-        let index = coords.nindex(self.width, self.depth, self.ox, self.oz);
+        let index = coords.nindex(self.width, self.depth, self.ox, self.oy, self.oz);
         // It's safe because we checked the coordinates
         unsafe {self.chunks.get_unchecked_mut(index).as_mut().map(|c| c.as_mut())}
     }
@@ -227,16 +243,6 @@ impl Chunks {
         return self.voxels_data(*gc).and_then(|vd| vd.get(&LocalCoords::from(*gc).index()));
     }
 
-    pub fn mut_voxel_data(&mut self, gc: GlobalCoords) -> Option<&mut VoxelData> {
-        let self_ptr = self as *mut Self;
-        let voxel_data = self.mut_voxels_data(gc).and_then(|vd| vd.get_mut(&LocalCoords::from(gc).index()));
-        let Some(VoxelAdditionalData::MultiBlockPart(gc)) = voxel_data.as_ref().map(|vd| vd.additionally.as_ref()) else {
-            return voxel_data;
-        };
-        // It's safe
-        return unsafe{&mut *(self_ptr)}.mut_voxels_data(*gc).and_then(|vd| vd.get_mut(&LocalCoords::from(*gc).index()));
-    }
-
     pub fn set_additional_voxel_data(&mut self, id: u32, gc: GlobalCoords, ad: Arc<VoxelAdditionalData>) {
         let local: LocalCoords = gc.into();
         let vd = self.mut_voxels_data(gc);
@@ -280,10 +286,10 @@ impl Chunks {
             additionally: Arc::new(VoxelAdditionalData::new_multiblock(id, dir, coords.clone())),
         });
         coords.iter().skip(1).for_each(|coord| {
-            self.set(*coord, 1, None);
+            self.set(*coord, MULTIBLOCK_PART_ID, None);
             let voxels_data = self.mut_voxels_data(*coord).unwrap();
             voxels_data.insert(LocalCoords::from(*coord).index(), VoxelData {
-                id: 1,
+                id: MULTIBLOCK_PART_ID,
                 global_coords: *coord,
                 additionally: Arc::new(VoxelAdditionalData::MultiBlockPart(coords[0])),
             });
@@ -1,10 +1,10 @@
-use std::time::Instant;
+use std::{collections::HashSet, time::Instant};
 
 use itertools::iproduct;
 
 use crate::{world::chunk_coords::ChunkCoords, rev_qumark};
 
-use super::{chunks::{Chunks, WORLD_HEIGHT}, chunk::{Chunk, CHUNK_SIZE}};
+use super::{chunks::Chunks, chunk::{Chunk, CHUNK_SIZE}};
 
 const SIDE_COORDS_OFFSET: [(i32, i32, i32); 4] = [
     (1,0,0), (-1,0,0),
@@ -12,18 +12,22 @@ const SIDE_COORDS_OFFSET: [(i32, i32, i32); 4] = [
 ];
 
 impl Chunks {
-    pub fn find_unloaded(&mut self) -> Option<(i32, i32)> {
+    /// The nearest column with a chunk missing at any height of the loaded area, the pending columns are skipped
+    pub fn find_unloaded(&self, pending: &HashSet<(i32, i32)>) -> Option<(i32, i32)> {
         let callback = |cx: i32, cz: i32| {
-            let index = ChunkCoords(cx, 0, cz).index_without_offset(self.width, self.depth);
-            unsafe {self.chunks.get_unchecked(index)}.is_none().then_some((cx + self.ox, cz + self.oz))
+            if pending.contains(&(cx + self.ox, cz + self.oz)) {return None};
+            (0..self.height).any(|cy| {
+                let index = ChunkCoords(cx, cy, cz).index_without_offset(self.width, self.depth);
+                unsafe {self.chunks.get_unchecked(index)}.is_none()
+            }).then_some((cx + self.ox, cz + self.oz))
         };
  
         Self::clockwise_square_spiral(self.width as usize, callback)
     }
 
-    pub fn find_unrendered(&mut self) -> Option<&mut Chunk> {
+    pub fn find_unrendered(&self) -> Option<&Chunk> {
         let callback = |cx: i32, cz: i32| {
-            for cy in 0..WORLD_HEIGHT as i32 {
+            for cy in 0..self.height {
                 let index = ChunkCoords(cx+1, cy, cz+1).index_without_offset(self.width, self.depth);
                 if unsafe {self.chunks.get_unchecked(index)}.as_ref()
                     .map_or(true, |c| !c.modified()) {continue};
@@ -40,7 +44,7 @@ impl Chunks {
         };
 
         Self::clockwise_square_spiral(self.width as usize - 2, callback)
-            .and_then(|index| self.chunks[index].as_mut().map(|c| c.as_mut()))
+            .and_then(|index| self.chunks[index].as_deref())
     }
 
     pub fn clockwise_square_spiral<T>(n: usize, callback: impl Fn(i32, i32) -> Option<T>) -> Option<T> {
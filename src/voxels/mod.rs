@@ -1,6 +1,7 @@
 pub mod block;
 pub mod voxel;
 pub mod chunk;
+pub mod palette;
 pub mod chunks;
 pub mod ray_cast;
 pub mod find_chunks;
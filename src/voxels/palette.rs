@@ -0,0 +1,320 @@
+use std::io::prelude::*;
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+
+use crate::bytes::{AsFromBytes, BytesCoder, DecodeError, slice_bytes, cast_vec_from_bytes, cast_bytes_from_slice};
+use super::{voxel::Voxel, chunk::CHUNK_VOLUME, block::blocks::BLOCKS};
+
+
+/// Voxels of a chunk as bit packed indices into a palette of block ids
+#[derive(Debug, Clone)]
+pub enum PaletteVoxels {
+    /// Every voxel has the same id, like the air above the ground
+    Single(u32),
+    Packed(PackedVoxels),
+}
+
+#[derive(Debug, Clone)]
+pub struct PackedVoxels {
+    palette: Vec<u32>,
+    /// Voxels of every palette entry, an entry no voxel uses is taken by the next new id
+    counts: Vec<u32>,
+    /// Bits of one index, a power of two so an index never crosses a word
+    bits: u32,
+    words: Box<[u64]>,
+}
+
+
+impl PackedVoxels {
+    fn bits_for(palette_len: usize) -> u32 {
+        (usize::BITS - (palette_len - 1).leading_zeros()).max(1).next_power_of_two()
+    }
+
+    fn new(palette: Vec<u32>, indices: impl Iterator<Item = usize>) -> Self {
+        let bits = Self::bits_for(palette.len());
+        let counts = vec![0; palette.len()];
+        let mut packed = Self { palette, counts, bits, words: vec![0; CHUNK_VOLUME*bits as usize/64].into_boxed_slice() };
+        indices.enumerate().for_each(|(i, index)| {
+            packed.set_index(i, index);
+            packed.counts[index] += 1;
+        });
+        packed
+    }
+
+    /// Every voxel has the index zero
+    fn filled(id: u32) -> Self {
+        Self { palette: vec![id], counts: vec![CHUNK_VOLUME as u32], bits: 1, words: vec![0; CHUNK_VOLUME/64].into_boxed_slice() }
+    }
+
+    #[inline]
+    fn mask(&self) -> u64 {(1 << self.bits) - 1}
+
+    #[inline]
+    fn index(&self, i: usize) -> usize {
+        let bit = i * self.bits as usize;
+        ((self.words[bit >> 6] >> (bit & 63)) & self.mask()) as usize
+    }
+
+    #[inline]
+    unsafe fn index_unchecked(&self, i: usize) -> usize {
+        let bit = i * self.bits as usize;
+        ((self.words.get_unchecked(bit >> 6) >> (bit & 63)) & self.mask()) as usize
+    }
+
+    #[inline]
+    fn set_index(&mut self, i: usize, index: usize) {
+        let bit = i * self.bits as usize;
+        let mask = self.mask() << (bit & 63);
+        let word = &mut self.words[bit >> 6];
+        *word = (*word & !mask) | ((index as u64) << (bit & 63));
+    }
+
+    #[inline]
+    fn id(&self, i: usize) -> u32 {
+        self.palette[self.index(i)]
+    }
+
+    /// A new id takes an unused palette entry or is appended to the palette,
+    /// the indices are repacked when they run out of bits
+    fn set(&mut self, i: usize, id: u32) {
+        let old = self.index(i);
+        if self.palette[old] == id {return};
+        let index = match self.palette.iter().position(|p| *p == id) {
+            Some(index) => index,
+            None => match self.counts.iter().position(|count| *count == 0) {
+                Some(unused) => {
+                    self.palette[unused] = id;
+                    unused
+                },
+                None => {
+                    self.palette.push(id);
+                    if self.palette.len() > 1 << self.bits {
+                        *self = Self::new(self.palette.clone(), (0..CHUNK_VOLUME).map(|i| self.index(i)));
+                    } else {
+                        self.counts.push(0);
+                    }
+                    self.palette.len() - 1
+                },
+            },
+        };
+        self.counts[old] -= 1;
+        self.counts[index] += 1;
+        self.set_index(i, index);
+    }
+
+    fn is_filled_with(&self, id: u32) -> bool {
+        self.palette.iter().position(|p| *p == id).is_some_and(|index| self.counts[index] as usize == CHUNK_VOLUME)
+    }
+}
+
+
+impl PaletteVoxels {
+    /// The ids of all voxels of the chunk
+    pub fn from_ids(ids: &[u32]) -> Self {
+        let mut palette = Vec::<u32>::new();
+        let indices = ids.iter().map(|id| palette.iter().position(|p| p == id).unwrap_or_else(|| {
+            palette.push(*id);
+            palette.len() - 1
+        })).collect::<Vec<usize>>();
+        if palette.len() == 1 {return Self::Single(palette[0])};
+        Self::Packed(PackedVoxels::new(palette, indices.into_iter()))
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> Voxel {
+        match self {
+            Self::Single(id) => Voxel::new(*id),
+            Self::Packed(packed) => Voxel::new(packed.id(i)),
+        }
+    }
+
+    /// # Safety
+    /// The index must be less than CHUNK_VOLUME
+    #[inline]
+    pub unsafe fn get_unchecked(&self, i: usize) -> Voxel {
+        match self {
+            Self::Single(id) => Voxel::new(*id),
+            Self::Packed(packed) => Voxel::new(*packed.palette.get_unchecked(packed.index_unchecked(i))),
+        }
+    }
+
+    pub fn set(&mut self, i: usize, id: u32) {
+        match self {
+            Self::Single(single) if *single == id => {},
+            Self::Single(single) => {
+                let mut packed = PackedVoxels::filled(*single);
+                packed.set(i, id);
+                *self = Self::Packed(packed);
+            },
+            // The chunk is single again when the last other id is replaced
+            Self::Packed(packed) => {
+                packed.set(i, id);
+                if packed.is_filled_with(id) {*self = Self::Single(id)};
+            },
+        }
+    }
+
+    /// Only the palette is checked, the packed chunk counts the voxels of every id
+    pub fn is_filled_with(&self, id: u32) -> bool {
+        match self {
+            Self::Single(single) => *single == id,
+            Self::Packed(packed) => packed.is_filled_with(id),
+        }
+    }
+
+    /// Drops the ids no voxel uses any more, the palette only grows while the chunk is changed
+    pub fn compacted(&self) -> Self {
+        match self {
+            Self::Single(id) => Self::Single(*id),
+            Self::Packed(packed) => Self::from_ids(&(0..CHUNK_VOLUME).map(|i| packed.id(i)).collect::<Vec<u32>>()),
+        }
+    }
+}
+
+
+/// The palette length, the palette and the zlib compressed indices when there is more than one id
+impl BytesCoder for PaletteVoxels {
+    fn encode_bytes(&self) -> Box<[u8]> {
+        let mut bytes = Vec::new();
+        match self.compacted() {
+            Self::Single(id) => {
+                bytes.extend(1u32.as_bytes());
+                bytes.extend(id.as_bytes());
+            },
+            Self::Packed(packed) => {
+                bytes.extend((packed.palette.len() as u32).as_bytes());
+                packed.palette.iter().for_each(|id| bytes.extend(id.as_bytes()));
+                let mut encoder = ZlibEncoder::new(bytes, Compression::default());
+                encoder.write_all(cast_bytes_from_slice(&packed.words)).unwrap();
+                bytes = encoder.finish().unwrap();
+            },
+        }
+        bytes.into()
+    }
+
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let len = u32::from_bytes_at(bytes, 0)? as usize;
+        if len == 0 || len > CHUNK_VOLUME {
+            return Err(DecodeError::InvalidValue { name: "palette length", value: len as u64 });
+        }
+        let palette_end = u32::size()*(len + 1);
+        let palette = cast_vec_from_bytes::<u32>(slice_bytes(bytes, u32::size()..palette_end)?)?;
+        if let Some(id) = palette.iter().find(|id| **id as usize >= BLOCKS().len()) {
+            return Err(DecodeError::InvalidValue { name: "block id", value: *id as u64 });
+        }
+        if len == 1 {return Ok(Self::Single(palette[0]))};
+
+        let bits = PackedVoxels::bits_for(len);
+        let words_len = CHUNK_VOLUME*bits as usize/64;
+        // One byte more than the words is enough to see that the data is too long
+        let mut buf = Vec::new();
+        ZlibDecoder::new(&bytes[palette_end..]).take((words_len*u64::size() + 1) as u64)
+            .read_to_end(&mut buf).map_err(DecodeError::Decompression)?;
+        if buf.len() != words_len*u64::size() {
+            return Err(DecodeError::Length { expected: words_len*u64::size(), found: buf.len() });
+        }
+        let mut packed = PackedVoxels { counts: vec![0; len], palette, bits, words: cast_vec_from_bytes::<u64>(&buf)?.into_boxed_slice() };
+        for i in 0..CHUNK_VOLUME {
+            let index = packed.index(i);
+            let Some(count) = packed.counts.get_mut(index) else {
+                return Err(DecodeError::InvalidValue { name: "palette index", value: index as u64 });
+            };
+            *count += 1;
+        }
+        if let Some(index) = packed.counts.iter().position(|count| *count as usize == CHUNK_VOLUME) {
+            return Ok(Self::Single(packed.palette[index]));
+        }
+        Ok(Self::Packed(packed))
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use flate2::{Compression, write::ZlibEncoder};
+
+    use crate::{bytes::{AsFromBytes, BytesCoder, DecodeError}, voxels::{chunk::CHUNK_VOLUME, block::blocks::BLOCKS}};
+    use super::{PackedVoxels, PaletteVoxels};
+
+    fn ids(voxels: &PaletteVoxels) -> Vec<u32> {
+        (0..CHUNK_VOLUME).map(|i| voxels.get(i).id).collect()
+    }
+
+    #[test]
+    fn single_to_packed() {
+        let mut voxels = PaletteVoxels::from_ids(&[2; CHUNK_VOLUME]);
+        assert!(matches!(voxels, PaletteVoxels::Single(2)));
+        voxels.set(7, 2);
+        assert!(matches!(voxels, PaletteVoxels::Single(2)));
+        voxels.set(7, 1);
+        let PaletteVoxels::Packed(packed) = &voxels else {panic!("one voxel differs")};
+        assert_eq!((packed.palette.as_slice(), packed.bits), ([2, 1].as_slice(), 1));
+        assert_eq!((voxels.get(7).id, voxels.get(6).id, voxels.get(8).id), (1, 2, 2));
+    }
+
+    #[test]
+    fn repack_on_growth() {
+        let mut voxels = PaletteVoxels::from_ids(&[0; CHUNK_VOLUME]);
+        let mut expected = vec![0; CHUNK_VOLUME];
+        let mut bits = vec![];
+        for id in 1..20u32 {
+            for i in (id as usize..CHUNK_VOLUME).step_by(37 + id as usize) {
+                voxels.set(i, id);
+                expected[i] = id;
+            }
+            let PaletteVoxels::Packed(packed) = &voxels else {panic!("the chunk has {} ids", id + 1)};
+            bits.push(packed.bits);
+            assert_eq!(ids(&voxels), expected, "{} ids in {} bits", id + 1, packed.bits);
+        }
+        bits.dedup();
+        assert_eq!(bits, [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn single_after_writes() {
+        let mut voxels = PaletteVoxels::from_ids(&[0; CHUNK_VOLUME]);
+        (0..100).for_each(|i| voxels.set(i, 3));
+        voxels.set(200, 4);
+        assert!(!voxels.is_filled_with(0) && !voxels.is_filled_with(5));
+        // An unused entry is taken by the next id instead of growing the palette
+        voxels.set(200, 0);
+        voxels.set(300, 5);
+        let PaletteVoxels::Packed(packed) = &voxels else {panic!("three ids are left")};
+        assert_eq!(packed.palette, [0, 3, 5]);
+
+        (0..100).chain([300]).for_each(|i| voxels.set(i, 0));
+        assert!(matches!(voxels, PaletteVoxels::Single(0)));
+        assert!(voxels.is_filled_with(0));
+    }
+
+    #[test]
+    fn encode_decode() {
+        let single = PaletteVoxels::from_ids(&[1; CHUNK_VOLUME]);
+        let decoded = PaletteVoxels::decode_bytes(&single.encode_bytes()).unwrap();
+        assert!(matches!(decoded, PaletteVoxels::Single(1)));
+
+        let ids_count = BLOCKS().len().min(6) as u32;
+        let mut voxels = PaletteVoxels::from_ids(&(0..CHUNK_VOLUME as u32).map(|i| i % ids_count).collect::<Vec<u32>>());
+        let decoded = PaletteVoxels::decode_bytes(&voxels.encode_bytes()).unwrap();
+        assert_eq!(ids(&decoded), ids(&voxels));
+
+        // Ids no voxel uses any more are not saved
+        for i in 0..CHUNK_VOLUME {
+            if voxels.get(i).id > 1 {voxels.set(i, 0)};
+        }
+        let decoded = PaletteVoxels::decode_bytes(&voxels.encode_bytes()).unwrap();
+        assert_eq!(ids(&decoded), ids(&voxels));
+        let PaletteVoxels::Packed(packed) = decoded else {panic!("two ids are left")};
+        assert_eq!(packed.palette.len(), 2);
+    }
+
+    #[test]
+    fn oversized_words() {
+        let words_size = CHUNK_VOLUME*PackedVoxels::bits_for(2) as usize/8;
+        let mut bytes: Vec<u8> = [2u32, 0, 1].iter().flat_map(|value| value.as_bytes().to_vec()).collect();
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&vec![0; words_size*64]).unwrap();
+        bytes.extend(encoder.finish().unwrap());
+        assert!(matches!(PaletteVoxels::decode_bytes(&bytes), Err(DecodeError::Length { found, .. }) if found == words_size + 1));
+    }
+}
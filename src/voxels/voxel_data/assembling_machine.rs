@@ -1,9 +1,9 @@
 use std::sync::{Arc, Mutex};
 
-use crate::{recipes::{item::{PossibleItem, Item}, storage::Storage, recipe::{Recipe, ActiveRecipe}, recipes::RECIPES}, world::global_coords::GlobalCoords, gui::{draw::Draw, my_widgets::{assembling_machine_slot::assembling_machine_slot, recipe::recipe}}, player::inventory::PlayerInventory, engine::texture::TextureAtlas, bytes::{BytesCoder, AsFromBytes, cast_bytes_from_slice, cast_vec_from_bytes}};
+use crate::{recipes::{item::{PossibleItem, Item}, storage::Storage, recipe::{Recipe, ActiveRecipe}, recipes::RECIPES}, world::global_coords::GlobalCoords, gui::{draw::Draw, my_widgets::{assembling_machine_slot::assembling_machine_slot, recipe::recipe, machine_status::machine_status}, slot_action::{SlotAction, container_slot_action}, gui_action::StorageAction}, player::inventory::PlayerInventory, engine::texture::TextureAtlas, bytes::{BytesCoder, AsFromBytes, DecodeError, slice_bytes, cast_bytes_from_slice, cast_vec_from_bytes}};
 use crate::gui::my_widgets::container::container;
 
-use super::{multiblock::MultiBlock, DrawStorage};
+use super::{multiblock::MultiBlock, DrawStorage, machine_status::MachineStatus};
 
 const INGREDIENT_LENGTH: usize = 3;
 const RESULT_LENGTH: usize = 1;
@@ -48,13 +48,27 @@ impl AssemblingMachine {
             self.active_recipe = self.start_recipe(self.selected_recipe.unwrap());
         }
 
-        let Some(active_recipe) = &self.active_recipe else {return};
-        if !active_recipe.is_finished() || !self.storage()[3].is_possible_add(&active_recipe.recipe.result) {return};
-        
+        let Some(active_recipe) = &mut self.active_recipe else {return};
+        active_recipe.advance(1);
         let add_item = active_recipe.recipe.result;
+        if !active_recipe.is_finished() || !self.storage()[3].is_possible_add(&add_item) {return};
+        
         self.mut_storage()[3].try_add_item(&add_item);
         self.active_recipe = None;
     }
+
+    pub fn status(&self) -> MachineStatus {
+        match (&self.active_recipe, self.selected_recipe) {
+            (_, None) => MachineStatus::NoRecipe,
+            (Some(ar), _) if ar.is_finished() && !self.storage[3].is_possible_add(&ar.recipe.result) => MachineStatus::OutputFull,
+            (Some(_), _) => MachineStatus::Working,
+            (None, _) => MachineStatus::NoIngredients,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.active_recipe.as_ref().map_or(0.0, |ar| if ar.is_finished() {1.0} else {ar.progress()})
+    }
 }
 
 
@@ -98,6 +112,12 @@ impl Storage for AssemblingMachine {
         Some(added_item)
     }
 
+    fn accepts(&self, index: usize, item: &Item) -> bool {
+        self.selected_recipe
+            .and_then(|recipe| recipe.ingredients.get(index))
+            .is_some_and(|ingredient| index < INGREDIENT_LENGTH && ingredient.id() == item.id())
+    }
+
     fn take_first_existing(&mut self, max_count: u32) -> Option<(Item, usize)> {
         for (i, possible_item) in self.mut_storage()[INGREDIENT_LENGTH..TOTAL_LENGTH].iter_mut().enumerate() {
             let Some(item) = possible_item.try_take(max_count) else {continue};
@@ -120,14 +140,16 @@ impl MultiBlock for AssemblingMachine {
 
 
 impl Draw for AssemblingMachine {
-    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>, inventory: Arc<Mutex<PlayerInventory>>) {
-        let mut task: Option<usize> = None;
+    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>) -> Option<StorageAction> {
+        let mut task: Option<StorageAction> = None;
         let selected_recipe = self.selected_recipe();
+        ui.add(machine_status(self.status(), self.progress()));
         if let Some(selected_recipe) = selected_recipe {
             ui.horizontal(|ui| {
                 for (i, item) in self.storage().iter().enumerate() {
-                    if ui.add(assembling_machine_slot(&atlas, item, i, selected_recipe, i==3)).drag_started() {
-                        task = Some(i);
+                    let response = ui.add(assembling_machine_slot(&atlas, item, i, selected_recipe, i==3));
+                    if let Some(action) = SlotAction::from_response(ui, &response) {
+                        task = Some(StorageAction::Slot(i, action));
                     };
                 }
             });
@@ -141,31 +163,38 @@ impl Draw for AssemblingMachine {
                 ui.set_style(style);
                 ui.horizontal(|ui| {
                     for i in RECIPES().assembler.all() {
-                        if ui.add(recipe(&atlas, i)).drag_started() {
-                            let result = self.select_recipe(i.index);
-                            for item in result.0 {
-                                let Some(item) = item.0 else {continue};
-                                inventory.lock().unwrap().add(&item, true);
-                            }
-                            for item in result.1 {
-                                inventory.lock().unwrap().add(&item, true);
-                            }
+                        if ui.add(recipe(&atlas, i, None, true)).drag_started() {
+                            task = Some(StorageAction::SelectRecipe(i.index));
                         };
                     }
                 });
             }, None));
         });
+        task
+    }
+}
 
-        if let Some(task) = task {
-            let Some(item) = self.mut_storage()[task].0.take() else {return};
-            let remainder = inventory.lock().unwrap().add(&item, true);
-            if let Some(r) = remainder {self.set(&r, task)}
+impl DrawStorage for AssemblingMachine {
+    fn apply_action(&mut self, action: StorageAction, inventory: &Mutex<PlayerInventory>) {
+        match action {
+            StorageAction::Slot(index, action) => container_slot_action(action, self, index, inventory),
+            StorageAction::SelectRecipe(index) => {
+                if !RECIPES().assembler.all().iter().any(|r| r.index == index) {return};
+                let result = self.select_recipe(index);
+                let mut inventory = inventory.lock().unwrap();
+                for item in result.0 {
+                    let Some(item) = item.0 else {continue};
+                    inventory.add(&item, true);
+                }
+                for item in result.1 {
+                    inventory.add(&item, true);
+                }
+            },
+            StorageAction::Sort => (),
         }
     }
 }
 
-impl DrawStorage for AssemblingMachine {}
-
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 struct Header {
@@ -177,29 +206,21 @@ struct Header {
 impl AsFromBytes for Header {}
 
 impl BytesCoder for AssemblingMachine {
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes[0..Header::size()]);
-        let selected_recipe = if header.selected_recipe_id != u32::MAX {
-            Some(&RECIPES().all[header.selected_recipe_id as usize])
-        } else {
-            None
-        };
-        let active_recipe = if header.active_recipe_id != u32::MAX {
-            Some(RECIPES().all[header.active_recipe_id as usize].start_absolute())
-        } else {
-            None
-        };
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::from_bytes_at(bytes, 0)?;
+        let selected_recipe = RECIPES().by_id(header.selected_recipe_id);
+        let active_recipe = RECIPES().by_id(header.active_recipe_id).map(|recipe| recipe.start_absolute());
         let storage_size = Header::size() + header.storage_len as usize;
-        let storage = <[PossibleItem; TOTAL_LENGTH]>::decode_bytes(&bytes[Header::size()..storage_size]);
+        let storage = <[PossibleItem; TOTAL_LENGTH]>::decode_bytes(slice_bytes(bytes, Header::size()..storage_size)?)?;
         let structure_size = storage_size+header.structure_len as usize;
-        let structure = cast_vec_from_bytes(&bytes[storage_size..structure_size]);
+        let structure = cast_vec_from_bytes(slice_bytes(bytes, storage_size..structure_size)?)?;
 
-        Self {
+        Ok(Self {
             selected_recipe,
             active_recipe,
             storage,
             structure_coordinates: structure,
-        }
+        })
     }
     fn encode_bytes(&self) -> Box<[u8]> {
         let mut bytes = Vec::new();
@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-use crate::bytes::BytesCoder;
+use crate::bytes::{BytesCoder, DecodeError};
 
 #[derive(Debug)]
 pub struct Cowboy {
@@ -22,5 +22,5 @@ impl Default for Cowboy {
 
 impl BytesCoder for Cowboy {
     fn encode_bytes(&self) -> Box<[u8]> { Box::new([]) }
-    fn decode_bytes(_: &[u8]) -> Self { Self::default() }
+    fn decode_bytes(_: &[u8]) -> Result<Self, DecodeError> { Ok(Self::default()) }
 }
\ No newline at end of file
@@ -1,34 +1,37 @@
-use std::time::{Instant, Duration};
+use std::time::Duration;
 
-use crate::bytes::{BytesCoder, AsFromBytes, cast_vec_from_bytes, cast_bytes_from_slice};
-use crate::{world::global_coords::GlobalCoords, direction::Direction, voxels::{chunks::Chunks, block::blocks::BLOCKS}, recipes::{item::PossibleItem, storage::Storage}};
+use crate::bytes::{BytesCoder, AsFromBytes, DecodeError, slice_bytes, cast_vec_from_bytes, cast_bytes_from_slice};
+use crate::{world::{global_coords::GlobalCoords, ticks::duration_ticks}, direction::Direction, voxels::{chunks::Chunks, block::blocks::BLOCKS}, recipes::{item::PossibleItem, storage::Storage}};
 
-use super::multiblock::MultiBlock;
+use super::{multiblock::MultiBlock, machine_status::MachineStatus};
 
 #[derive(Debug)]
 pub struct Drill {
     dir: [i8; 3],
     storage: [PossibleItem; 1],
     structure_coordinates: Vec<GlobalCoords>,
-    start: Instant,
+    /// Ticks since the last mining
+    ticks: u32,
+    no_ore: bool,
 }
 
 
 impl Drill {
     const DURATION: Duration = Duration::new(4, 0);
+    const DURATION_TICKS: u32 = duration_ticks(Self::DURATION);
 
     pub fn new(structure_coordinates: Vec<GlobalCoords>, dir: &Direction) -> Self {Self {
         storage: [PossibleItem::new_none()],
         structure_coordinates,
-        start: Instant::now(),
-        dir: dir.simplify_to_one_greatest(true, false, true)
+        ticks: 0,
+        dir: dir.simplify_to_one_greatest(true, false, true),
+        no_ore: false,
     }}
 
-    pub fn update(&mut self, chunks: *mut Chunks) {
+    pub fn update(&mut self, chunks: &Chunks) {
         let xyz = self.structure_coordinates[0];
         let global = GlobalCoords(xyz.0 - self.dir[0] as i32, xyz.1, xyz.2-self.dir[2] as i32);
-        let chunks = unsafe {chunks.as_mut().expect("Chunks don't exist")};
-        if let Some(storage) = chunks.mut_voxel_data(global).and_then(|vd| vd.additionally.storage()) {
+        if let Some(storage) = chunks.voxel_data(global).and_then(|vd| vd.additionally.storage()) {
             if let Some(item) = self.storage[0].0.take() {
                 if let Some(r_item) = storage.lock().unwrap().add(&item, false) {
                     self.storage[0].try_add_item(&r_item);
@@ -36,20 +39,29 @@ impl Drill {
             }
         }
 
-        if self.start.elapsed() < Self::DURATION {return}
-        self.start = Instant::now();
+        self.ticks += 1;
+        if self.ticks < Self::DURATION_TICKS {return}
+        self.ticks = 0;
         
         
+        self.no_ore = true;
         self.structure_coordinates.iter().for_each(|coord| {
             let ore_coords = GlobalCoords(coord.0, coord.1-1, coord.2);
             let voxel = chunks.voxel_global(ore_coords);
             let Some(voxel) = voxel else {return};
             if let Some(item) = BLOCKS()[voxel.id as usize].ore() {
                 self.storage[0].try_add_item(&item);
+                self.no_ore = false;
             }
         });
     }
 
+    pub fn status(&self) -> MachineStatus {
+        if self.storage[0].0.is_some_and(|item| item.count >= item.stack_size()) {return MachineStatus::OutputFull};
+        if self.no_ore {return MachineStatus::NoOre};
+        MachineStatus::Working
+    }
+
     pub fn rotation_index(&self) -> u32 {
         if self.dir[2] > 0 {return 0};
         if self.dir[0] < 0 {return 3};
@@ -91,20 +103,21 @@ struct Header {
 impl AsFromBytes for Header {}
 
 impl BytesCoder for Drill {
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes[0..Header::size()]);
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::from_bytes_at(bytes, 0)?;
 
         let storage_size = Header::size() + header.storage_len as usize;
-        let storage = <[PossibleItem; 1]>::decode_bytes(&bytes[Header::size()..storage_size]);
+        let storage = <[PossibleItem; 1]>::decode_bytes(slice_bytes(bytes, Header::size()..storage_size)?)?;
         let structure_size = storage_size+header.structure_len as usize;
-        let structure = cast_vec_from_bytes(&bytes[storage_size..structure_size]);
+        let structure = cast_vec_from_bytes(slice_bytes(bytes, storage_size..structure_size)?)?;
 
-        Self {
+        Ok(Self {
             dir: header.direction,
             storage,
             structure_coordinates: structure,
-            start: Instant::now(),
-        }
+            ticks: 0,
+            no_ore: false,
+        })
     }
     fn encode_bytes(&self) -> Box<[u8]> {
         let mut bytes = Vec::new();
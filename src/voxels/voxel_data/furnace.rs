@@ -1,8 +1,8 @@
 use std::sync::{Arc, Mutex};
 
-use crate::{recipes::{recipes::RECIPES, item::{PossibleItem, Item}, recipe::ActiveRecipe, storage::Storage}, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot}, player::inventory::PlayerInventory, engine::texture::TextureAtlas, bytes::{BytesCoder, AsFromBytes}};
+use crate::{recipes::{recipes::RECIPES, item::{PossibleItem, Item}, recipe::ActiveRecipe, storage::Storage}, gui::{draw::Draw, my_widgets::{inventory_slot::inventory_slot, machine_status::machine_status}, slot_action::{SlotAction, container_slot_action}, gui_action::StorageAction}, player::inventory::PlayerInventory, engine::texture::TextureAtlas, bytes::{BytesCoder, AsFromBytes, DecodeError}};
 
-use super::DrawStorage;
+use super::{DrawStorage, machine_status::MachineStatus};
 
 #[derive(Debug)]
 pub struct Furnace {
@@ -20,8 +20,9 @@ impl Furnace {
     }
 
     pub fn update(&mut self) {
-        let active_recipe_take = self.active_recipe.take();
-        if let Some(active_recipe) = &active_recipe_take {
+        let mut active_recipe_take = self.active_recipe.take();
+        if let Some(active_recipe) = &mut active_recipe_take {
+            active_recipe.advance(1);
             let storage = self.mut_storage();
             if active_recipe.is_finished() && storage[1].is_possible_add(&active_recipe.recipe.result) {
                 storage[1].try_add_item(&active_recipe.recipe.result);
@@ -35,6 +36,18 @@ impl Furnace {
             self.active_recipe = self.start_recipe(&recipe);
         }
     }
+
+    pub fn status(&self) -> MachineStatus {
+        match &self.active_recipe {
+            Some(ar) if ar.is_finished() && !self.storage[1].is_possible_add(&ar.recipe.result) => MachineStatus::OutputFull,
+            Some(_) => MachineStatus::Working,
+            None => MachineStatus::NoIngredients,
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.active_recipe.as_ref().map_or(0.0, |ar| if ar.is_finished() {1.0} else {ar.progress()})
+    }
 }
 
 
@@ -61,6 +74,10 @@ impl Storage for Furnace {
     fn is_item_exist(&self, item: &Item) -> bool {
         self.storage[0].contains(item.id()) >= item.count
     }
+
+    fn accepts(&self, index: usize, item: &Item) -> bool {
+        index == 0 && RECIPES().furnace.get_by_ingredient(item.id()).is_some()
+    }
 }
 
 impl Default for Furnace {
@@ -70,42 +87,39 @@ impl Default for Furnace {
 }
 
 impl Draw for Furnace {
-    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>, inventory: Arc<Mutex<PlayerInventory>>) {
-        let mut task: Option<usize> = None;
+    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>) -> Option<StorageAction> {
+        let mut task: Option<StorageAction> = None;
+        ui.add(machine_status(self.status(), self.progress()));
         ui.horizontal(|ui| {
             for (index, item) in self.storage().iter().enumerate() {
-                if ui.add(inventory_slot(&atlas, item)).drag_started() {
-                    task = Some(index);
+                let response = ui.add(inventory_slot(&atlas, item));
+                if let Some(action) = SlotAction::from_response(ui, &response) {
+                    task = Some(StorageAction::Slot(index, action));
                 }
             }
         });
-
-        if let Some(task) = task {
-            let Some(item) = self.mut_storage()[task].0.take() else {return};
-            let remainder = inventory.lock().unwrap().add(&item, true);
-            if let Some(r) = remainder {self.set(&r, task)}
-        }
+        task
     }
 }
 
-impl DrawStorage for Furnace {}
+impl DrawStorage for Furnace {
+    fn apply_action(&mut self, action: StorageAction, inventory: &Mutex<PlayerInventory>) {
+        let StorageAction::Slot(index, action) = action else {return};
+        container_slot_action(action, self, index, inventory);
+    }
+}
 
 impl BytesCoder for Furnace {
-    fn decode_bytes(data: &[u8]) -> Self {
-        let recipe_index = u32::from_bytes(&data[0..u32::size()]);
-        let mut furnace = Self {
-            active_recipe: None,
-            storage: <[PossibleItem; 2]>::decode_bytes(&data[u32::size()..]),
-        };
-        if recipe_index != u32::MAX {
-            let ar = RECIPES().all[recipe_index as usize].start_absolute();
-            furnace.active_recipe = Some(ar);
-        }
-        furnace
+    fn decode_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let recipe_id = u32::from_bytes_at(data, 0)?;
+        Ok(Self {
+            active_recipe: RECIPES().by_id(recipe_id).map(|recipe| recipe.start_absolute()),
+            storage: <[PossibleItem; 2]>::decode_bytes(&data[u32::size()..])?,
+        })
     }
     fn encode_bytes(&self) -> Box<[u8]> {
         let mut bytes = Vec::new();
-        let ri = self.active_recipe.as_ref().map(|ar| ar.recipe.index as u32).unwrap_or(u32::MAX);
+        let ri = self.active_recipe.as_ref().map(|ar| ar.recipe.id).unwrap_or(u32::MAX);
         bytes.extend(ri.as_bytes());
         bytes.extend(self.storage.encode_bytes().as_ref());
         bytes.into()
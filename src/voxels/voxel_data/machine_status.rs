@@ -0,0 +1,35 @@
+use egui::Color32;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MachineStatus {
+    Working,
+    NoIngredients,
+    OutputFull,
+    NoRecipe,
+    /// No ore under a drill
+    NoOre,
+}
+
+
+impl MachineStatus {
+    pub fn text(&self) -> &'static str {
+        match self {
+            Self::Working => "working",
+            Self::NoIngredients => "no ingredients",
+            Self::OutputFull => "output full",
+            Self::NoRecipe => "no recipe",
+            Self::NoOre => "no ore",
+        }
+    }
+
+    pub fn color(&self) -> Color32 {
+        match self {
+            Self::Working => Color32::from_rgb(80, 200, 80),
+            Self::NoIngredients => Color32::from_rgb(230, 190, 40),
+            Self::OutputFull => Color32::from_rgb(220, 60, 60),
+            Self::NoRecipe => Color32::from_rgb(150, 150, 150),
+            Self::NoOre => Color32::from_rgb(160, 90, 40),
+        }
+    }
+}
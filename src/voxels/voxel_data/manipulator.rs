@@ -1,10 +1,12 @@
-use std::time::{Instant, Duration};
-use crate::{world::global_coords::GlobalCoords, direction::Direction, voxels::chunks::Chunks, recipes::item::Item, bytes::{BytesCoder, AsFromBytes}};
+use std::time::Duration;
+use crate::{world::{global_coords::GlobalCoords, ticks::duration_ticks}, direction::Direction, voxels::chunks::Chunks, recipes::item::Item, bytes::{BytesCoder, AsFromBytes, DecodeError}};
 
 #[derive(Debug)]
 pub struct Manipulator {
-    start_time: Option<Instant>,
-    return_time: Option<Instant>,
+    /// Ticks since the item was taken
+    start_ticks: Option<u32>,
+    /// Ticks since the item was put
+    return_ticks: Option<u32>,
     item_id: Option<u32>,
     direction: [i8; 3],
 }
@@ -12,57 +14,60 @@ pub struct Manipulator {
 
 impl Manipulator {
     const SPEED: Duration = Duration::from_millis(300);
+    const HALF_TICKS: u32 = duration_ticks(Self::SPEED).div_ceil(2);
 
     pub fn new(direction: &Direction) -> Self {Self {
-        start_time: None,
-        return_time: None,
+        start_ticks: None,
+        return_ticks: None,
         item_id: None,
         direction: direction.simplify_to_one_greatest(true, false, true),
     }}
 
-    pub fn update(&mut self, coords: GlobalCoords, chunks: *mut Chunks) {
-        let return_time = self.return_time.map_or(true, |rt| rt.elapsed() >= (Self::SPEED/2));
-        if self.item_id.is_none() && self.start_time.is_none() && return_time {
+    pub fn update(&mut self, coords: GlobalCoords, chunks: &Chunks) {
+        self.start_ticks.iter_mut().chain(self.return_ticks.iter_mut()).for_each(|ticks| *ticks += 1);
+        let return_time = self.return_ticks.map_or(true, |ticks| ticks >= Self::HALF_TICKS);
+        if self.item_id.is_none() && self.start_ticks.is_none() && return_time {
             let src_coords = GlobalCoords(coords.0 - self.direction[0] as i32, coords.1, coords.2 - self.direction[2] as i32);
-            let src = unsafe {
-                chunks.as_mut().expect("Chunks don't exist")
-            };
-            let Some(storage) = src.mut_voxel_data(src_coords).and_then(|vd| vd.additionally.storage()) else {return};
+            let Some(storage) = chunks.voxel_data(src_coords).and_then(|vd| vd.additionally.storage()) else {return};
             if let Some(item) = storage.lock().unwrap().take_first_existing(1) {
                 self.item_id = Some(item.0.id());
-                self.start_time = Some(Instant::now());
-                self.return_time = None;
+                self.start_ticks = Some(0);
+                self.return_ticks = None;
             };
         }
         
-        let start_time = self.start_time.map_or(true, |rt| rt.elapsed() >= (Self::SPEED/2));
+        let start_time = self.start_ticks.map_or(true, |ticks| ticks >= Self::HALF_TICKS);
         if self.item_id.is_some() && start_time {
             let dst_coords = GlobalCoords(coords.0 + self.direction[0] as i32, coords.1, coords.2 + self.direction[2] as i32);
-            let dst = unsafe {
-                chunks.as_mut().expect("Chunks don't exist")
-            };
-            let Some(storage) = dst.mut_voxel_data(dst_coords).and_then(|vd| vd.additionally.storage()) else {return};
+            let Some(storage) = chunks.voxel_data(dst_coords).and_then(|vd| vd.additionally.storage()) else {return};
             let result = storage.lock().unwrap().add(&Item::new(self.item_id.unwrap(), 1), false).is_none();
             if result {
                 self.item_id = None;
-                self.start_time = None;
-                self.return_time = Some(Instant::now());
+                self.start_ticks = None;
+                self.return_ticks = Some(0);
             }
         }
     }
 
 
     pub fn animation_progress(&self) -> f32 {
-        if let Some(start_time) = self.start_time {
-            (start_time.elapsed().as_secs_f32() / Self::SPEED.as_secs_f32()).min(0.5)
-        } else if let Some(return_time) = self.return_time {
-            (return_time.elapsed().as_secs_f32() / Self::SPEED.as_secs_f32() + 0.5).min(1.0)
+        let speed = (2*Self::HALF_TICKS) as f32;
+        if let Some(start_ticks) = self.start_ticks {
+            (start_ticks as f32 / speed).min(0.5)
+        } else if let Some(return_ticks) = self.return_ticks {
+            (return_ticks as f32 / speed + 0.5).min(1.0)
         } else {
             0.0
         }
     }
 
 
+    /// Item carried between the storages
+    pub fn item_id(&self) -> Option<u32> {
+        self.item_id
+    }
+
+
     pub fn rotation_index(&self) -> u32 {
         if self.direction[0] < 0 {return 2};
         if self.direction[2] > 0 {return 3};
@@ -80,14 +85,14 @@ struct Header {
 impl AsFromBytes for Header {}
 
 impl BytesCoder for Manipulator {
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes[0..Header::size()]);
-        Self {
-            start_time: None,
-            return_time: None,
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::from_bytes_at(bytes, 0)?;
+        Ok(Self {
+            start_ticks: None,
+            return_ticks: None,
             item_id: if u32::MAX == header.item_id {None} else {Some(header.item_id)},
             direction: header.direction,
-        }
+        })
     }
 
     fn encode_bytes(&self) -> Box<[u8]> {
@@ -1,8 +1,8 @@
 use std::sync::{Arc, Mutex, Weak};
-use crate::{direction::Direction, recipes::storage::Storage, world::global_coords::GlobalCoords, gui::draw::Draw, bytes::{BytesCoder, AsFromBytes}};
-use self::{voxel_box::VoxelBox, furnace::Furnace, drill::Drill, cowboy::Cowboy, assembling_machine::AssemblingMachine, transport_belt::TransportBelt, manipulator::Manipulator, multiblock::MultiBlock};
+use crate::{direction::Direction, recipes::storage::Storage, world::global_coords::GlobalCoords, gui::{draw::Draw, gui_action::StorageAction}, player::inventory::PlayerInventory, bytes::{BytesCoder, AsFromBytes, DecodeError}};
+use self::{machine_status::MachineStatus, voxel_box::VoxelBox, furnace::Furnace, drill::Drill, cowboy::Cowboy, assembling_machine::AssemblingMachine, transport_belt::TransportBelt, manipulator::Manipulator, multiblock::MultiBlock};
 
-use super::chunks::Chunks;
+use super::{chunks::Chunks, block::{blocks::BLOCKS, block_behaviour::BlockBehaviour}};
 pub mod voxel_box;
 pub mod furnace;
 pub mod multiblock;
@@ -11,8 +11,12 @@ pub mod cowboy;
 pub mod manipulator;
 pub mod assembling_machine;
 pub mod transport_belt;
+pub mod machine_status;
 
-pub trait DrawStorage: Draw + Storage {}
+pub trait DrawStorage: Draw + Storage {
+    /// Applies the action picked in the window of the storage
+    fn apply_action(&mut self, action: StorageAction, inventory: &Mutex<PlayerInventory>);
+}
 
 
 #[derive(Debug)]
@@ -24,8 +28,8 @@ pub struct VoxelData {
 }
 
 impl VoxelData {
-    pub fn update(&self, chunks: *mut Chunks) {
-        if self.id == 1 {return};
+    pub fn update(&self, chunks: &Chunks) {
+        if BLOCKS()[self.id as usize].behaviour() == BlockBehaviour::MultiBlockPart {return};
         self.additionally.update(self.global_coords, chunks);
     }
 
@@ -55,20 +59,20 @@ pub enum VoxelAdditionalData {
 
 impl VoxelAdditionalData {
     pub fn new_multiblock(id: u32, direction: &Direction, structure_coordinates: Vec<GlobalCoords>) -> Self {
-        match id {
-            15 => Self::Drill(Arc::new(Mutex::new(Drill::new(structure_coordinates, direction)))),
-            16 => Self::AssemblingMachine(Arc::new(Mutex::new(AssemblingMachine::new(structure_coordinates)))),
+        match BLOCKS()[id as usize].behaviour() {
+            BlockBehaviour::Drill => Self::Drill(Arc::new(Mutex::new(Drill::new(structure_coordinates, direction)))),
+            BlockBehaviour::AssemblingMachine => Self::AssemblingMachine(Arc::new(Mutex::new(AssemblingMachine::new(structure_coordinates)))),
             _ => Self::Empty,
         }
     }
 
     pub fn new(id: u32, direction: &Direction) -> Self {
-        match id {
-            9 => Self::Manipulator(Box::new(Mutex::new(Manipulator::new(direction)))),
-            12 => Self::Cowboy(Box::new(Mutex::new(Cowboy::new()))),
-            13 => Self::VoxelBox(Arc::new(Mutex::new(VoxelBox::new()))),
-            14 => Self::Furnace(Arc::new(Mutex::new(Furnace::new()))),
-            17 => Self::TransportBelt(Arc::new(Mutex::new(TransportBelt::new(direction)))),
+        match BLOCKS()[id as usize].behaviour() {
+            BlockBehaviour::Manipulator => Self::Manipulator(Box::new(Mutex::new(Manipulator::new(direction)))),
+            BlockBehaviour::Cowboy => Self::Cowboy(Box::new(Mutex::new(Cowboy::new()))),
+            BlockBehaviour::VoxelBox => Self::VoxelBox(Arc::new(Mutex::new(VoxelBox::new()))),
+            BlockBehaviour::Furnace => Self::Furnace(Arc::new(Mutex::new(Furnace::new()))),
+            BlockBehaviour::TransportBelt => Self::TransportBelt(Arc::new(Mutex::new(TransportBelt::new(direction)))),
             _ => Self::Empty,
         }
     }
@@ -93,7 +97,7 @@ impl VoxelAdditionalData {
     }
 
 
-    pub fn update(&self, coords: GlobalCoords, chunks: *mut Chunks) {
+    pub fn update(&self, coords: GlobalCoords, chunks: &Chunks) {
         match self {
             Self::Manipulator(o) => o.lock().unwrap().update(coords, chunks),
             Self::Drill(d) => d.lock().unwrap().update(chunks),
@@ -126,6 +130,30 @@ impl VoxelAdditionalData {
     }
 
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::MultiBlockPart(_) => "MultiBlockPart",
+            Self::Manipulator(_) => "Manipulator",
+            Self::Cowboy(_) => "Cowboy",
+            Self::VoxelBox(_) => "VoxelBox",
+            Self::Furnace(_) => "Furnace",
+            Self::Drill(_) => "Drill",
+            Self::AssemblingMachine(_) => "AssemblingMachine",
+            Self::TransportBelt(_) => "TransportBelt",
+        }
+    }
+
+    pub fn status(&self) -> Option<MachineStatus> {
+        match self {
+            Self::Furnace(f) => Some(f.lock().unwrap().status()),
+            Self::Drill(d) => Some(d.lock().unwrap().status()),
+            Self::AssemblingMachine(a) => Some(a.lock().unwrap().status()),
+            _ => None,
+        }
+    }
+
+
     pub fn structure_coordinates(&self) -> Option<Vec<GlobalCoords>> {
         match self {
             VoxelAdditionalData::Drill(d) => Some(Vec::from(d.lock().unwrap().structure_coordinates())),
@@ -169,19 +197,21 @@ impl VoxelAdditionalData {
         }
     }
 
-    fn decode_bytes(bytes: &[u8], id: u32) -> Self {
-        match id {
-            1 => {Self::MultiBlockPart(GlobalCoords::from_bytes(bytes))},
-            9 => {Self::Manipulator(Box::new(Mutex::new(Manipulator::decode_bytes(bytes))))},
-            12 => {Self::Cowboy(Box::new(Mutex::new(Cowboy::decode_bytes(bytes))))},
-            13 => {Self::VoxelBox(Arc::new(Mutex::new(VoxelBox::decode_bytes(bytes))))},
-            14 => {Self::Furnace(Arc::new(Mutex::new(Furnace::decode_bytes(bytes))))},
-            17 => {Self::TransportBelt(Arc::new(Mutex::new(TransportBelt::decode_bytes(bytes))))},
-
-            16 => {Self::AssemblingMachine(Arc::new(Mutex::new(AssemblingMachine::decode_bytes(bytes))))},
-            15 => {Self::Drill(Arc::new(Mutex::new(Drill::decode_bytes(bytes))))},
-            _ => unimplemented!(),
-        }
+    fn decode_bytes(bytes: &[u8], id: u32) -> Result<Self, DecodeError> {
+        let block = BLOCKS().get(id as usize).ok_or(DecodeError::InvalidValue { name: "block id", value: id as u64 })?;
+        Ok(match block.behaviour() {
+            BlockBehaviour::MultiBlockPart => {Self::MultiBlockPart(GlobalCoords::from_bytes(bytes)?)},
+            BlockBehaviour::Manipulator => {Self::Manipulator(Box::new(Mutex::new(Manipulator::decode_bytes(bytes)?)))},
+            BlockBehaviour::Cowboy => {Self::Cowboy(Box::new(Mutex::new(Cowboy::decode_bytes(bytes)?)))},
+            BlockBehaviour::VoxelBox => {Self::VoxelBox(Arc::new(Mutex::new(VoxelBox::decode_bytes(bytes)?)))},
+            BlockBehaviour::Furnace => {Self::Furnace(Arc::new(Mutex::new(Furnace::decode_bytes(bytes)?)))},
+            BlockBehaviour::TransportBelt => {Self::TransportBelt(Arc::new(Mutex::new(TransportBelt::decode_bytes(bytes)?)))},
+
+            BlockBehaviour::AssemblingMachine => {Self::AssemblingMachine(Arc::new(Mutex::new(AssemblingMachine::decode_bytes(bytes)?)))},
+            BlockBehaviour::Drill => {Self::Drill(Arc::new(Mutex::new(Drill::decode_bytes(bytes)?)))},
+            _ if bytes.is_empty() => Self::Empty,
+            _ => return Err(DecodeError::InvalidValue { name: "voxel data of block", value: id as u64 }),
+        })
     }
 }
 
@@ -201,12 +231,12 @@ impl BytesCoder for VoxelData {
         bytes.into()
     }
 
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes[0..Header::size()]);
-        Self {
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::from_bytes_at(bytes, 0)?;
+        Ok(Self {
             id: header.id,
             global_coords: header.global_coords,
-            additionally: Arc::new(VoxelAdditionalData::decode_bytes(&bytes[Header::size()..], header.id)),
-        }
+            additionally: Arc::new(VoxelAdditionalData::decode_bytes(&bytes[Header::size()..], header.id)?),
+        })
     }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use itertools::Itertools;
 
-use crate::{recipes::{item::{PossibleItem, Item}, storage::Storage}, direction::Direction, world::global_coords::GlobalCoords, voxels::chunks::Chunks, bytes::{AsFromBytes, BytesCoder}};
+use crate::{recipes::{item::{PossibleItem, Item}, storage::Storage}, direction::Direction, world::global_coords::GlobalCoords, voxels::chunks::Chunks, bytes::{AsFromBytes, BytesCoder, DecodeError, slice_bytes}};
 // TODO: PLEASE UPDATE THIS SHIT
 
 #[derive(Debug, PartialEq, Eq)]
@@ -34,7 +34,7 @@ impl TransportBelt {
         2
     }
 
-    pub fn update(&mut self, coords: GlobalCoords, chunks: *mut Chunks) {
+    pub fn update(&mut self, coords: GlobalCoords, chunks: &Chunks) {
         if self.storage[0].0.is_some() {self.item_progress[0] += 0.1;}
         if self.storage[3].0.is_some() {self.item_progress[3] += 0.1;}
 
@@ -55,11 +55,8 @@ impl TransportBelt {
         });
 
         let dst_coords = GlobalCoords(coords.0+self.direction[0] as i32, coords.1, coords.2+self.direction[2] as i32);
-        let Some(dst) = (unsafe {
-            chunks.as_mut().expect("Chunks don't exist")
-                .mut_voxel_data(dst_coords)
-                .and_then(|voxel_data| voxel_data.additionally.transport_belt())
-        }) else {return};
+        let Some(dst) = chunks.voxel_data(dst_coords)
+            .and_then(|voxel_data| voxel_data.additionally.transport_belt()) else {return};
         
         if self.item_progress[0] > 1.0
          && dst.lock().unwrap().put(&self.storage[0].0.unwrap(), TransportBeltSide::Left).is_none() {
@@ -161,10 +158,10 @@ struct Header {
 impl AsFromBytes for Header {}
 
 impl BytesCoder for TransportBelt {
-    fn decode_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes[0..Header::size()]);
-        let storage = <[PossibleItem; 6]>::decode_bytes(&bytes[Header::size()..]);
-        Self { item_progress: header.progress, direction: header.direction, storage }
+    fn decode_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::from_bytes_at(bytes, 0)?;
+        let storage = <[PossibleItem; 6]>::decode_bytes(slice_bytes(bytes, Header::size()..bytes.len())?)?;
+        Ok(Self { item_progress: header.progress, direction: header.direction, storage })
     }
     fn encode_bytes(&self) -> Box<[u8]> {
         let mut bytes = Vec::new();
@@ -1,6 +1,6 @@
 use std::sync::{Arc, Mutex};
 
-use crate::{recipes::{item::PossibleItem, storage::Storage}, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot}, player::inventory::PlayerInventory, engine::texture::TextureAtlas, bytes::BytesCoder};
+use crate::{recipes::{item::PossibleItem, storage::{Storage, sort_items}}, gui::{draw::Draw, my_widgets::inventory_slot::inventory_slot, slot_action::{SlotAction, container_slot_action}, gui_action::StorageAction}, player::inventory::PlayerInventory, engine::texture::TextureAtlas, bytes::{BytesCoder, DecodeError}};
 
 use super::DrawStorage;
 
@@ -32,35 +32,40 @@ impl Default for VoxelBox {
 }
 
 impl Draw for VoxelBox {
-    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>,inventory: Arc<Mutex<PlayerInventory>>) {
-        let mut task: Option<usize> = None;
+    fn draw(&mut self, ui: &mut egui::Ui, atlas: Arc<TextureAtlas>) -> Option<StorageAction> {
+        let mut task: Option<StorageAction> = None;
         ui.horizontal(|ui| {ui.vertical(|ui| {
+            if ui.button("Sort").clicked() {task = Some(StorageAction::Sort)};
             let len = self.storage().len();
             let count = (len as f32 / 10.0).ceil() as usize;
             for i in 0..count {
                 ui.horizontal(|ui| {
                     for j in 0..(std::cmp::min(10, len - i*10)) {
-                        if ui.add(inventory_slot(&atlas, &self.storage()[i*10 + j])).clicked() {
-                            task = Some(i*10 + j);
+                        let response = ui.add(inventory_slot(&atlas, &self.storage()[i*10 + j]));
+                        if let Some(action) = SlotAction::from_response(ui, &response) {
+                            task = Some(StorageAction::Slot(i*10 + j, action));
                         };
                     }
                 });
             }
         })});
+        task
+    }
+}
 
-        if let Some(task) = task {
-            let Some(item) = self.mut_storage()[task].0.take() else {return};
-            let remainder = inventory.lock().unwrap().add(&item, true);
-            if let Some(r) = remainder {self.set(&r, task)}
+impl DrawStorage for VoxelBox {
+    fn apply_action(&mut self, action: StorageAction, inventory: &Mutex<PlayerInventory>) {
+        match action {
+            StorageAction::Slot(index, action) => container_slot_action(action, self, index, inventory),
+            StorageAction::Sort => sort_items(&mut self.storage),
+            StorageAction::SelectRecipe(_) => (),
         }
     }
 }
 
-impl DrawStorage for VoxelBox {}
-
 impl BytesCoder for VoxelBox {
-    fn decode_bytes(data: &[u8]) -> Self {
-        Self {storage: <[PossibleItem; 30]>::decode_bytes(data)}
+    fn decode_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        Ok(Self {storage: <[PossibleItem; 30]>::decode_bytes(data)?})
     }
     fn encode_bytes(&self) -> Box<[u8]> {
         self.storage.encode_bytes()
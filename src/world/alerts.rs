@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use crate::voxels::voxel_data::machine_status::MachineStatus;
+
+use super::global_coords::GlobalCoords;
+
+/// Number of simulation passes a machine must be stalled before it is reported,
+/// so machines between two recipes are not reported
+const STALLED_PASSES: u32 = 10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Alert {
+    pub coords: GlobalCoords,
+    pub block_id: u32,
+    pub status: MachineStatus,
+}
+
+
+#[derive(Debug, Default)]
+pub struct AlertService {
+    stalled: HashMap<GlobalCoords, (Alert, u32)>,
+    pass: Vec<Alert>,
+}
+
+
+impl AlertService {
+    pub fn new() -> Self {Self::default()}
+
+    /// Reports the status of a machine during the current simulation pass
+    pub fn report(&mut self, coords: GlobalCoords, block_id: u32, status: MachineStatus) {
+        if status == MachineStatus::Working {return};
+        self.pass.push(Alert { coords, block_id, status });
+    }
+
+    /// Machines that were not reported in this pass are no longer stalled
+    pub fn end_pass(&mut self) {
+        let mut stalled = HashMap::with_capacity(self.pass.len());
+        for alert in self.pass.drain(..) {
+            let passes = self.stalled.get(&alert.coords)
+                .filter(|(old, _)| old.status == alert.status)
+                .map_or(1, |(_, passes)| passes + 1);
+            stalled.insert(alert.coords, (alert, passes));
+        }
+        self.stalled = stalled;
+    }
+
+    /// Alerts grouped by status
+    pub fn alerts(&self) -> Vec<Alert> {
+        let mut alerts = self.stalled.values()
+            .filter(|(_, passes)| *passes >= STALLED_PASSES)
+            .map(|(alert, _)| *alert)
+            .collect::<Vec<Alert>>();
+        alerts.sort_by_key(|alert| (alert.status as u8, alert.coords.0, alert.coords.1, alert.coords.2));
+        alerts
+    }
+}
@@ -2,20 +2,20 @@ use crate::{voxels::{chunk::{CHUNK_BIT_SHIFT, CHUNK_SIZE}, chunks::Chunks}, byte
 use super::{global_coords::GlobalCoords, local_coords::LocalCoords};
 
 #[repr(C)]
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct ChunkCoords(pub i32, pub i32, pub i32);
 
 impl AsFromBytes for ChunkCoords {}
 
 impl ChunkCoords {
     #[inline]
-    pub fn nindex(&self, w: i32, d: i32, ox: i32, oz: i32) -> usize {
-        ((self.1*d + self.2-oz)*w + self.0-ox) as usize
+    pub fn nindex(&self, w: i32, d: i32, ox: i32, oy: i32, oz: i32) -> usize {
+        (((self.1-oy)*d + self.2-oz)*w + self.0-ox) as usize
     }
 
     #[inline]
     pub fn chunk_index(&self, chunks: &Chunks) -> usize {
-        ((self.1*chunks.depth + self.2-chunks.oz)*chunks.width + self.0-chunks.ox) as usize
+        (((self.1-chunks.oy)*chunks.depth + self.2-chunks.oz)*chunks.width + self.0-chunks.ox) as usize
     }
 
     #[inline]
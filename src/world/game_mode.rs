@@ -0,0 +1,28 @@
+use std::str::FromStr;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Survival,
+    /// Blocks break instantly
+    Creative,
+}
+
+impl GameMode {
+    pub fn all() -> [GameMode; 2] {[GameMode::Survival, GameMode::Creative]}
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "survival",
+            GameMode::Creative => "creative",
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GameMode::all().into_iter().find(|mode| mode.name() == s).ok_or(())
+    }
+}
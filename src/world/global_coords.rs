@@ -1,7 +1,7 @@
 use crate::bytes::AsFromBytes;
 
 #[repr(C)]
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct GlobalCoords(pub i32, pub i32, pub i32);
 
 impl AsFromBytes for GlobalCoords {}
@@ -0,0 +1,46 @@
+use crate::voxels::chunk::CHUNK_SIZE;
+
+/// Range of new worlds, the chunks below zero are underground
+pub const DEFAULT_HEIGHT_RANGE: HeightRange = HeightRange { bottom: -4, top: 8 };
+/// Region headers store the height in a byte
+pub const MAX_WORLD_HEIGHT: i32 = u8::MAX as i32;
+
+
+/// Vertical range of the world in chunks, the top is exclusive.
+/// The default is the fixed range of the worlds created before it was configurable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightRange {
+    pub bottom: i32,
+    pub top: i32,
+}
+
+impl Default for HeightRange {
+    fn default() -> Self {Self { bottom: 0, top: 256 / CHUNK_SIZE as i32 }}
+}
+
+impl HeightRange {
+    pub fn new(bottom: i32, top: i32) -> Option<Self> {
+        (top > bottom && top - bottom <= MAX_WORLD_HEIGHT).then_some(Self { bottom, top })
+    }
+
+    /// In chunks
+    #[inline]
+    pub fn height(&self) -> i32 {self.top - self.bottom}
+
+    #[inline]
+    pub fn contains(&self, cy: i32) -> bool {cy >= self.bottom && cy < self.top}
+
+    /// The lowest voxel y
+    #[inline]
+    pub fn bottom_y(&self) -> i32 {self.bottom * CHUNK_SIZE as i32}
+
+    /// The voxel y above the highest voxel
+    #[inline]
+    pub fn top_y(&self) -> i32 {self.top * CHUNK_SIZE as i32}
+
+    /// The lowest chunk and the height of the loaded area around the chunk
+    pub fn window(&self, cy: i32, height: i32) -> (i32, i32) {
+        let height = height.clamp(1, self.height());
+        ((cy - height/2).clamp(self.bottom, self.top - height), height)
+    }
+}
@@ -1,6 +1,8 @@
 use itertools::iproduct;
 
-use crate::{light::light::LightSolvers, voxels::{chunks::{Chunks, WORLD_HEIGHT}, voxel::Voxel, chunk::Chunk}, direction::Direction, save_load::{WorldRegions, EncodedChunk}, bytes::BytesCoder};
+use crate::{light::light::LightSolvers, voxels::{chunks::Chunks, chunk::Chunk, voxel::Voxel}, direction::Direction};
+
+use self::{height_range::HeightRange, chunk_coords::ChunkCoords};
 
 use self::global_coords::GlobalCoords;
 
@@ -9,6 +11,10 @@ pub mod chunk_coords;
 pub mod local_coords;
 pub mod coords;
 pub mod sun;
+pub mod alerts;
+pub mod game_mode;
+pub mod ticks;
+pub mod height_range;
 
 
 #[derive(Debug)]
@@ -18,9 +24,9 @@ pub struct World {
 }
 
 impl World {
-    pub fn new(width: i32, height: i32, depth: i32, ox: i32, oy: i32, oz: i32) -> Self {
+    pub fn new(width: i32, height: i32, depth: i32, origin: ChunkCoords, seed: u64, heights: HeightRange) -> Self {
         Self {
-            chunks: Chunks::new(width, height, depth, ox, oy, oz),
+            chunks: Chunks::new(width, height, depth, origin, seed, heights),
             light: LightSolvers::new()
         }
     }
@@ -29,12 +35,20 @@ impl World {
         self.light.solve_rgbs(&mut self.chunks);
     }
 
-    pub fn load_column_of_chunks(&mut self, regions: &mut WorldRegions, cx: i32, cz: i32) {
-        for cy in (0..WORLD_HEIGHT as i32).rev() {
-            let chunk = match regions.chunk((cx, cy, cz).into()) {
-                EncodedChunk::None => Chunk::new(cx, cy, cz),
-                EncodedChunk::Some(b) => Chunk::decode_bytes(b),
-            };
+    /// Coordinates of the missing chunks of the column in the loaded area from the top
+    pub fn missing_chunks(&self, cx: i32, cz: i32) -> Vec<ChunkCoords> {
+        (self.chunks.oy..self.chunks.height_with_offset).rev()
+            .map(|cy| ChunkCoords(cx, cy, cz))
+            .filter(|coords| self.chunks.is_in_area(*coords) && self.chunks.chunk(*coords).is_none())
+            .collect()
+    }
+
+    /// Adds the loaded chunks and lights them, the chunks that left the area
+    /// or were loaded meanwhile are dropped. The chunks go from the top
+    pub fn add_chunks(&mut self, chunks: Vec<Chunk>) {
+        for chunk in chunks {
+            let ChunkCoords(cx, cy, cz) = chunk.xyz;
+            if !self.chunks.is_in_area(chunk.xyz) || self.chunks.chunk(chunk.xyz).is_some() {continue};
             let index = chunk.xyz.chunk_index(&self.chunks);
             self.chunks.chunks[index] = Some(Box::new(chunk));
             self.build_chunk(cx, cy, cz);
@@ -44,6 +58,7 @@ impl World {
 
     pub fn build_chunk(&mut self, cx: i32, cy: i32, cz: i32) {
         self.light.build_sky_light_chunk(&mut self.chunks, cx, cy, cz);
+        self.light.build_sky_light_below(&mut self.chunks, cx, cy, cz);
         self.light.on_chunk_loaded(&mut self.chunks, cx, cy, cz);
     }
 
@@ -58,7 +73,157 @@ impl World {
         self.light.on_block_set(&mut self.chunks, xyz.0, xyz.1, xyz.2, id);
     }
 
-    pub fn voxel(&self, xyz: &GlobalCoords) -> Option<&Voxel> {
+    pub fn voxel(&self, xyz: &GlobalCoords) -> Option<Voxel> {
         self.chunks.voxel_global(*xyz)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::{Arc, RwLock, atomic::{AtomicBool, AtomicUsize, Ordering}}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+    use itertools::iproduct;
+
+    use crate::{bytes::BytesCoder, direction::Direction, graphic::render::snapshot::ChunkSnapshot, recipes::{content::load_content, item::Item, storage::Storage},
+        save_load::{WorldMetadata, WorldRegions}, threads::{scheduler::Scheduler, voxel_data_updater::update_voxels_data},
+        test_utils::{block_id, load_column, world_path}, voxels::{block::block_behaviour::BlockBehaviour, chunk::CHUNK_SIZE, voxel_data::{VoxelAdditionalData, transport_belt::TransportBeltSide}}};
+
+    use super::{World, alerts::AlertService, chunk_coords::ChunkCoords, global_coords::GlobalCoords, height_range::HeightRange};
+
+    /// Items of the box, the manipulator and the belts of the line
+    fn line_items(world: &World, machines: &[(GlobalCoords, u32)]) -> u32 {
+        machines.iter().filter_map(|(coords, _)| world.chunks.voxel_data(*coords)).map(|vd| match &*vd.additionally {
+            VoxelAdditionalData::Manipulator(manipulator) => manipulator.lock().unwrap().item_id().map_or(0, |_| 1),
+            additionally => additionally.storage().map_or(0, |storage| {
+                storage.lock().unwrap().storage().iter().filter_map(|possible_item| possible_item.0).map(|item| item.count).sum()
+            }),
+        }).sum()
+    }
+
+    /// Machine updates and snapshots read the world while the edits and the reloads of the chunks
+    /// under the machines change it, the items on the belts move across the reloaded chunks
+    #[test]
+    fn shared_world_stress() {
+        const ITERATIONS: i32 = 24;
+        load_content(&mut WorldMetadata::default()).unwrap();
+        let (belt, manipulator, voxel_box) = (
+            block_id(BlockBehaviour::TransportBelt), block_id(BlockBehaviour::Manipulator), block_id(BlockBehaviour::VoxelBox));
+        let world = Arc::new(RwLock::new(World::new(3, 2, 3, ChunkCoords(0, 0, 0), 0, HeightRange::new(0, 2).unwrap())));
+        for (cx, cz) in iproduct!(0..3, 0..3) {load_column(&world, cx, cz)};
+
+        // A line of machines across the chunk borders, the box and every other belt hold items
+        let machines: Vec<(GlobalCoords, u32)> = [(10, voxel_box), (11, manipulator)].into_iter()
+            .chain((12..70).map(|x| (x, belt)))
+            .map(|(x, id)| (GlobalCoords(x, 40, 8), id))
+            .collect();
+        let mut world_g = world.write().unwrap();
+        machines.iter().for_each(|(coords, id)| world_g.set_voxel(coords, *id, &Direction::new_x()));
+        let storage = |coords| world_g.chunks.voxel_data(coords).and_then(|vd| vd.additionally.storage()).unwrap();
+        storage(GlobalCoords(10, 40, 8)).lock().unwrap().add(&Item::new(0, 30), false);
+        for x in (12..70).step_by(2) {
+            let belt = world_g.chunks.voxel_data(GlobalCoords(x, 40, 8)).and_then(|vd| vd.additionally.transport_belt()).unwrap();
+            belt.lock().unwrap().put(&Item::new(0, 1), TransportBeltSide::Left);
+        }
+        let items = line_items(&world_g, &machines);
+        drop(world_g);
+
+        let mut scheduler = Scheduler::new();
+        let jobs = scheduler.add_pool("machines", 2);
+        let is_done = Arc::new(AtomicBool::new(false));
+        let passes = Arc::new(AtomicUsize::new(0));
+        // The writers change the world between the machine updates, a stopped updater fails the test instead of hanging it
+        let wait_pass = |passes: &AtomicUsize, updater: &JoinHandle<()>| {
+            let seen = passes.load(Ordering::Acquire);
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while passes.load(Ordering::Acquire) < seen + 2 {
+                assert!(!updater.is_finished(), "the machine updater stopped");
+                assert!(Instant::now() < deadline, "the machine updater did not pass in time");
+                thread::yield_now();
+            }
+        };
+        let updater = Arc::new(thread::spawn({let (world, is_done, passes) = (world.clone(), is_done.clone(), passes.clone()); move || {
+            let mut alerts = AlertService::new();
+            while !is_done.load(Ordering::Acquire) {
+                update_voxels_data(&world, &mut alerts, Some(&jobs));
+                passes.fetch_add(1, Ordering::Release);
+            }
+        }}));
+        let snapshots = thread::spawn({let (world, is_done) = (world.clone(), is_done.clone()); move || {
+            while !is_done.load(Ordering::Acquire) {
+                let world = world.read().unwrap();
+                for chunk in world.chunks.chunks.iter().flatten() {
+                    let snapshot = ChunkSnapshot::new(&world.chunks, chunk);
+                    for (y, z, x) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE).step_by(97) {
+                        let local = (x, y, z).into();
+                        assert_eq!(snapshot.local_id(local), chunk.voxel(local).id);
+                    }
+                }
+            }
+        }});
+        let writers = [
+            thread::spawn({let (world, passes, updater) = (world.clone(), passes.clone(), updater.clone()); move || {
+                for i in 0..ITERATIONS {
+                    let coords = GlobalCoords(5 + i*7 % 80, 40, 9);
+                    world.write().unwrap().set_voxel(&coords, belt, &Direction::new_x());
+                    wait_pass(&passes, &updater);
+                    world.write().unwrap().break_voxel(&coords);
+                }
+            }}),
+            thread::spawn({let (world, passes, updater) = (world.clone(), passes.clone(), updater.clone()); move || {
+                let path = world_path("shared_world_stress");
+                let heights = world.read().unwrap().chunks.heights;
+                for i in 0..ITERATIONS {
+                    // The column leaves the area and is written to its region like the save loop does
+                    let cx = i % 3;
+                    let mut regions = WorldRegions::new(&path);
+                    regions.set_heights(heights);
+                    let mut world_g = world.write().unwrap();
+                    for cy in 0..2 {
+                        let index = ChunkCoords(cx, cy, 0).chunk_index(&world_g.chunks);
+                        let chunk = world_g.chunks.chunks[index].take().unwrap();
+                        regions.save_chunk(chunk.xyz, chunk.encode_bytes());
+                    }
+                    drop(world_g);
+                    regions.save_all_regions();
+                    wait_pass(&passes, &updater);
+
+                    let mut regions = WorldRegions::new(&path);
+                    regions.set_heights(heights);
+                    let chunks = (0..2).map(|cy| regions.load_chunk(ChunkCoords(cx, cy, 0), 0)).collect();
+                    world.write().unwrap().add_chunks(chunks);
+                    wait_pass(&passes, &updater);
+                }
+                fs::remove_dir_all(&path).unwrap();
+            }}),
+        ];
+        // The readers are stopped before a failed writer fails the test
+        let results: Vec<_> = writers.into_iter().map(|writer| writer.join()).collect();
+        is_done.store(true, Ordering::Release);
+        snapshots.join().unwrap();
+        Arc::into_inner(updater).expect("The writers are done").join().unwrap();
+        results.into_iter().for_each(|result| result.unwrap());
+        scheduler.shutdown(Duration::from_secs(1));
+
+        let world = world.read().unwrap();
+        assert!(world.chunks.chunks.iter().all(|chunk| chunk.is_some()));
+        for (coords, id) in machines.iter() {
+            assert_eq!(world.chunks.voxel_data(*coords).map(|vd| vd.id), Some(*id));
+        }
+        assert_eq!(line_items(&world, &machines), items, "the reloads keep every item of the line");
+        let last = world.chunks.voxel_data(GlobalCoords(69, 40, 8)).and_then(|vd| vd.additionally.transport_belt()).unwrap();
+        assert!(last.lock().unwrap().storage().iter().any(|possible_item| possible_item.0.is_some()), "the items reach the end of the line");
+    }
+
+    /// The sun comes only from the world top, a loaded area below it is lit when it moves up
+    #[test]
+    fn sun_from_world_top() {
+        let world = RwLock::new(World::new(1, 1, 1, ChunkCoords(0, 0, 0), 0, HeightRange::new(0, 2).unwrap()));
+        load_column(&world, 0, 0);
+        let top_sun = |world: &RwLock<World>| world.read().unwrap().chunks.chunk(ChunkCoords(0, 0, 0)).unwrap()
+            .lightmap.get_sun((5, (CHUNK_SIZE-1) as u8, 5));
+        assert_eq!(top_sun(&world), 0, "the world top is not loaded");
+
+        world.write().unwrap().chunks.resize(1, 2, 1, 0, 0, 0);
+        load_column(&world, 0, 0);
+        assert_eq!(top_sun(&world), 15);
+    }
+}
@@ -42,8 +42,18 @@ impl<const N: usize> Sun<N> {
         sky
     }}
 
+    /// Seconds since the start of the day
+    pub fn time(&self) -> f32 {
+        (self.start.elapsed().as_secs_f32() + self.start_offset) % *self.time_start.last().unwrap() as f32
+    }
+
+    pub fn set_time(&mut self, time: u64) {
+        self.start_offset = time as f32;
+        self.start = Instant::now();
+    }
+
     pub fn sun_sky(&self) -> (Color, Color) {
-        let time = (self.start.elapsed().as_secs_f32() + self.start_offset) % *self.time_start.last().unwrap() as f32;
+        let time = self.time();
         for i in (0..(self.sun.len()-1)).rev() {
             if time >= self.time_start[i] as f32 {
                 let end_progress = self.time_start[i + 1] - self.time_start[i];
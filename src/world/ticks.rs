@@ -0,0 +1,147 @@
+use std::{fmt::Display, sync::{Mutex, Condvar}, time::Duration};
+
+use crate::threads::scheduler::CancellationToken;
+
+/// Longest wait of the main thread for one simulation tick of a replay or a recording
+const TICK_TIMEOUT: Duration = Duration::from_secs(5);
+/// The machines and the crafting only count the ticks, so a replay gives the same world at any frame rate
+pub const TICK_DURATION: Duration = Duration::from_millis(100);
+
+/// Whole ticks the duration takes
+pub const fn duration_ticks(duration: Duration) -> u32 {
+    duration.as_millis().div_ceil(TICK_DURATION.as_millis()) as u32
+}
+
+
+#[derive(Debug)]
+struct TicksState {
+    done: u64,
+    /// The updater stops at this tick, used to record and replay the ticks frame by frame
+    limit: Option<u64>,
+    is_running: bool,
+}
+
+
+/// The updater didn't finish a tick of the replay in time, the replay can't go on in sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickTimeout {
+    pub tick: u64,
+}
+
+impl Display for TickTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "simulation tick {} timed out", self.tick)
+    }
+}
+
+
+/// Simulation ticks done by the voxel data updater
+#[derive(Debug)]
+pub struct SimulationTicks {
+    state: Mutex<TicksState>,
+    condvar: Condvar,
+    /// Waiting for the updater ends when the world is closed
+    token: CancellationToken,
+    timeout: Duration,
+}
+
+
+impl SimulationTicks {
+    pub fn new(limit: Option<u64>, token: CancellationToken) -> Self {
+        Self { state: Mutex::new(TicksState { done: 0, limit, is_running: false }), condvar: Condvar::new(), token, timeout: TICK_TIMEOUT }
+    }
+
+    pub fn done(&self) -> u64 {
+        self.state.lock().unwrap().done
+    }
+
+    /// The updater starts the next tick if it may, the tick must be finished
+    pub fn start_tick(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.is_running = state.limit.is_none_or(|limit| state.done < limit);
+        state.is_running
+    }
+
+    pub fn finish_tick(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.done += 1;
+        state.is_running = false;
+        self.condvar.notify_all();
+    }
+
+    /// Stops the updater between the ticks and returns the done ticks,
+    /// a recorded frame applies its input at these ticks like the replay does
+    pub fn hold(&self) -> Result<u64, TickTimeout> {
+        let mut state = self.state.lock().unwrap();
+        while state.is_running && !self.token.is_cancelled() {
+            let (new_state, timeout) = self.condvar.wait_timeout(state, self.timeout).unwrap();
+            state = new_state;
+            if timeout.timed_out() && state.is_running {
+                return Err(TickTimeout { tick: state.done + 1 });
+            }
+        }
+        state.limit = Some(state.done);
+        Ok(state.done)
+    }
+
+    /// Lets the updater run to the tick and waits until it is done
+    pub fn run_until(&self, ticks: u64) -> Result<(), TickTimeout> {
+        let mut state = self.state.lock().unwrap();
+        state.limit = Some(ticks);
+        while state.done < ticks && !self.token.is_cancelled() {
+            let (new_state, timeout) = self.condvar.wait_timeout(state, self.timeout).unwrap();
+            state = new_state;
+            if timeout.timed_out() && state.done < ticks {
+                return Err(TickTimeout { tick: state.done + 1 });
+            }
+        }
+        Ok(())
+    }
+
+    /// The updater runs freely again
+    pub fn release(&self) {
+        self.state.lock().unwrap().limit = None;
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use crate::threads::scheduler::CancellationToken;
+
+    use super::{SimulationTicks, TickTimeout};
+
+    #[test]
+    fn hold_waits_for_running_tick() {
+        let ticks = Arc::new(SimulationTicks::new(None, CancellationToken::new()));
+        assert!(ticks.start_tick());
+        let updater = thread::spawn({let ticks = ticks.clone(); move || {
+            thread::sleep(Duration::from_millis(20));
+            ticks.finish_tick();
+        }});
+        // The frame sees the tick the updater was running, no tick is done while it is held
+        assert_eq!(ticks.hold(), Ok(1));
+        updater.join().unwrap();
+        assert!(!ticks.start_tick());
+        assert_eq!(ticks.done(), 1);
+
+        ticks.release();
+        assert!(ticks.start_tick());
+        ticks.finish_tick();
+        assert_eq!(ticks.run_until(2), Ok(()));
+    }
+
+    #[test]
+    fn hold_times_out() {
+        let mut ticks = SimulationTicks::new(None, CancellationToken::new());
+        ticks.timeout = Duration::from_millis(20);
+        // The updater never finishes the tick, the recording can't hold it
+        assert!(ticks.start_tick());
+        assert_eq!(ticks.hold(), Err(TickTimeout { tick: 1 }));
+        // The updater isn't limited by the failed hold
+        ticks.finish_tick();
+        assert!(ticks.start_tick());
+    }
+}